@@ -0,0 +1,63 @@
+//! Renders a typical query many times and reports the time per query.
+//!
+//! Compare the default build against the `buffer-reuse` feature:
+//!
+//! ```text
+//! cargo bench --bench render
+//! cargo bench --bench render --features buffer-reuse
+//! ```
+//!
+//! Only rendering is measured; building the clauses allocates the same
+//! either way. Timings vary from run to run, so compare the best rounds
+//! of several runs on the same machine.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use composable_query_builder2::{OrderDir, QueryError, Select};
+
+const QUERIES: u32 = 100_000;
+const ROUNDS: u32 = 7;
+
+fn query(id: i64) -> Result<Select, QueryError> {
+    let recent = Select::from("orders")
+        .select("user_id")
+        .where_("created_at > now() - interval '7 days'")?;
+    Ok(Select::from("users u")
+        .select("u.id")
+        .select("u.name")
+        .select("u.email")
+        .left_join("profiles p on p.user_id = u.id")?
+        .where_(("u.org_id = ?", id))?
+        .where_("u.deleted_at is null")?
+        .where_in_subquery("u.id", recent)
+        .order_by("u.name", OrderDir::Asc)
+        .limit(50))
+}
+
+fn main() -> Result<(), QueryError> {
+    // Only the rendering is timed, not building the Select. The fastest
+    // round is reported, as the least disturbed by the rest of the machine.
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let mut elapsed = Duration::ZERO;
+        for id in 0..QUERIES {
+            let select = query(id.into())?;
+            let started = Instant::now();
+            black_box(select.into_builder());
+            elapsed += started.elapsed();
+        }
+        best = best.min(elapsed);
+    }
+
+    println!(
+        "into_builder: {:.0} ns/query, best of {ROUNDS} rounds of {QUERIES} (buffer-reuse {})",
+        best.as_nanos() as f64 / QUERIES as f64,
+        if cfg!(feature = "buffer-reuse") {
+            "on"
+        } else {
+            "off"
+        },
+    );
+    Ok(())
+}
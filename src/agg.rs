@@ -0,0 +1,80 @@
+//! Aggregate helpers for the select list.
+//!
+//! The conditions accept the same forms as [Select::where_](crate::Select::where_),
+//! and their values are numbered along with the rest of the query.
+//!
+//! Example:
+//! ```
+//! use composable_query_builder2::{agg, Select};
+//! let q = Select::from("orders")
+//!     .select(agg::count_filter(("status = ?", 2))?.alias("shipped"))
+//!     .where_(("user_id = ?", 10))?;
+//! assert_eq!(
+//!     "select count(*) filter (where status = $1) as shipped from orders where user_id = $2",
+//!     q.into_builder().sql().trim()
+//! );
+//! # Ok::<(), composable_query_builder2::QueryError>(())
+//! ```
+
+use crate::error::{QResult, QueryError};
+use crate::r#where::{IntoWhere, Where};
+use crate::select::SelectExpr;
+
+/// Renders `aggregate filter (where cond)`.
+pub fn filter<T, E>(aggregate: impl Into<String>, cond: T) -> QResult<SelectExpr>
+where
+    T: TryInto<Where, Error = E>,
+    QueryError: From<E>,
+{
+    let mut expr = aggregate.into();
+    let mut values = vec![];
+    expr.push_str(" filter (where ");
+    cond.try_into()?.into_where(&mut expr, &mut values)?;
+    expr.push(')');
+    Ok(SelectExpr::new(expr, values))
+}
+
+/// Renders `count(*) filter (where cond)`.
+pub fn count_filter<T, E>(cond: T) -> QResult<SelectExpr>
+where
+    T: TryInto<Where, Error = E>,
+    QueryError: From<E>,
+{
+    filter("count(*)", cond)
+}
+
+/// Renders `sum(col) filter (where cond)`.
+pub fn sum_filter<T, E>(col: impl Into<String>, cond: T) -> QResult<SelectExpr>
+where
+    T: TryInto<Where, Error = E>,
+    QueryError: From<E>,
+{
+    filter(format!("sum({})", col.into()), cond)
+}
+
+/// Renders `avg(col) filter (where cond)`.
+pub fn avg_filter<T, E>(col: impl Into<String>, cond: T) -> QResult<SelectExpr>
+where
+    T: TryInto<Where, Error = E>,
+    QueryError: From<E>,
+{
+    filter(format!("avg({})", col.into()), cond)
+}
+
+/// Renders `min(col) filter (where cond)`.
+pub fn min_filter<T, E>(col: impl Into<String>, cond: T) -> QResult<SelectExpr>
+where
+    T: TryInto<Where, Error = E>,
+    QueryError: From<E>,
+{
+    filter(format!("min({})", col.into()), cond)
+}
+
+/// Renders `max(col) filter (where cond)`.
+pub fn max_filter<T, E>(col: impl Into<String>, cond: T) -> QResult<SelectExpr>
+where
+    T: TryInto<Where, Error = E>,
+    QueryError: From<E>,
+{
+    filter(format!("max({})", col.into()), cond)
+}
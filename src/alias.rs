@@ -0,0 +1,54 @@
+use std::collections::hash_map::RandomState;
+use std::fmt::Write;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Generated aliases are rendered as this prefix, a unique number and
+/// `__`, then renumbered when the statement is rendered.
+const PREFIX: &str = "__cqb_alias_";
+
+static NEXT_ALIAS: AtomicU64 = AtomicU64::new(0);
+
+/// A unique placeholder alias. Unique across every query, and with a
+/// random start across processes, so independently built or stored
+/// subqueries can be composed without collisions.
+///
+/// The placeholder is a valid identifier, but [renumber] replaces it with
+/// `t1`, `t2`, ... in order of appearance when the statement is rendered,
+/// so the same query always renders the same SQL.
+pub(crate) fn next_alias() -> String {
+    static START: OnceLock<u64> = OnceLock::new();
+    let start = START.get_or_init(|| RandomState::new().hash_one(0u8));
+    let n = start.wrapping_add(NEXT_ALIAS.fetch_add(1, Ordering::Relaxed));
+    format!("{PREFIX}{n:x}__")
+}
+
+/// Replaces the placeholder aliases in `q[start..]` with `t1`, `t2`, ...
+/// numbered by first appearance.
+pub(crate) fn renumber(q: &mut String, start: usize) {
+    if !q[start..].contains(PREFIX) {
+        return;
+    }
+    let mut out = String::with_capacity(q.len() - start);
+    let mut seen: Vec<&str> = vec![];
+    let mut rest = &q[start..];
+    while let Some(i) = rest.find(PREFIX) {
+        out.push_str(&rest[..i]);
+        let len = rest[i + PREFIX.len()..]
+            .find("__")
+            .map_or(rest.len() - i, |end| PREFIX.len() + end + 2);
+        let alias = &rest[i..i + len];
+        let n = match seen.iter().position(|seen| *seen == alias) {
+            Some(n) => n + 1,
+            None => {
+                seen.push(alias);
+                seen.len()
+            }
+        };
+        let _ = write!(out, "t{n}");
+        rest = &rest[i + len..];
+    }
+    out.push_str(rest);
+    q.replace_range(start.., &out);
+}
@@ -0,0 +1,53 @@
+use crate::error::{QResult, QueryError};
+use crate::Select;
+use std::collections::HashSet;
+
+/// The set of query fingerprints which may be run.
+///
+/// Useful when end users can shape queries, e.g. through a filter DSL:
+/// approve the fingerprints of the queries the application is meant to
+/// produce, and anything else is refused before it reaches the database.
+/// Fingerprints ignore bound values, see [Select::fingerprint].
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{FingerprintAllowList, Select};
+/// let by_id = |id: i64| Select::from("users").where_(("id = ?", id));
+/// let allowed = FingerprintAllowList::new().allow(&by_id(0)?);
+///
+/// assert!(allowed.check(&by_id(42)?).is_ok());
+/// assert!(allowed.check(&Select::from("users")).is_err());
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FingerprintAllowList {
+    fingerprints: HashSet<u64>,
+}
+
+impl FingerprintAllowList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows queries with the same shape as `select`.
+    pub fn allow(self, select: &Select) -> Self {
+        self.allow_fingerprint(select.fingerprint())
+    }
+
+    /// Allows a fingerprint recorded earlier, e.g. loaded from config.
+    pub fn allow_fingerprint(mut self, fingerprint: u64) -> Self {
+        self.fingerprints.insert(fingerprint);
+        self
+    }
+
+    /// Returns [QueryError::NotAllowed] unless the query's fingerprint is in
+    /// the list.
+    pub fn check(&self, select: &Select) -> QResult<()> {
+        let fingerprint = select.fingerprint();
+        if self.fingerprints.contains(&fingerprint) {
+            Ok(())
+        } else {
+            Err(QueryError::NotAllowed(fingerprint))
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Array subscript helpers, binding the indexes as values.
+//!
+//! Each returns a [Where], so it can be passed to
+//! [Select::select_expr](crate::Select::select_expr) or
+//! [Select::order_by_expr](crate::Select::order_by_expr), or inlined into a
+//! condition with a `?`.
+//!
+//! Example:
+//! ```
+//! use composable_query_builder2::{array, Select};
+//! let q = Select::from("posts")
+//!     .select_expr(array::slice("tags", 1, 3)?)?
+//!     .where_(("? = ?", array::index("tags", 1)?, "rust"))?;
+//! assert_eq!(
+//!     "select tags[$1:$2] from posts where tags[$3] = $4",
+//!     q.into_builder().sql().trim()
+//! );
+//! # Ok::<(), composable_query_builder2::QueryError>(())
+//! ```
+
+use crate::bool_kind::BoolKind;
+use crate::error::QResult;
+use crate::r#where::Where;
+use crate::util::validate_column;
+
+/// Renders `col[?]`. Postgres arrays start at 1.
+pub fn index(col: &str, index: i32) -> QResult<Where> {
+    validate_column(col)?;
+    Ok(Where::Simple {
+        expr: format!("{col}[?]"),
+        values: vec![index.into()],
+        kind: BoolKind::And,
+    })
+}
+
+/// Renders `col[?:?]`, the elements from `from` to `to` inclusive.
+pub fn slice(col: &str, from: i32, to: i32) -> QResult<Where> {
+    validate_column(col)?;
+    Ok(Where::Simple {
+        expr: format!("{col}[?:?]"),
+        values: vec![from.into(), to.into()],
+        kind: BoolKind::And,
+    })
+}
@@ -0,0 +1,33 @@
+//! Thread-local render buffers, kept between queries with the
+//! `buffer-reuse` feature.
+//!
+//! Only the rendered SQL and its bind list are reused. Building a
+//! [crate::Select] allocates its clauses as usual.
+
+use std::cell::RefCell;
+
+use crate::SQLValue;
+
+/// Buffers that grew past this are dropped instead of kept, so one huge
+/// statement doesn't pin its memory to the thread.
+const MAX_KEPT_SQL: usize = 64 * 1024;
+const MAX_KEPT_VALUES: usize = 1024;
+
+thread_local! {
+    static BUFFERS: RefCell<(String, Vec<SQLValue>)> =
+        const { RefCell::new((String::new(), Vec::new())) };
+}
+
+/// Runs `f` with this thread's buffers, emptied. A call nested in `f`
+/// finds them taken and starts from fresh ones.
+pub(crate) fn with_buffers<R>(f: impl FnOnce(&mut String, &mut Vec<SQLValue>) -> R) -> R {
+    let (mut sql, mut values) = BUFFERS.with(|b| std::mem::take(&mut *b.borrow_mut()));
+    sql.clear();
+    values.clear();
+    let out = f(&mut sql, &mut values);
+    if sql.capacity() <= MAX_KEPT_SQL && values.capacity() <= MAX_KEPT_VALUES {
+        values.clear();
+        BUFFERS.with(|b| *b.borrow_mut() = (sql, values));
+    }
+    out
+}
@@ -0,0 +1,152 @@
+use crate::error::QResult;
+use crate::util;
+use crate::{ExecPolicy, Select};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Storage for cached query results.
+///
+/// Values are the JSON encoded rows, and keys hold the whole query, see
+/// [CachedSelect::key]. Implement this to back the cache with
+/// something shared between processes, such as redis.
+pub trait QueryCache {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&self, key: &str, value: String, ttl: Duration);
+}
+
+/// An in-process [QueryCache] holding up to a fixed number of entries.
+/// Expired entries are dropped when read. When full, storing drops the
+/// expired entries, then the ones closest to expiring.
+#[derive(Debug)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, (Instant, String)>>,
+    capacity: usize,
+}
+
+impl MemoryCache {
+    /// The capacity used by [MemoryCache::new].
+    pub const DEFAULT_CAPACITY: usize = 10_000;
+
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    /// Holds at most `capacity` entries. A capacity of 0 stores nothing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            capacity,
+        }
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QueryCache for MemoryCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((expires, value)) if *expires > Instant::now() => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn set(&self, key: &str, value: String, ttl: Duration) {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(key) && entries.len() >= self.capacity {
+            entries.retain(|_, (expires, _)| *expires > now);
+            while entries.len() >= self.capacity {
+                let Some(soonest) = entries
+                    .iter()
+                    .min_by_key(|(_, (expires, _))| *expires)
+                    .map(|(key, _)| key.clone())
+                else {
+                    return;
+                };
+                entries.remove(&soonest);
+            }
+        }
+        entries.insert(key.to_string(), (now + ttl, value));
+    }
+}
+
+/// A [Select] whose results are read through a [QueryCache].
+///
+/// Created with [Select::cached].
+#[derive(Debug, Clone)]
+pub struct CachedSelect {
+    select: Select,
+    ttl: Duration,
+}
+
+impl CachedSelect {
+    pub fn new(select: Select, ttl: Duration) -> Self {
+        Self { select, ttl }
+    }
+
+    /// The cache key: the query [fingerprint](Select::fingerprint)
+    /// followed by the rendered SQL, bind values and row level security
+    /// settings in full, so two queries only share an entry when they are
+    /// the same query rather than when their hashes collide.
+    pub fn key(&self) -> QResult<String> {
+        self.select.check_policy()?;
+        let (sql, values) = self.select.clone().parts();
+        // Row level security settings change which rows come back.
+        let context = self.select.rls_statements();
+        Ok(format!(
+            "{:016x}:{}",
+            util::fnv1a(sql.as_bytes()),
+            serde_json::to_string(&(sql, values, context))?
+        ))
+    }
+
+    /// Returns the cached rows when present, otherwise runs the query and
+    /// stores the rows for the configured ttl.
+    pub async fn fetch_all_cached<T, C>(self, pool: &PgPool, cache: &C) -> QResult<Vec<T>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Serialize + DeserializeOwned + Send + Unpin,
+        C: QueryCache,
+    {
+        self.fetch_all_cached_with(pool, cache, &ExecPolicy::default())
+            .await
+    }
+
+    /// Same as [CachedSelect::fetch_all_cached], running the query under
+    /// `policy`. The policy's checks, such as its allow list, apply to
+    /// cached rows too, so the cache can't serve a query the policy refuses.
+    pub async fn fetch_all_cached_with<T, C>(
+        self,
+        pool: &PgPool,
+        cache: &C,
+        policy: &ExecPolicy,
+    ) -> QResult<Vec<T>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Serialize + DeserializeOwned + Send + Unpin,
+        C: QueryCache,
+    {
+        policy.check(&self.select)?;
+        let key = self.key()?;
+        if let Some(hit) = cache.get(&key) {
+            return Ok(serde_json::from_str(&hit)?);
+        }
+
+        let rows: Vec<T> = self.select.fetch_all_with(pool, policy).await?;
+        cache.set(&key, serde_json::to_string(&rows)?, self.ttl);
+
+        Ok(rows)
+    }
+}
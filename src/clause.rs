@@ -0,0 +1,71 @@
+use crate::SQLValue;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Where a [RenderClause] is rendered in the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClausePosition {
+    /// Before `select`, e.g. a planner hint comment.
+    Start,
+    /// Appended to the select list.
+    SelectList,
+    /// After the where clause, before `group by`.
+    AfterWhere,
+    /// After everything else.
+    End,
+}
+
+/// A clause rendered by code outside this crate, for vendor specific syntax
+/// the builder doesn't know about. Add one with [crate::Select::push_clause].
+///
+/// Placeholders are `?`, as everywhere else, and the values pushed must
+/// match them in number and order.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{ClausePosition, RenderClause, SQLValue, Select};
+///
+/// #[derive(Debug)]
+/// struct TimeBucket(&'static str);
+///
+/// impl RenderClause for TimeBucket {
+///     fn position(&self) -> ClausePosition {
+///         ClausePosition::SelectList
+///     }
+///
+///     fn render(&self, sql: &mut String, values: &mut Vec<SQLValue>) {
+///         sql.push_str("time_bucket(?::interval, time) as bucket");
+///         values.push(self.0.into());
+///     }
+/// }
+///
+/// let q = Select::from("metrics").select("avg(cpu)").push_clause(Box::new(TimeBucket("5 minutes")));
+/// assert_eq!(
+///     "select avg(cpu), time_bucket($1::interval, time) as bucket from metrics",
+///     q.into_builder().sql()
+/// );
+/// ```
+pub trait RenderClause: Debug + Send + Sync {
+    fn position(&self) -> ClausePosition;
+
+    fn render(&self, sql: &mut String, values: &mut Vec<SQLValue>);
+}
+
+/// Renders the clauses at `position`, separated from the rest of the query
+/// by a space.
+pub(crate) fn push_clauses(
+    clauses: &[Arc<dyn RenderClause>],
+    position: ClausePosition,
+    q: &mut String,
+    vals: &mut Vec<SQLValue>,
+) {
+    for clause in clauses.iter().filter(|c| c.position() == position) {
+        if position != ClausePosition::Start {
+            q.push(' ');
+        }
+        clause.render(q, vals);
+        if position == ClausePosition::Start {
+            q.push(' ');
+        }
+    }
+}
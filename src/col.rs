@@ -0,0 +1,106 @@
+use crate::error::{QResult, QueryError};
+use crate::group_by::IntoGroupBy;
+use crate::select::{IntoSelect, SelectExpr};
+use crate::table::quote;
+use std::fmt::{Display, Formatter};
+
+/// A column, optionally qualified with its table, rendered as a quoted
+/// identifier where needed.
+///
+/// Converts into a `String`, so it can be passed anywhere a column name is
+/// accepted, e.g. [crate::Select::order_by] or [crate::Select::where_in].
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{col, Col, OrderDir, Select};
+/// let id = Col::new("users", "id");
+/// let q = Select::from("users")
+///     .select([id.clone(), col("users.displayName")])
+///     .group_by(id.clone())
+///     .order_by(id, OrderDir::Asc);
+/// assert_eq!(
+///     "select users.id, users.\"displayName\" from users group by users.id  order by users.id asc",
+///     q.into_builder().sql().trim()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Col {
+    /// The table, optionally schema qualified as `schema.table`.
+    pub table: Option<String>,
+    pub name: String,
+}
+
+/// Parses `table.column` or `column` into a [Col]. The table may be
+/// schema qualified, `schema.table.column`, and each part is quoted on its
+/// own.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::col;
+/// assert_eq!("crm.\"Users\".\"displayName\"", col("crm.Users.displayName").to_string());
+/// ```
+pub fn col(s: &str) -> Col {
+    match s.rsplit_once('.') {
+        Some((table, name)) => Col::new(table, name),
+        None => Col::unqualified(s),
+    }
+}
+
+impl Col {
+    pub fn new(table: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            table: Some(table.into()),
+            name: name.into(),
+        }
+    }
+
+    pub fn unqualified(name: impl Into<String>) -> Self {
+        Self {
+            table: None,
+            name: name.into(),
+        }
+    }
+
+    /// Checks the column is in `allowed`, written as `table.column` or
+    /// `column`, returning [QueryError::UnknownField] if it isn't.
+    pub fn validate(&self, allowed: &[&str]) -> QResult<&Self> {
+        let name = match &self.table {
+            Some(table) => format!("{}.{}", table, self.name),
+            None => self.name.clone(),
+        };
+        if allowed.contains(&name.as_str()) {
+            Ok(self)
+        } else {
+            Err(QueryError::UnknownField(name))
+        }
+    }
+}
+
+impl Display for Col {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(table) = &self.table {
+            for part in table.split('.') {
+                write!(f, "{}.", quote(part))?;
+            }
+        }
+        write!(f, "{}", quote(&self.name))
+    }
+}
+
+impl From<Col> for String {
+    fn from(col: Col) -> Self {
+        col.to_string()
+    }
+}
+
+impl IntoSelect for Col {
+    fn into_select(self) -> Vec<SelectExpr> {
+        vec![SelectExpr::new(self.to_string(), vec![])]
+    }
+}
+
+impl IntoGroupBy for Col {
+    fn into_group_by(self) -> String {
+        self.to_string()
+    }
+}
@@ -0,0 +1,42 @@
+use crate::error::QResult;
+use crate::{Delete, Insert, SQLValue, Select, Update};
+
+/// A statement usable as a common table expression, see [Select::with].
+pub trait IntoCte {
+    fn into_cte(self) -> (String, Vec<SQLValue>);
+
+    /// Fails if the statement would get around its
+    /// [ColumnPolicy](crate::ColumnPolicy), checked before it is rendered.
+    fn check_policy(&self) -> QResult<()> {
+        Ok(())
+    }
+}
+
+impl IntoCte for Select {
+    fn into_cte(self) -> (String, Vec<SQLValue>) {
+        let (sql, values) = self.render();
+        (sql.trim().to_string(), values)
+    }
+
+    fn check_policy(&self) -> QResult<()> {
+        Select::check_policy(self)
+    }
+}
+
+impl IntoCte for Insert {
+    fn into_cte(self) -> (String, Vec<SQLValue>) {
+        self.parts()
+    }
+}
+
+impl IntoCte for Update {
+    fn into_cte(self) -> (String, Vec<SQLValue>) {
+        self.parts()
+    }
+}
+
+impl IntoCte for Delete {
+    fn into_cte(self) -> (String, Vec<SQLValue>) {
+        self.parts()
+    }
+}
@@ -0,0 +1,113 @@
+use crate::error::{QResult, QueryError};
+use crate::r#where::{push_where_list, Where};
+use crate::util::validate_column;
+use crate::{alias, build_query, located, SQLValue, Select};
+use sqlx::{Postgres, QueryBuilder};
+
+/// Builds a `delete from` statement.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::Delete;
+/// let q = Delete::from("sessions")
+///     .where_(("user_id = ?", 7))?
+///     .returning(["id"]);
+/// assert_eq!(
+///     "delete from sessions where user_id = $1 returning id",
+///     q.into_builder().sql()
+/// );
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Delete {
+    table: String,
+    where_: Vec<Where>,
+    returning: Vec<String>,
+}
+
+impl Delete {
+    pub fn from(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            where_: vec![],
+            returning: vec![],
+        }
+    }
+
+    /// Adds a where condition. Accepts the same forms as
+    /// [Select::where_](crate::Select::where_).
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_<T, E>(mut self, where_: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.where_.push(located(where_.try_into())?);
+        Ok(self)
+    }
+
+    /// Renders `returning col, ...`.
+    pub fn returning<C: Into<String>>(mut self, cols: impl IntoIterator<Item = C>) -> Self {
+        self.returning.extend(cols.into_iter().map(Into::into));
+        self
+    }
+
+    /// Limits the delete to `batch_size` of the matching rows, picked by
+    /// the unique column `key`:
+    /// `delete from t where key in (select key from t where ... limit ?)`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Delete;
+    /// let q = Delete::from("events")
+    ///     .where_(("created_at < now() - ?::interval", "90 days"))?
+    ///     .batch("id", 1000)?;
+    /// assert_eq!(
+    ///     "delete from events where id in (select id from events where created_at < now() - $1::interval  limit $2)",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn batch(self, key: &str, batch_size: u64) -> QResult<Delete> {
+        validate_column(key)?;
+        let batch = self
+            .where_
+            .into_iter()
+            .try_fold(
+                Select::from(self.table.as_str()).select(key),
+                Select::where_,
+            )?
+            .limit(batch_size);
+        Delete {
+            table: self.table,
+            where_: vec![],
+            returning: self.returning,
+        }
+        .where_((format!("{key} in (?)"), batch))
+    }
+
+    pub fn parts(self) -> (String, Vec<SQLValue>) {
+        let mut q = format!("delete from {}", self.table);
+        let mut vals = vec![];
+
+        if !self.where_.is_empty() {
+            let mut where_ = String::new();
+            push_where_list(&self.where_, &mut where_, &mut vals);
+            q.push_str(" where ");
+            q.push_str(where_.trim_end());
+        }
+
+        if !self.returning.is_empty() {
+            q.push_str(" returning ");
+            q.push_str(&self.returning.join(", "));
+        }
+
+        alias::renumber(&mut q, 0);
+        (q, vals)
+    }
+
+    pub fn into_builder<'args>(self) -> QueryBuilder<'args, Postgres> {
+        let (sql, values) = self.parts();
+        build_query(&sql, values, String::new)
+    }
+}
@@ -6,6 +6,80 @@ pub type QResult<T> = Result<T, QueryError>;
 pub enum QueryError {
     #[error("incorrect placeholder count in query: {0} expected {1}")]
     IncorrectPlaceholderCount(String, usize),
+    /// A row, cursor or list of binds with a different number of values
+    /// than the columns, keys or binds it has to match.
+    #[error("expected {expected} values, found {found}")]
+    ValueCountMismatch { expected: usize, found: usize },
+    #[error("bind {index} should be {expected}, found {found}")]
+    BindTypeMismatch {
+        index: usize,
+        expected: String,
+        found: String,
+    },
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("query timed out after {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("unsupported: {0}")]
+    Unsupported(String),
+    #[error("unknown field: {0}")]
+    UnknownField(String),
+    #[error("invalid identifier: {0}")]
+    InvalidIdentifier(String),
+    #[error("column not allowed: {0}")]
+    DisallowedColumn(String),
+    #[error("query fingerprint {0:016x} is not on the allow list")]
+    NotAllowed(u64),
+    #[error("output columns changed from {expected:?} to {found:?}")]
+    IncompatibleColumns {
+        expected: Vec<String>,
+        found: Vec<String>,
+    },
+    #[error("cannot group by select alias `{alias}`, group by `{expr}` instead")]
+    GroupByAlias { alias: String, expr: String },
+    #[error("unknown table: {0}")]
+    UnknownTable(String),
+    #[error("unknown view: {0}")]
+    UnknownView(String),
+    #[error("unknown operator: {0}")]
+    UnknownOperator(String),
+    #[error("unknown sort direction: {0}")]
+    UnknownOrderDir(String),
+    #[cfg(feature = "track-caller")]
+    #[error("{1} (at {0})")]
+    Located(&'static std::panic::Location<'static>, Box<QueryError>),
     #[error("infallible")]
     Infallible(#[from] std::convert::Infallible),
 }
+
+impl QueryError {
+    /// The underlying error, without any [QueryError::Located] wrapping
+    /// added by the `track-caller` feature.
+    pub fn root(&self) -> &QueryError {
+        match self {
+            #[cfg(feature = "track-caller")]
+            QueryError::Located(_, e) => e.root(),
+            e => e,
+        }
+    }
+}
+
+/// Converts a clause error into a [QueryError]. With the `track-caller`
+/// feature the error is wrapped in [QueryError::Located], pointing at the
+/// user's `where_`/`join`/`select` call rather than at this crate.
+#[cfg_attr(feature = "track-caller", track_caller)]
+pub(crate) fn located<T, E>(result: Result<T, E>) -> QResult<T>
+where
+    QueryError: From<E>,
+{
+    #[cfg(feature = "track-caller")]
+    let at = std::panic::Location::caller();
+    result.map_err(|e| {
+        #[cfg(feature = "track-caller")]
+        return QueryError::Located(at, Box::new(e.into()));
+        #[cfg(not(feature = "track-caller"))]
+        e.into()
+    })
+}
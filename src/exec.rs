@@ -0,0 +1,265 @@
+use crate::error::{QResult, QueryError};
+use crate::rls::set_context;
+use crate::{Delete, FingerprintAllowList, Select};
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgPool};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Timeout and retry handling for read queries.
+///
+/// Only transient failures are retried: timeouts, I/O errors, pool timeouts,
+/// serialization failures and deadlocks. The delay between attempts starts at
+/// `backoff` and doubles after each retry.
+///
+/// Example:
+/// ```no_run
+/// # async fn run(pool: sqlx::PgPool) -> composable_query_builder2::QResult<()> {
+/// use composable_query_builder2::{ExecPolicy, Select};
+/// use std::time::Duration;
+///
+/// let policy = ExecPolicy::new()
+///     .timeout(Duration::from_secs(2))
+///     .retries(3)
+///     .backoff(Duration::from_millis(50));
+///
+/// let ids: Vec<(i64,)> = Select::from("users")
+///     .select("id")
+///     .fetch_all_with(&pool, &policy)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExecPolicy {
+    pub timeout: Option<Duration>,
+    pub retries: u32,
+    pub backoff: Duration,
+    /// When set, queries whose fingerprint isn't on the list are refused.
+    pub allow_list: Option<Arc<FingerprintAllowList>>,
+}
+
+impl Default for ExecPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            retries: 0,
+            backoff: Duration::from_millis(100),
+            allow_list: None,
+        }
+    }
+}
+
+impl ExecPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies to each attempt, not the total time spent retrying.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Refuses to run queries not on `allow_list`, returning
+    /// [QueryError::NotAllowed] without touching the database.
+    pub fn allow_list(mut self, allow_list: Arc<FingerprintAllowList>) -> Self {
+        self.allow_list = Some(allow_list);
+        self
+    }
+
+    /// Run before rendering, so the helpers' [Select::into_builder] can't
+    /// panic on a [ColumnPolicy](crate::ColumnPolicy) violation.
+    pub(crate) fn check(&self, select: &Select) -> QResult<()> {
+        select.check_policy()?;
+        match &self.allow_list {
+            Some(allow_list) => allow_list.check(select),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs `f` under this policy, calling it again for each retry.
+    pub async fn run<F, Fut, T>(&self, mut f: F) -> QResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = match self.timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, f()).await {
+                    Ok(result) => result.map_err(QueryError::from),
+                    Err(_) => Err(QueryError::Timeout(timeout)),
+                },
+                None => f().await.map_err(QueryError::from),
+            };
+
+            match result {
+                Err(e) if attempt < self.retries && is_transient(&e) => {
+                    tokio::time::sleep(self.backoff * 2u32.saturating_pow(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+fn is_transient(e: &QueryError) -> bool {
+    match e {
+        QueryError::Timeout(_) => true,
+        QueryError::Database(sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut) => true,
+        QueryError::Database(sqlx::Error::Database(db)) => {
+            // serialization_failure and deadlock_detected
+            matches!(db.code().as_deref(), Some("40001") | Some("40P01"))
+        }
+        _ => false,
+    }
+}
+
+impl Select {
+    pub async fn fetch_all<T>(self, pool: &PgPool) -> QResult<Vec<T>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        self.fetch_all_with(pool, &ExecPolicy::default()).await
+    }
+
+    pub async fn fetch_one<T>(self, pool: &PgPool) -> QResult<T>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        self.fetch_one_with(pool, &ExecPolicy::default()).await
+    }
+
+    pub async fn fetch_optional<T>(self, pool: &PgPool) -> QResult<Option<T>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        self.fetch_optional_with(pool, &ExecPolicy::default()).await
+    }
+
+    pub async fn fetch_all_with<T>(self, pool: &PgPool, policy: &ExecPolicy) -> QResult<Vec<T>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        policy.check(&self)?;
+        policy
+            .run(|| {
+                let context = self.rls_statements();
+                let mut qb = self.clone().into_builder();
+                async move {
+                    if context.is_empty() {
+                        return qb.build_query_as().fetch_all(pool).await;
+                    }
+                    let mut tx = pool.begin().await?;
+                    set_context(&mut tx, context).await?;
+                    let result = qb.build_query_as().fetch_all(&mut *tx).await?;
+                    tx.commit().await?;
+                    Ok(result)
+                }
+            })
+            .await
+    }
+
+    pub async fn fetch_one_with<T>(self, pool: &PgPool, policy: &ExecPolicy) -> QResult<T>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        policy.check(&self)?;
+        policy
+            .run(|| {
+                let context = self.rls_statements();
+                let mut qb = self.clone().into_builder();
+                async move {
+                    if context.is_empty() {
+                        return qb.build_query_as().fetch_one(pool).await;
+                    }
+                    let mut tx = pool.begin().await?;
+                    set_context(&mut tx, context).await?;
+                    let result = qb.build_query_as().fetch_one(&mut *tx).await?;
+                    tx.commit().await?;
+                    Ok(result)
+                }
+            })
+            .await
+    }
+
+    pub async fn fetch_optional_with<T>(
+        self,
+        pool: &PgPool,
+        policy: &ExecPolicy,
+    ) -> QResult<Option<T>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        policy.check(&self)?;
+        policy
+            .run(|| {
+                let context = self.rls_statements();
+                let mut qb = self.clone().into_builder();
+                async move {
+                    if context.is_empty() {
+                        return qb.build_query_as().fetch_optional(pool).await;
+                    }
+                    let mut tx = pool.begin().await?;
+                    set_context(&mut tx, context).await?;
+                    let result = qb.build_query_as().fetch_optional(&mut *tx).await?;
+                    tx.commit().await?;
+                    Ok(result)
+                }
+            })
+            .await
+    }
+}
+
+impl Delete {
+    /// Deletes the matching rows `batch_size` at a time, see [Delete::batch],
+    /// until a batch deletes fewer rows. Each batch is its own statement,
+    /// so locks are held briefly even when purging a large table. Returns
+    /// the total number of rows deleted. A `batch_size` of 0 is refused,
+    /// as no batch would ever delete a row.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # async fn run(pool: sqlx::PgPool) -> composable_query_builder2::QResult<()> {
+    /// use composable_query_builder2::Delete;
+    ///
+    /// let deleted = Delete::from("events")
+    ///     .where_(("created_at < now() - ?::interval", "90 days"))?
+    ///     .delete_in_batches(&pool, "id", 10_000)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn delete_in_batches(
+        self,
+        pool: &PgPool,
+        key: &str,
+        batch_size: u64,
+    ) -> QResult<u64> {
+        if batch_size == 0 {
+            return Err(QueryError::Unsupported("batch size of 0".into()));
+        }
+        let mut total = 0;
+        loop {
+            let mut qb = self.clone().batch(key, batch_size)?.into_builder();
+            let deleted = qb.build().execute(pool).await?.rows_affected();
+            total += deleted;
+            if deleted < batch_size {
+                return Ok(total);
+            }
+        }
+    }
+}
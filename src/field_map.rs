@@ -0,0 +1,47 @@
+use crate::error::{QResult, QueryError};
+use std::collections::HashMap;
+
+/// Translates external field names, such as the camelCase names used by an
+/// API, into trusted column expressions.
+///
+/// Anything not in the map is rejected, so it also serves as the allow-list
+/// for user supplied sort and filter fields.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{FieldMap, OrderDir, Select};
+/// let fields = FieldMap::new()
+///     .field("createdAt", "users.created_at")
+///     .field("name", "lower(users.name)");
+///
+/// let q = Select::from("users").order_by_field("createdAt", OrderDir::Desc, &fields)?;
+/// assert_eq!("select * from users order by users.created_at desc", q.into_builder().sql().trim());
+///
+/// assert!(Select::from("users").order_by_field("password", OrderDir::Asc, &fields).is_err());
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FieldMap {
+    fields: HashMap<String, String>,
+}
+
+impl FieldMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ## Danger: SQL injection
+    ///
+    /// The `column` is used as is. It should never come from user input.
+    pub fn field(mut self, api_name: impl Into<String>, column: impl Into<String>) -> Self {
+        self.fields.insert(api_name.into(), column.into());
+        self
+    }
+
+    pub fn resolve(&self, api_name: &str) -> QResult<&str> {
+        self.fields
+            .get(api_name)
+            .map(String::as_str)
+            .ok_or_else(|| QueryError::UnknownField(api_name.to_string()))
+    }
+}
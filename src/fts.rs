@@ -0,0 +1,107 @@
+//! Full text search helpers.
+//!
+//! [Select::search] binds the search text once, as a `ts_query` row joined
+//! to the query, so ranking with [Select::order_by_ts_rank] or
+//! [Select::select_ts_rank] refers to the same tsquery rather than binding
+//! the text again.
+//!
+//! Example:
+//! ```
+//! use composable_query_builder2::{fts, Select};
+//! let document = fts::weighted(&[("title", 'A'), ("body", 'B')])?;
+//! let q = Select::from("articles")
+//!     .select("id")
+//!     .search(&document, "rust async")?
+//!     .order_by_ts_rank(&document)?;
+//! assert_eq!(
+//!     "select id from articles cross join websearch_to_tsquery($1) as ts_query where setweight(to_tsvector(coalesce(title, '')), 'A') || setweight(to_tsvector(coalesce(body, '')), 'B') @@ ts_query  order by ts_rank_cd(setweight(to_tsvector(coalesce(title, '')), 'A') || setweight(to_tsvector(coalesce(body, '')), 'B'), ts_query) desc ",
+//!     q.into_builder().sql()
+//! );
+//! # Ok::<(), composable_query_builder2::QueryError>(())
+//! ```
+
+use crate::error::{QResult, QueryError};
+use crate::join::{Join, JoinKind};
+use crate::util::validate_column;
+use crate::{located, OrderDir, SQLValue, Select, SelectExpr};
+use itertools::Itertools;
+
+const TS_QUERY_JOIN: &str = "websearch_to_tsquery(?) as ts_query";
+
+/// Renders `setweight(to_tsvector(coalesce(col, '')), 'A')`, the text of
+/// `col` as a tsvector with every lexeme given `weight`, `A` to `D`.
+pub fn setweight(col: &str, weight: char) -> QResult<String> {
+    validate_column(col)?;
+    if !('A'..='D').contains(&weight) {
+        return Err(QueryError::Unsupported(format!(
+            "tsvector weight `{weight}`, expected A to D"
+        )));
+    }
+    Ok(format!(
+        "setweight(to_tsvector(coalesce({col}, '')), '{weight}')"
+    ))
+}
+
+/// Concatenates [setweight] for each column, e.g. to rank matches in a
+/// title above matches in the body.
+pub fn weighted(cols: &[(&str, char)]) -> QResult<String> {
+    Ok(cols
+        .iter()
+        .map(|(col, weight)| setweight(col, *weight))
+        .collect::<QResult<Vec<_>>>()?
+        .into_iter()
+        .join(" || "))
+}
+
+impl Select {
+    /// Keeps the rows whose `document`, a tsvector expression, matches the
+    /// search text in `query`. The text is parsed with
+    /// `websearch_to_tsquery`, so it accepts the syntax of a search box:
+    /// quoted phrases, `or` and `-word`.
+    ///
+    /// ## Danger: SQL injection
+    ///
+    /// The `document` is used as is, see [setweight] to build it.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn search(self, document: &str, query: impl Into<String>) -> QResult<Self> {
+        if self.ts_query_joined() {
+            return located(Err(QueryError::Unsupported(
+                "more than one search per query".to_string(),
+            )));
+        }
+        let query: SQLValue = query.into().into();
+        self.cross_join(Join::Bound(TS_QUERY_JOIN.to_string(), vec![query]))?
+            .where_(format!("{document} @@ ts_query").as_str())
+    }
+
+    /// Orders by `ts_rank_cd(document, ts_query)`, best match first. Needs
+    /// a [Select::search] to rank against.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn order_by_ts_rank(self, document: &str) -> QResult<Self> {
+        let rank = located(self.ts_rank(document))?;
+        Ok(self.order_by(rank, OrderDir::Desc))
+    }
+
+    /// Adds `ts_rank_cd(document, ts_query) as alias` to the select list.
+    /// Needs a [Select::search] to rank against.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_ts_rank(self, document: &str, alias: &str) -> QResult<Self> {
+        let rank = located(self.ts_rank(document))?;
+        Ok(self.select(SelectExpr::new(rank, vec![]).alias(alias)))
+    }
+
+    fn ts_rank(&self, document: &str) -> QResult<String> {
+        if !self.ts_query_joined() {
+            return Err(QueryError::Unsupported(
+                "ranking without a search".to_string(),
+            ));
+        }
+        Ok(format!("ts_rank_cd({document}, ts_query)"))
+    }
+
+    fn ts_query_joined(&self) -> bool {
+        self.join.iter().any(|(kind, join)| {
+            matches!((kind, join), (JoinKind::Cross, Join::Bound(sql, _)) if sql == TS_QUERY_JOIN)
+        })
+    }
+}
@@ -0,0 +1,407 @@
+use crate::error::{QResult, QueryError};
+use crate::r#where::{push_where_list, IntoWhere, Where};
+use crate::util::{fold_identifier, placeholder_count};
+use crate::values::ValuesRow;
+use crate::{alias, build_query, located, SQLValue, ValueTransform};
+use itertools::Itertools;
+use sqlx::{Postgres, QueryBuilder};
+use std::sync::Arc;
+
+/// Builds an `insert into` statement.
+///
+/// Single rows can be built a column at a time with [Insert::value].
+/// Batches set the columns once with [Insert::columns] and then add each
+/// row with [Insert::row].
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::Insert;
+/// let q = Insert::into("users")
+///     .value("email", "a@example.com")
+///     .value("active", true)
+///     .returning(["id"]);
+/// assert_eq!(
+///     "insert into users (email, active) values ($1, $2) returning id",
+///     q.into_builder().sql()
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Insert {
+    table: String,
+    columns: Vec<String>,
+    /// Each cell is an expression with `?` placeholders and its values.
+    rows: Vec<Vec<(String, Vec<SQLValue>)>>,
+    on_conflict: Option<OnConflict>,
+    returning: Vec<String>,
+    transform: Option<Arc<dyn ValueTransform>>,
+}
+
+#[derive(Debug, Clone)]
+struct OnConflict {
+    target: ConflictTarget,
+    /// The predicate of a partial unique index.
+    where_: Vec<Where>,
+    /// Columns to overwrite with the excluded row. Empty means `do nothing`.
+    update: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum ConflictTarget {
+    Columns(Vec<String>),
+    Constraint(String),
+}
+
+impl Insert {
+    pub fn into(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            columns: vec![],
+            rows: vec![],
+            on_conflict: None,
+            returning: vec![],
+            transform: None,
+        }
+    }
+
+    /// Adds a column and its value to a single row insert.
+    pub fn value(self, col: impl Into<String>, value: impl Into<SQLValue>) -> Self {
+        self.push_value(col.into(), "?".to_string(), vec![value.into()])
+    }
+
+    /// Adds a column set to an SQL expression, e.g. `now()`, to a single
+    /// row insert. The expression can't contain placeholders.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Insert;
+    /// let q = Insert::into("posts")
+    ///     .value("title", "Hello World")
+    ///     .value_expr("created_at", "now()")?
+    ///     .value_expr_bound("slug", "lower(?)", "Hello-World")?;
+    /// assert_eq!(
+    ///     "insert into posts (title, created_at, slug) values ($1, now(), lower($2))",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn value_expr(self, col: impl Into<String>, expr: impl Into<String>) -> QResult<Self> {
+        let expr = expr.into();
+        placeholder_count(&expr, 0)?;
+        Ok(self.push_value(col.into(), expr, vec![]))
+    }
+
+    /// Adds a column set to an SQL expression with one `?` for `value`,
+    /// see [Insert::value_expr].
+    pub fn value_expr_bound(
+        self,
+        col: impl Into<String>,
+        expr: impl Into<String>,
+        value: impl IntoWhere,
+    ) -> QResult<Self> {
+        let Where::Simple { expr, values, .. } = (expr.into(), value).try_into()?;
+        Ok(self.push_value(col.into(), expr, values))
+    }
+
+    fn push_value(mut self, col: String, expr: String, values: Vec<SQLValue>) -> Self {
+        self.columns.push(col);
+        if self.rows.is_empty() {
+            self.rows.push(vec![]);
+        }
+        self.rows[0].push((expr, values));
+        self
+    }
+
+    /// Sets the columns for [Insert::row].
+    pub fn columns<C: Into<String>>(mut self, cols: impl IntoIterator<Item = C>) -> Self {
+        self.columns = cols.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Adds a row with one value per column.
+    pub fn row(mut self, row: impl ValuesRow) -> QResult<Self> {
+        let row = row.into_row();
+        if row.len() != self.columns.len() {
+            return Err(QueryError::ValueCountMismatch {
+                expected: self.columns.len(),
+                found: row.len(),
+            });
+        }
+        self.rows.push(
+            row.into_iter()
+                .map(|v| ("?".to_string(), vec![v]))
+                .collect(),
+        );
+        Ok(self)
+    }
+
+    /// Adds each row, see [Insert::row].
+    pub fn rows<R: ValuesRow>(self, rows: impl IntoIterator<Item = R>) -> QResult<Self> {
+        rows.into_iter().try_fold(self, Insert::row)
+    }
+
+    /// Renders `on conflict (cols) do nothing`.
+    pub fn on_conflict_do_nothing<C: Into<String>>(
+        mut self,
+        cols: impl IntoIterator<Item = C>,
+    ) -> Self {
+        self.on_conflict = Some(OnConflict {
+            target: ConflictTarget::Columns(cols.into_iter().map(Into::into).collect()),
+            where_: vec![],
+            update: vec![],
+        });
+        self
+    }
+
+    /// Renders `on conflict (cols) do update set col = excluded.col` for
+    /// each of the `update` columns.
+    pub fn on_conflict_update<C: Into<String>, U: Into<String>>(
+        mut self,
+        cols: impl IntoIterator<Item = C>,
+        update: impl IntoIterator<Item = U>,
+    ) -> Self {
+        self.on_conflict = Some(OnConflict {
+            target: ConflictTarget::Columns(cols.into_iter().map(Into::into).collect()),
+            where_: vec![],
+            update: update.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Renders `on conflict on constraint name do nothing`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Insert;
+    /// let q = Insert::into("users")
+    ///     .value("email", "a@example.com")
+    ///     .on_conflict_constraint("users_email_key");
+    /// assert_eq!(
+    ///     "insert into users (email) values ($1) on conflict on constraint users_email_key do nothing",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    pub fn on_conflict_constraint(mut self, name: impl Into<String>) -> Self {
+        self.on_conflict = Some(OnConflict {
+            target: ConflictTarget::Constraint(name.into()),
+            where_: vec![],
+            update: vec![],
+        });
+        self
+    }
+
+    /// Same as [Insert::on_conflict_update], with the conflict target given
+    /// as a constraint name.
+    pub fn on_conflict_constraint_update<U: Into<String>>(
+        mut self,
+        name: impl Into<String>,
+        update: impl IntoIterator<Item = U>,
+    ) -> Self {
+        self.on_conflict = Some(OnConflict {
+            target: ConflictTarget::Constraint(name.into()),
+            where_: vec![],
+            update: update.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Adds a condition to the conflict target, so it can match a partial
+    /// unique index. Accepts the same forms as
+    /// [Select::where_](crate::Select::where_), and must follow one of the
+    /// column list `on_conflict` methods. Postgres doesn't allow a
+    /// condition with a constraint name, which returns
+    /// [QueryError::Unsupported].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Insert;
+    /// let q = Insert::into("subscriptions")
+    ///     .value("user_id", 7)
+    ///     .value("plan", "pro")
+    ///     .on_conflict_update(["user_id"], ["plan"])
+    ///     .on_conflict_where(("status = ?", "active"))?;
+    /// assert_eq!(
+    ///     "insert into subscriptions (user_id, plan) values ($1, $2) on conflict (user_id) where status = $3 do update set plan = excluded.plan",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn on_conflict_where<T, E>(mut self, where_: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        let where_ = located(where_.try_into())?;
+        let pushed = match &mut self.on_conflict {
+            Some(OnConflict {
+                target: ConflictTarget::Columns(_),
+                where_: list,
+                ..
+            }) => {
+                list.push(where_);
+                Ok(self)
+            }
+            Some(_) => Err(QueryError::Unsupported(
+                "a conflict condition can't be used with a constraint name".to_string(),
+            )),
+            None => Err(QueryError::Unsupported(
+                "a conflict condition needs a column list conflict target".to_string(),
+            )),
+        };
+        located::<_, QueryError>(pushed)
+    }
+
+    /// Inserts `rows`, updating every other column of rows which conflict
+    /// on `key_cols`. The usual statement for keeping a table in sync with
+    /// another source.
+    ///
+    /// An empty batch is refused with [QueryError::Unsupported], as the
+    /// statement would otherwise insert a row of column defaults. Skip the
+    /// statement when there is nothing to sync.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Insert;
+    /// let q = Insert::into("products")
+    ///     .columns(["sku", "name", "price"])
+    ///     .sync_table([("a-1", "Apple", 1.5), ("b-2", "Banana", 0.25)], ["sku"])?;
+    /// assert_eq!(
+    ///     "insert into products (sku, name, price) values ($1, $2, $3), ($4, $5, $6) on conflict (sku) do update set name = excluded.name, price = excluded.price",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn sync_table<R, K>(
+        self,
+        rows: impl IntoIterator<Item = R>,
+        key_cols: impl IntoIterator<Item = K>,
+    ) -> QResult<Self>
+    where
+        R: ValuesRow,
+        K: Into<String>,
+    {
+        let keys = key_cols
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<String>>();
+        if let Some(key) = keys.iter().find(|k| !self.columns.contains(k)) {
+            return Err(QueryError::UnknownField(key.clone()));
+        }
+        let update = self
+            .columns
+            .iter()
+            .filter(|c| !keys.contains(c))
+            .cloned()
+            .collect::<Vec<_>>();
+        let insert = self.rows(rows)?;
+        if insert.rows.is_empty() {
+            return Err(QueryError::Unsupported("sync with no rows".to_string()));
+        }
+        Ok(insert.on_conflict_update(keys, update))
+    }
+
+    /// Renders `returning col, ...`.
+    pub fn returning<C: Into<String>>(mut self, cols: impl IntoIterator<Item = C>) -> Self {
+        self.returning.extend(cols.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds `(xmax = 0) as inserted` to `returning`, which is true for rows
+    /// inserted and false for rows updated by [Insert::on_conflict_update].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Insert;
+    /// let q = Insert::into("users")
+    ///     .value("email", "a@example.com")
+    ///     .value("name", "A")
+    ///     .on_conflict_update(["email"], ["name"])
+    ///     .returning(["id"])
+    ///     .returning_inserted();
+    /// assert_eq!(
+    ///     "insert into users (email, name) values ($1, $2) on conflict (email) do update set name = excluded.name returning id, (xmax = 0) as inserted",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    pub fn returning_inserted(self) -> Self {
+        self.returning(["(xmax = 0) as inserted"])
+    }
+
+    /// Writes values through a [ValueTransform], e.g. encrypting them.
+    /// Only values are transformed, not [Insert::value_expr] expressions.
+    pub fn with_transform(mut self, transform: Arc<dyn ValueTransform>) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    pub fn parts(mut self) -> (String, Vec<SQLValue>) {
+        if let Some(transform) = self.transform.take() {
+            for row in &mut self.rows {
+                for (col, (expr, values)) in self.columns.iter().zip(row.iter_mut()) {
+                    if expr == "?" && values.len() == 1 {
+                        (*expr, *values) = transform.write(&fold_identifier(col), values.remove(0));
+                    }
+                }
+            }
+        }
+
+        let mut q = format!("insert into {}", self.table);
+        let mut vals = vec![];
+
+        if self.rows.is_empty() {
+            q.push_str(" default values");
+        } else {
+            q.push_str(&format!(" ({}) values ", self.columns.join(", ")));
+            for (i, row) in self.rows.into_iter().enumerate() {
+                if i > 0 {
+                    q.push_str(", ");
+                }
+                q.push('(');
+                q.push_str(&row.iter().map(|(expr, _)| expr).join(", "));
+                q.push(')');
+                vals.extend(row.into_iter().flat_map(|(_, values)| values));
+            }
+        }
+
+        if let Some(on_conflict) = self.on_conflict {
+            match &on_conflict.target {
+                ConflictTarget::Columns(cols) => {
+                    q.push_str(&format!(" on conflict ({})", cols.join(", ")))
+                }
+                ConflictTarget::Constraint(name) => {
+                    q.push_str(&format!(" on conflict on constraint {name}"))
+                }
+            }
+            if !on_conflict.where_.is_empty() {
+                let mut where_ = String::new();
+                push_where_list(&on_conflict.where_, &mut where_, &mut vals);
+                q.push_str(" where ");
+                q.push_str(where_.trim_end());
+            }
+            if on_conflict.update.is_empty() {
+                q.push_str(" do nothing");
+            } else {
+                let set = on_conflict
+                    .update
+                    .iter()
+                    .map(|c| format!("{c} = excluded.{c}"))
+                    .join(", ");
+                q.push_str(" do update set ");
+                q.push_str(&set);
+            }
+        }
+
+        if !self.returning.is_empty() {
+            q.push_str(" returning ");
+            q.push_str(&self.returning.join(", "));
+        }
+
+        alias::renumber(&mut q, 0);
+        (q, vals)
+    }
+
+    pub fn into_builder<'args>(self) -> QueryBuilder<'args, Postgres> {
+        let (sql, values) = self.parts();
+        build_query(&sql, values, String::new)
+    }
+}
@@ -0,0 +1,14 @@
+use std::time::Duration;
+
+const UNTAGGED: &str = "untagged";
+
+/// Records a single [Select::into_builder](crate::Select::into_builder) call
+/// through the `metrics` facade. Nothing is recorded unless the application
+/// has installed a recorder.
+pub fn record_build(tag: Option<&str>, binds: usize, elapsed: Duration) {
+    let tag = tag.unwrap_or(UNTAGGED).to_string();
+
+    metrics::counter!("query_builder_queries_built_total", "tag" => tag.clone()).increment(1);
+    metrics::histogram!("query_builder_bind_count", "tag" => tag.clone()).record(binds as f64);
+    metrics::histogram!("query_builder_render_seconds", "tag" => tag).record(elapsed.as_secs_f64());
+}
@@ -1,16 +1,27 @@
-use crate::error::QueryError;
-use crate::util::placeholder_count;
-use crate::Select;
+use crate::error::{QResult, QueryError};
+use crate::r#where::{IntoWhere, Where};
+use crate::util::{is_identifier, placeholder_count, validate_column};
+use crate::{SQLValue, Select};
+use itertools::Itertools;
 
 #[derive(Debug, Clone)]
 pub enum JoinKind {
     Left,
+    Inner,
+    Right,
+    Full,
+    /// Has no join condition, so the join text is just the table or subquery.
+    Cross,
 }
 
 impl JoinKind {
     pub fn as_str(&self) -> &'static str {
         match self {
             JoinKind::Left => "left",
+            JoinKind::Inner => "inner",
+            JoinKind::Right => "right",
+            JoinKind::Full => "full",
+            JoinKind::Cross => "cross",
         }
     }
 }
@@ -19,6 +30,148 @@ impl JoinKind {
 pub enum Join {
     Simple(String),
     SubQuery(String, Box<Select>),
+    Lateral(Box<Join>),
+    /// Join text with its values already inlined as `?` placeholders.
+    Bound(String, Vec<SQLValue>),
+}
+
+impl Join {
+    /// Starts an `inner join` on `table`. See [JoinBuilder].
+    pub fn inner(table: impl Into<String>) -> JoinBuilder {
+        JoinBuilder::new(JoinKind::Inner, table)
+    }
+
+    /// Starts a `left join` on `table`. See [JoinBuilder].
+    pub fn left(table: impl Into<String>) -> JoinBuilder {
+        JoinBuilder::new(JoinKind::Left, table)
+    }
+
+    /// Starts a `right join` on `table`. See [JoinBuilder].
+    pub fn right(table: impl Into<String>) -> JoinBuilder {
+        JoinBuilder::new(JoinKind::Right, table)
+    }
+
+    /// Starts a `full join` on `table`. See [JoinBuilder].
+    pub fn full(table: impl Into<String>) -> JoinBuilder {
+        JoinBuilder::new(JoinKind::Full, table)
+    }
+
+    /// See [Select::check_policy].
+    pub(crate) fn check_policy(&self) -> QResult<()> {
+        match self {
+            Join::SubQuery(_, select) => select.check_policy(),
+            Join::Lateral(join) => join.check_policy(),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn using<C: Into<String>>(
+        table: impl Into<String>,
+        cols: impl IntoIterator<Item = C>,
+    ) -> Self {
+        let cols = cols.into_iter().map(Into::into).join(", ");
+        Join::Simple(format!("{} using ({})", table.into(), cols))
+    }
+
+    /// Renders everything after the `join` keyword.
+    pub(crate) fn push_to(self, q: &mut String, vals: &mut Vec<SQLValue>) {
+        match self {
+            Join::Simple(s) => q.push_str(&s),
+            Join::SubQuery(s, select) => {
+                let mut parts = s.split('?');
+
+                // When creating the join we check to ensure we have
+                // at least one `?`, so this unwrap should be safe.
+                q.push_str(parts.next().unwrap());
+                select.render_trimmed_into(q, vals);
+                if let Some(part) = parts.next() {
+                    q.push_str(part);
+                }
+            }
+            Join::Lateral(join) => {
+                q.push_str("lateral ");
+                join.push_to(q, vals);
+            }
+            Join::Bound(s, values) => {
+                q.push_str(&s);
+                vals.extend(values);
+            }
+        }
+    }
+}
+
+/// Builds a join whose `on` condition can carry bound values.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{Join, Select};
+/// let q = Select::from("users").join(
+///     Join::left("posts").on(("posts.user_id = users.id and posts.created_at > ?", "2024-01-01"))?,
+/// );
+/// assert_eq!(
+///     "select * from users left join posts on posts.user_id = users.id and posts.created_at > $1",
+///     q.into_builder().sql()
+/// );
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct JoinBuilder {
+    kind: JoinKind,
+    expr: String,
+    values: Vec<SQLValue>,
+    has_on: bool,
+}
+
+impl JoinBuilder {
+    fn new(kind: JoinKind, table: impl Into<String>) -> Self {
+        Self {
+            kind,
+            expr: table.into(),
+            values: vec![],
+            has_on: false,
+        }
+    }
+
+    /// Starts a join on already rendered text and its values.
+    pub(crate) fn bound(kind: JoinKind, expr: String, values: Vec<SQLValue>) -> Self {
+        Self {
+            kind,
+            expr,
+            values,
+            has_on: false,
+        }
+    }
+
+    /// Starts a join on `table as alias`, checking both are identifiers.
+    pub(crate) fn aliased(kind: JoinKind, table: &str, alias: &str) -> QResult<Self> {
+        validate_column(table)?;
+        if !is_identifier(alias) {
+            return Err(QueryError::InvalidIdentifier(alias.to_string()));
+        }
+        Ok(Self::new(kind, format!("{table} as {alias}")))
+    }
+
+    /// Accepts the same forms as [Select::where_]. Calling this again adds
+    /// another condition joined with `and`.
+    pub fn on<T, E>(self, cond: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.push_on(cond.try_into()?)
+    }
+
+    pub(crate) fn push_on(mut self, cond: Where) -> QResult<Self> {
+        self.expr
+            .push_str(if self.has_on { " and " } else { " on " });
+        cond.into_where(&mut self.expr, &mut self.values)?;
+        self.has_on = true;
+        Ok(self)
+    }
+
+    pub(crate) fn build(self) -> (JoinKind, Join) {
+        (self.kind, Join::Bound(self.expr, self.values))
+    }
 }
 
 impl TryFrom<String> for Join {
@@ -46,3 +199,41 @@ impl<T: Into<String>> TryFrom<(T, Select)> for Join {
         Ok(Join::SubQuery(expr, Box::new(select)))
     }
 }
+
+/// A subquery join whose text also binds one value, e.g.
+/// `("(?) as a on a.id = b.id and a.kind = ?", sub, 2)`.
+impl<T, V1> TryFrom<(T, Select, V1)> for Join
+where
+    T: Into<String>,
+    V1: IntoWhere,
+{
+    type Error = QueryError;
+
+    fn try_from((expr, select, v1): (T, Select, V1)) -> Result<Self, Self::Error> {
+        let w = Where::try_from((expr.into(), select, v1))?;
+        Ok(w.into())
+    }
+}
+
+/// A subquery join whose text also binds two values.
+impl<T, V1, V2> TryFrom<(T, Select, V1, V2)> for Join
+where
+    T: Into<String>,
+    V1: IntoWhere,
+    V2: IntoWhere,
+{
+    type Error = QueryError;
+
+    fn try_from((expr, select, v1, v2): (T, Select, V1, V2)) -> Result<Self, Self::Error> {
+        let w = Where::try_from((expr.into(), select, v1, v2))?;
+        Ok(w.into())
+    }
+}
+
+impl From<Where> for Join {
+    fn from(w: Where) -> Self {
+        match w {
+            Where::Simple { expr, values, .. } => Join::Bound(expr, values),
+        }
+    }
+}
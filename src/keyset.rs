@@ -0,0 +1,192 @@
+use crate::bool_kind::BoolKind;
+use crate::error::{QResult, QueryError};
+use crate::r#where::Where;
+use crate::sql_value::SQLValue;
+use crate::util::validate_column;
+use crate::values::ValuesRow;
+use crate::OrderDir;
+use itertools::Itertools;
+
+/// Key columns for keyset (seek) pagination, see [crate::Select::after].
+///
+/// Instead of skipping `offset` rows, each page starts after the key of
+/// the last row of the previous one, so it can use an index on the keys
+/// however deep the page. The keys must be unique together, usually by
+/// ending with the primary key, and not null unless marked with
+/// [Keyset::nullable].
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{Keyset, OrderDir, Select};
+/// let keyset = Keyset::new([("score", OrderDir::Desc), ("id", OrderDir::Desc)])?;
+///
+/// // The last row of the previous page had score 90 and id 42.
+/// let q = Select::from("players").after(&keyset, (90, 42))?.limit(50);
+/// assert_eq!(
+///     "select * from players where (score, id) < ($1, $2)  order by score desc, id desc  limit $3",
+///     q.into_builder().sql()
+/// );
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Keyset {
+    keys: Vec<(String, OrderDir)>,
+    nullable: Vec<bool>,
+}
+
+impl Keyset {
+    /// Key columns in sort order. Each must be a plain, optionally
+    /// qualified, column name, and there must be at least one.
+    pub fn new<C: Into<String>>(keys: impl IntoIterator<Item = (C, OrderDir)>) -> QResult<Self> {
+        let keys = keys
+            .into_iter()
+            .map(|(col, dir)| (col.into(), dir))
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            return Err(QueryError::Unsupported(
+                "keyset without key columns".to_string(),
+            ));
+        }
+        for (col, _) in &keys {
+            validate_column(col)?;
+        }
+        let nullable = vec![false; keys.len()];
+        Ok(Self { keys, nullable })
+    }
+
+    /// Marks the key column `col` as nullable. Nulls sort after all other
+    /// values, going forward, by ordering on `col is null` first, so an
+    /// index for the keys needs to be on `(col is null, col, ...)`. The
+    /// cursor may then hold [SQLValue::Null] for `col`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Keyset, OrderDir, SQLValue, Select};
+    /// let keyset = Keyset::new([("due_at", OrderDir::Asc), ("id", OrderDir::Asc)])?
+    ///     .nullable("due_at")?;
+    /// let q = Select::from("tasks").after(&keyset, ("2024-05-01", 7))?;
+    /// assert_eq!(
+    ///     "select * from tasks where (((due_at > $1 or due_at is null)) or (due_at = $2 and id > $3))  order by due_at is null asc, due_at asc, id asc ",
+    ///     q.into_builder().sql()
+    /// );
+    ///
+    /// // Past the last dated task, only undated ones are left.
+    /// let q = Select::from("tasks").after(&keyset, (SQLValue::Null, 9))?;
+    /// assert_eq!(
+    ///     "select * from tasks where ((due_at is null and id > $1))  order by due_at is null asc, due_at asc, id asc ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn nullable(mut self, col: &str) -> QResult<Self> {
+        let i = self
+            .keys
+            .iter()
+            .position(|(key, _)| key == col)
+            .ok_or_else(|| QueryError::UnknownField(col.to_string()))?;
+        self.nullable[i] = true;
+        Ok(self)
+    }
+
+    /// The sort keys, flipped when paging backwards. A nullable key is
+    /// preceded by `col is null`, putting nulls last going forward.
+    pub(crate) fn order(&self, forward: bool) -> impl Iterator<Item = (String, OrderDir)> + '_ {
+        let dir = move |dir| if forward { dir } else { flip(dir) };
+        self.keys
+            .iter()
+            .zip(&self.nullable)
+            .flat_map(move |((col, d), nullable)| {
+                let is_null = nullable.then(|| (format!("{col} is null"), dir(OrderDir::Asc)));
+                is_null.into_iter().chain([(col.clone(), dir(*d))])
+            })
+    }
+
+    /// The condition for rows after (or, going backwards, before) `cursor`.
+    ///
+    /// Keys sorted the same way compare as a row, `(a, b) > (?, ?)`, which
+    /// Postgres can answer from a single index. Mixed directions expand to
+    /// `a > ? or (a = ? and b < ?)`.
+    pub(crate) fn seek(&self, cursor: impl ValuesRow, forward: bool) -> QResult<Where> {
+        let cursor = cursor.into_row();
+        if cursor.len() != self.keys.len() {
+            return Err(QueryError::ValueCountMismatch {
+                expected: self.keys.len(),
+                found: cursor.len(),
+            });
+        }
+        let keys = self
+            .keys
+            .iter()
+            .map(|(col, dir)| (col, if forward { *dir } else { flip(*dir) }))
+            .collect::<Vec<_>>();
+        let op = |dir| match dir {
+            OrderDir::Asc => ">",
+            OrderDir::Desc => "<",
+        };
+
+        let nullable = self.nullable.contains(&true);
+        if !nullable && keys.iter().map(|(_, dir)| dir).all_equal() {
+            let cols = keys.iter().map(|(col, _)| col).join(", ");
+            let placeholders = vec!["?"; keys.len()].join(", ");
+            return Ok(Where::Simple {
+                expr: format!("({cols}) {} ({placeholders})", op(keys[0].1)),
+                values: cursor,
+                kind: BoolKind::And,
+            });
+        }
+
+        // Each term matches rows equal to the cursor on the first `i` keys
+        // and after it on key `i`. A null only equals a null, and going
+        // forward nulls come after every value.
+        let mut terms = vec![];
+        let mut values = vec![];
+        for (i, (col, dir)) in keys.iter().enumerate() {
+            let mut term = vec![];
+            let mut term_values = vec![];
+            for (j, (col, _)) in keys[..i].iter().enumerate() {
+                if self.nullable[j] && matches!(cursor[j], SQLValue::Null) {
+                    term.push(format!("{col} is null"));
+                } else {
+                    term.push(format!("{col} = ?"));
+                    term_values.push(cursor[j].clone());
+                }
+            }
+            let value = &cursor[i];
+            let after = match (self.nullable[i], matches!(value, SQLValue::Null), forward) {
+                (false, _, _) => Some(format!("{col} {} ?", op(*dir))),
+                (true, false, true) => Some(format!("({col} {} ? or {col} is null)", op(*dir))),
+                (true, false, false) => Some(format!("{col} {} ?", op(*dir))),
+                (true, true, true) => None,
+                (true, true, false) => Some(format!("{col} is not null")),
+            };
+            let Some(after) = after else {
+                continue;
+            };
+            if after.contains('?') {
+                term_values.push(value.clone());
+            }
+            term.push(after);
+            terms.push(format!("({})", term.join(" and ")));
+            values.extend(term_values);
+        }
+        if terms.is_empty() {
+            return Ok(Where::Simple {
+                expr: "false".to_string(),
+                values,
+                kind: BoolKind::And,
+            });
+        }
+        Ok(Where::Simple {
+            expr: format!("({})", terms.join(" or ")),
+            values,
+            kind: BoolKind::And,
+        })
+    }
+}
+
+fn flip(dir: OrderDir) -> OrderDir {
+    match dir {
+        OrderDir::Asc => OrderDir::Desc,
+        OrderDir::Desc => OrderDir::Asc,
+    }
+}
@@ -1,43 +1,189 @@
+pub mod agg;
+mod alias;
+mod allow_list;
+pub mod array;
 mod bool_kind;
+#[cfg(feature = "buffer-reuse")]
+mod buffer;
+#[cfg(feature = "cache")]
+mod cache;
+mod clause;
+mod col;
+mod cte;
+mod delete;
 mod error;
+#[cfg(feature = "exec")]
+mod exec;
+mod field_map;
+pub mod fts;
 mod group_by;
+mod insert;
+#[cfg(feature = "metrics")]
+mod instrument;
 mod join;
+mod keyset;
+mod money;
+mod op;
 mod optional_num;
 mod order;
+mod page;
+#[cfg(feature = "exec")]
+mod paginate;
+mod policy;
+mod registry;
+mod rls;
+mod sample;
+mod schema;
+#[cfg(feature = "sea-query")]
+mod sea_query_interop;
 mod select;
+mod shape;
 mod sql_value;
+mod stored;
+mod table;
+#[cfg(feature = "timescale")]
+mod timescale;
+#[cfg(feature = "tokio-postgres")]
+mod tokio_pg;
+mod transform;
+mod update;
 mod util;
+mod values;
 mod r#where;
+mod window;
 
+pub use crate::allow_list::FingerprintAllowList;
 use crate::bool_kind::BoolKind;
-use crate::error::QResult;
-use crate::join::{Join, JoinKind};
+#[cfg(feature = "cache")]
+pub use crate::cache::{CachedSelect, MemoryCache, QueryCache};
+use crate::clause::push_clauses;
+pub use crate::clause::{ClausePosition, RenderClause};
+pub use crate::col::{col, Col};
+pub use crate::cte::IntoCte;
+pub use crate::delete::Delete;
+pub use crate::field_map::FieldMap;
+pub use crate::insert::Insert;
+use crate::join::JoinKind;
+pub use crate::join::{Join, JoinBuilder};
+pub use crate::keyset::Keyset;
+pub use crate::money::Money;
+pub use crate::op::Op;
 use crate::optional_num::IntoOptional;
 pub use crate::order::OrderDir;
-pub use crate::r#where::{IntoWhere, Where, WhereBuilder};
+pub use crate::page::{ListParams, PageLimits};
+#[cfg(feature = "exec")]
+pub use crate::paginate::{Page, Paginate};
+pub use crate::policy::ColumnPolicy;
+use crate::r#where::push_where_list;
+pub use crate::r#where::{IntoWhere, List, Where, WhereBuilder};
+pub use crate::registry::QueryRegistry;
+pub use crate::rls::RlsContext;
+pub use crate::sample::TableSample;
+pub use crate::schema::Schema;
 use crate::select::IntoSelect;
-pub use crate::sql_value::SQLValue;
-pub use error::QueryError;
+pub use crate::select::SelectExpr;
+pub use crate::shape::QueryShape;
+pub use crate::sql_value::{ArrayElement, SQLValue, TypedArray};
+pub use crate::stored::{StoredSelect, StoredSelectV1};
+pub use crate::table::Table;
+pub use crate::transform::{PgpSymEncryption, ValueTransform};
+pub use crate::update::Update;
+pub use crate::values::ValuesRow;
+pub use crate::window::Partition;
+use error::located;
+pub use error::{QResult, QueryError};
+#[cfg(feature = "exec")]
+pub use exec::ExecPolicy;
 use group_by::IntoGroupBy;
 use itertools::{EitherOrBoth, Itertools};
 use sqlx::{Postgres, QueryBuilder};
+use std::borrow::Cow;
+use std::sync::Arc;
+#[cfg(feature = "tokio-postgres")]
+pub use tokio_pg::TokioPostgresQuery;
 
+/// A select query under construction.
+///
+/// `Select` owns all of its clauses and values, so it is `Send + Sync`.
+/// A base query can be built once, shared across threads (e.g. in an
+/// `Arc`), and cloned or passed to [Select::extend_from] per task.
 #[derive(Debug, Clone, Default)]
 pub struct Select {
+    /// Common table expressions: name, rendered statement and its values.
+    ctes: Vec<(String, String, Vec<SQLValue>)>,
     table: Option<TableType>,
-    select: Vec<String>,
+    alias: Option<String>,
+    also_from: Vec<TableType>,
+    tablesample: Option<TableSample>,
+    /// Generated aliases and the source they were generated for.
+    aliases: Vec<(String, String)>,
+    select: Vec<SelectExpr>,
     join: Vec<(JoinKind, Join)>,
     where_: Vec<Where>,
-    order_by: Option<(String, OrderDir)>,
+    /// Sort keys with the values bound in them.
+    order_by: Vec<(String, Vec<SQLValue>, OrderDir)>,
     group_by: Option<String>,
+    having: Vec<Where>,
     limit: Option<u64>,
     offset: Option<u64>,
+    limit_percent: Option<f64>,
+    /// Renders the limit as `fetch first ? rows with ties`.
+    with_ties: bool,
+    /// Used when no limit is set, see [Select::default_limit].
+    default_limit: Option<u64>,
+    lock: Option<Lock>,
+    tag: Option<String>,
+    policy: Option<Arc<ColumnPolicy>>,
+    transform: Option<Arc<dyn ValueTransform>>,
+    rls: Option<RlsContext>,
+    /// See [Select::in_schema]. Kept apart from the [RlsContext] so that
+    /// [Select::with_rls] can't drop it.
+    schema: Option<String>,
+    /// See [Select::uppercase_keywords].
+    uppercase: bool,
+    /// A column refused by the [ColumnPolicy] of a subquery already
+    /// rendered into this query, see [Select::check_policy].
+    policy_violation: Option<String>,
+    clauses: Vec<Arc<dyn RenderClause>>,
+    #[cfg(feature = "track-caller")]
+    call_sites: Vec<(&'static str, &'static std::panic::Location<'static>)>,
+}
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Select>();
+};
+
+/// A `for update` or `for share` clause.
+#[derive(Debug, Clone)]
+struct Lock {
+    /// `update` or `share`.
+    strength: &'static str,
+    /// Tables the lock applies to, all of them when empty.
+    of: Vec<String>,
+    /// `skip locked` or `nowait`.
+    wait: Option<&'static str>,
+}
+
+impl Lock {
+    fn new(strength: &'static str) -> Self {
+        Self {
+            strength,
+            of: vec![],
+            wait: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum TableType {
     Simple(String),
     Complex(String, Vec<Select>),
+    /// Rendered comma separated, e.g. `from users, accounts`.
+    List(Vec<TableType>),
+    Table(Table),
+    /// Table text with its values already inlined as `?` placeholders.
+    Bound(String, Vec<SQLValue>),
 }
 
 impl From<&str> for TableType {
@@ -76,6 +222,88 @@ impl From<(String, Select, Select)> for TableType {
     }
 }
 
+impl TableType {
+    /// Whether the table carries its own alias, see [Select::alias]. A
+    /// subquery has one when something follows its closing parenthesis,
+    /// e.g. `(?) as x`.
+    fn has_alias(&self) -> bool {
+        match self {
+            TableType::Table(table) => table.alias.is_some(),
+            TableType::Complex(template, _) => !template.trim_end().ends_with(')'),
+            _ => false,
+        }
+    }
+
+    /// See [Select::check_policy].
+    fn check_policy(&self) -> QResult<()> {
+        match self {
+            TableType::Complex(_, selects) => selects.iter().try_for_each(Select::check_policy),
+            TableType::List(tables) => tables.iter().try_for_each(TableType::check_policy),
+            _ => Ok(()),
+        }
+    }
+
+    fn push_to(self, q: &mut String, vals: &mut Vec<SQLValue>) {
+        match self {
+            TableType::Simple(s) => q.push_str(s.as_str()),
+            TableType::Complex(s, v) => {
+                // println!("q at the start is {}", q);
+                // println!("vals at the start are {:?}", vals);
+
+                let mut parts = s.split("?");
+                if let Some(part) = parts.next() {
+                    q.push_str(part);
+                }
+                for select in v {
+                    select.render_into(q, vals);
+
+                    if let Some(part) = parts.next() {
+                        q.push_str(part);
+                    }
+                }
+
+                for part in parts {
+                    q.push_str(part);
+                }
+            }
+            TableType::Table(table) => q.push_str(&table.to_sql()),
+            TableType::Bound(s, values) => {
+                q.push_str(&s);
+                vals.extend(values);
+            }
+            TableType::List(tables) => {
+                for (i, table) in tables.into_iter().enumerate() {
+                    if i > 0 {
+                        q.push_str(", ");
+                    }
+                    table.push_to(q, vals);
+                }
+            }
+        }
+    }
+}
+
+impl From<Table> for TableType {
+    fn from(value: Table) -> Self {
+        TableType::Table(value)
+    }
+}
+impl From<Select> for TableType {
+    fn from(value: Select) -> Self {
+        TableType::Complex("(?)".to_string(), vec![value])
+    }
+}
+impl From<(&str, &str)> for TableType {
+    fn from((a, b): (&str, &str)) -> Self {
+        TableType::List(vec![a.into(), b.into()])
+    }
+}
+impl From<(&str, &str, &str)> for TableType {
+    fn from((a, b, c): (&str, &str, &str)) -> Self {
+        TableType::List(vec![a.into(), b.into(), c.into()])
+    }
+}
+
 impl Select {
     pub fn new() -> Self {
         Self::default()
@@ -113,542 +341,4809 @@ impl Select {
     /// ```
     ///
     /// You will probably wany to use [Select::from] in most cases
+    ///
+    /// A table with an alias of its own replaces one set with
+    /// [Select::alias].
     pub fn table(mut self, table: impl Into<TableType>) -> Self {
-        self.table = Some(table.into());
+        let table = table.into();
+        if table.has_alias() {
+            self.alias = None;
+        }
+        self.table = Some(table);
         self
     }
 
-    /// The passed item should _not_ contain leading "left join" text.
-    /// That is added automatically.
-    pub fn left_join<T>(mut self, join: T) -> QResult<Self>
-    where
-        T: TryInto<Join, Error = QueryError>,
-    {
-        self.join.push((JoinKind::Left, join.try_into()?));
+    /// Adds `name as (statement)` to the query's `with` clause. The
+    /// statement's values are numbered along with the rest of the query.
+    ///
+    /// Data modifying statements with `returning` make create-then-read
+    /// a single round trip.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Insert, Select};
+    /// let insert = Insert::into("posts")
+    ///     .value("title", "Hello")
+    ///     .returning(["id", "author_id", "title"]);
+    /// let q = Select::new()
+    ///     .with("created", insert)?
+    ///     .table("created")
+    ///     .select(("created.id", "created.title", "users.name"))
+    ///     .inner_join("users on users.id = created.author_id")?;
+    /// assert_eq!(
+    ///     "with created as (insert into posts (title) values ($1) returning id, author_id, title) select created.id, created.title, users.name from created inner join users on users.id = created.author_id",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn with(mut self, name: &str, statement: impl IntoCte) -> QResult<Self> {
+        if !util::is_identifier(name) {
+            return Err(QueryError::InvalidIdentifier(name.to_string()));
+        }
+        statement.check_policy()?;
+        let (sql, values) = statement.into_cte();
+        self.ctes.push((name.to_string(), sql, values));
         Ok(self)
     }
 
-    /// Where expressions are constructed as either strings or tuples.
-    /// The first value in the tuple is the query fragment, and the remaining
-    /// are the values to pass in.
+    /// Selects from an inline `values` list, aliased as `v` with the
+    /// passed column names. Handy for joining against a small set of rows
+    /// without a temporary table.
     ///
-    /// Placeholders are `?` and _not_ the normal Postgres `$1, $2, $3` style.
-    /// They will be converted to the Postgres `$1` style when the query is built.
-    ///
-    /// One, two, or three values can be passed in, in addition to
-    /// the first string value.
+    /// Every row must have one value per column, and there must be at
+    /// least one row.
     ///
     /// Example:
     /// ```
     /// use composable_query_builder2::Select;
-    /// Select::from("my_table").where_("id > 0")?;
-    /// Select::from("my_table").where_(("id > ?", 0))?;
-    /// Select::from("my_table").where_(("id > ? and id < ?", 0, 10))?;
-    /// Select::from("my_table").where_(("id > ? and id < ? and other_col > ?", 0, 10, 5))?;
+    /// let q = Select::from_values([(1, "a"), (2, "b")], ["id", "name"])?;
+    /// assert_eq!(
+    ///     "select * from (values ($1,$2),($3,$4)) as v(id, name)",
+    ///     q.into_builder().sql()
+    /// );
     /// # Ok::<(), composable_query_builder2::QueryError>(())
     /// ```
+    pub fn from_values<R, C>(
+        rows: impl IntoIterator<Item = R>,
+        cols: impl IntoIterator<Item = C>,
+    ) -> QResult<Self>
+    where
+        R: ValuesRow,
+        C: Into<String>,
+    {
+        let cols = cols.into_iter().map(Into::into).collect::<Vec<String>>();
+        let mut sql = "(values ".to_string();
+        let mut values = vec![];
+        for (i, row) in rows.into_iter().enumerate() {
+            let row = row.into_row();
+            if row.len() != cols.len() {
+                return Err(QueryError::ValueCountMismatch {
+                    expected: cols.len(),
+                    found: row.len(),
+                });
+            }
+            let placeholders = vec!["?"; row.len()].join(",");
+            if i > 0 {
+                sql.push(',');
+            }
+            sql.push('(');
+            sql.push_str(&placeholders);
+            sql.push(')');
+            values.extend(row);
+        }
+        if values.is_empty() {
+            return Err(QueryError::Unsupported(
+                "values list with no rows".to_string(),
+            ));
+        }
+        sql.push_str(&format!(") as v({})", cols.join(", ")));
+        Ok(Select::from(TableType::Bound(sql, values)))
+    }
+
+    /// Selects from a set returning function, accepting the same forms as
+    /// [Select::where_] so the function arguments can be bound.
     ///
-    /// Where clauses can also be composed:
+    /// Example:
     /// ```
-    /// use composable_query_builder2::{Where, Select};
-    /// let sub: Where = ("id > ? and id < ?", 1, 10).try_into()?;
-    /// Select::from("my_table").where_(("id = 20 or (?)", sub))?;
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from_fn(("generate_series(?::date, ?::date, '1 day') as g(day)", "2024-01-01", "2024-01-31"))?;
+    /// assert_eq!(
+    ///     "select * from generate_series($1::date, $2::date, '1 day') as g(day)",
+    ///     q.into_builder().sql()
+    /// );
     /// # Ok::<(), composable_query_builder2::QueryError>(())
     /// ```
-    pub fn where_<T, E>(mut self, where_: T) -> QResult<Self>
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn from_fn<T, E>(func: T) -> QResult<Self>
     where
         T: TryInto<Where, Error = E>,
         QueryError: From<E>,
     {
-        self.where_.push(where_.try_into()?);
-        Ok(self)
+        let Where::Simple { expr, values, .. } = located(func.try_into())?;
+        Ok(Select::from(TableType::Bound(expr, values)))
     }
 
-    pub fn where_if<T, E>(mut self, cond: bool, callback: impl Fn() -> T) -> QResult<Self>
-    where
-        T: TryInto<Where, Error = E>,
-        QueryError: From<E>,
-    {
-        if cond {
-            self.where_.push(callback().try_into()?);
-        }
-        Ok(self)
+    /// Reads a random sample of the table, e.g. for analytics over very
+    /// large tables where an estimate is good enough.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Select, TableSample};
+    /// let q = Select::from("events").tablesample(TableSample::System(1.0));
+    /// assert_eq!(
+    ///     "select * from events tablesample system ($1)",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    pub fn tablesample(mut self, sample: TableSample) -> Self {
+        self.tablesample = Some(sample);
+        self
     }
 
-    pub fn where_in(mut self, col: impl Into<String>, values: Vec<i64>) -> Self {
-        let expr = format!("{} = ANY(?)", col.into());
-        self.where_.push(Where::Simple {
-            expr,
-            values: vec![values.into()],
-            kind: BoolKind::And,
-        });
+    /// Adds another table to the `from` list, for old style comma joins,
+    /// e.g. `from users, accounts`. The join condition goes in the where
+    /// clause.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users")
+    ///     .also_from("accounts")
+    ///     .where_("accounts.id = users.account_id")?;
+    /// assert_eq!(
+    ///     "select * from users, accounts where accounts.id = users.account_id ",
+    ///     q.into_builder().sql()
+    /// );
+    /// assert_eq!(
+    ///     "select * from users, accounts",
+    ///     Select::from(("users", "accounts")).into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn also_from(mut self, table: impl Into<TableType>) -> Self {
+        self.also_from.push(table.into());
         self
     }
 
-    pub fn or_where<T>(mut self, where_: T) -> QResult<Self>
-    where
-        T: TryInto<Where, Error = QueryError>,
-    {
-        let mut w = where_.try_into()?;
-        w.kind(BoolKind::Or);
-        self.where_.push(w);
-        Ok(self)
+    /// Renders `from table as alias`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from_as("users", "u")?;
+    /// let q = q.clone().select_prefixed(["id", "name"]).where_(q.qualify("active"))?;
+    /// assert_eq!(
+    ///     "select u.id, u.name from users as u where u.active ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn from_as(table: impl Into<TableType>, alias: impl Into<String>) -> QResult<Self> {
+        Select::from(table).alias(alias)
     }
 
-    /// Adds one or more columns to the select statement.
+    /// Sets the alias the table is referred to by. See [Select::from_as].
     ///
-    /// See [`IntoSelect`] for details on what can be passed in.
-    pub fn select(mut self, column: impl IntoSelect) -> Self {
-        self.select.append(&mut column.into_select());
-        self
+    /// Fails with [QueryError::Unsupported] when the table already has an
+    /// alias of its own, e.g. `Table::new("users").alias("u")` or a
+    /// `(?) as x` subquery, rather than rendering two.
+    pub fn alias(mut self, alias: impl Into<String>) -> QResult<Self> {
+        if self.table.as_ref().is_some_and(TableType::has_alias) {
+            return Err(QueryError::Unsupported(
+                "alias on a table that already has one".to_string(),
+            ));
+        }
+        self.alias = Some(alias.into());
+        Ok(self)
     }
 
-    pub fn group_by(mut self, group_by: impl IntoGroupBy) -> Self {
-        self.group_by = Some(group_by.into_group_by());
-        self
+    /// The name columns of the table should be qualified with: the alias
+    /// if one is set, otherwise the table name for a plain table. A
+    /// [Table] name is quoted like it is rendered.
+    pub fn qualifier(&self) -> Option<Cow<'_, str>> {
+        match (&self.alias, &self.table) {
+            (Some(alias), _) => Some(Cow::Borrowed(alias)),
+            (None, Some(TableType::Simple(table))) => Some(Cow::Borrowed(table)),
+            (None, Some(TableType::Table(table))) => Some(Cow::Owned(table.qualifier())),
+            _ => None,
+        }
     }
 
-    /// ## Danger: SQL injection
+    /// Prefixes `col` with [Select::qualifier], e.g. `u.id`.
+    pub fn qualify(&self, col: &str) -> String {
+        match self.qualifier() {
+            Some(prefix) => format!("{prefix}.{col}"),
+            None => col.to_string(),
+        }
+    }
+
+    /// Adds each column to the select list, qualified with
+    /// [Select::qualifier].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_prefixed<C: AsRef<str>>(self, cols: impl IntoIterator<Item = C>) -> Self {
+        let cols = cols
+            .into_iter()
+            .map(|c| self.qualify(c.as_ref()))
+            .collect::<Vec<_>>();
+        self.select(cols)
+    }
+
+    /// Copies the clauses of `template` into this query.
     ///
-    /// The passed `col` is _not_ sanitized. If this is taking
-    /// user input, it should be compared against an allow-list.
-    pub fn order_by(mut self, col: impl Into<String>, dir: OrderDir) -> Self {
-        self.order_by = Some((col.into(), dir));
+    /// Selected columns, joins and where clauses are appended after the ones
+    /// already present. The table, grouping, ordering, limit, offset and tag
+    /// are only taken from the template when this query has none set.
+    ///
+    /// The template's clauses were checked when they were added, so nothing
+    /// is re-validated here.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let base = Select::from("users").where_("deleted_at is null")?;
+    /// let q = Select::new().extend_from(&base).where_(("id = ?", 1))?;
+    /// assert_eq!(
+    ///     "select * from users where deleted_at is null and id = $1 ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn extend_from(mut self, template: &Select) -> Self {
+        let template = template.clone();
+        self.ctes.extend(template.ctes);
+        if self.table.is_none() {
+            self.table = template.table;
+            self.alias = template.alias;
+            self.tablesample = template.tablesample;
+        }
+        self.also_from.extend(template.also_from);
+        self.aliases.extend(template.aliases);
+        self.select.extend(template.select);
+        self.join.extend(template.join);
+        self.where_.extend(template.where_);
+        if self.order_by.is_empty() {
+            self.order_by = template.order_by;
+        }
+        self.group_by = self.group_by.or(template.group_by);
+        self.having.extend(template.having);
+        if self.limit.is_none() {
+            self.with_ties = template.with_ties;
+        }
+        self.limit = self.limit.or(template.limit);
+        self.default_limit = self.default_limit.or(template.default_limit);
+        self.offset = self.offset.or(template.offset);
+        self.limit_percent = self.limit_percent.or(template.limit_percent);
+        self.lock = self.lock.or(template.lock);
+        self.tag = self.tag.or(template.tag);
+        self.policy = self.policy.or(template.policy);
+        self.transform = self.transform.or(template.transform);
+        self.rls = self.rls.or(template.rls);
+        self.schema = self.schema.or(template.schema);
+        self.uppercase |= template.uppercase;
+        self.policy_violation = self.policy_violation.or(template.policy_violation);
+        self.clauses.extend(template.clauses);
+        #[cfg(feature = "track-caller")]
+        self.call_sites.extend(template.call_sites);
         self
     }
 
-    pub fn limit(mut self, limit: impl IntoOptional<u64>) -> Self {
-        self.limit = limit.into_optional();
+    /// Adds a join built with [JoinBuilder], e.g. `Join::inner("posts").on(...)`.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn join(mut self, join: JoinBuilder) -> Self {
+        self.record("join");
+        self.join.push(join.build());
         self
     }
 
-    /// An alias for [Select::limit]
-    pub fn take(self, take: impl IntoOptional<u64>) -> Self {
-        self.limit(take)
+    /// The passed item should _not_ contain leading "left join" text.
+    /// That is added automatically.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn left_join<T, E>(self, join: T) -> QResult<Self>
+    where
+        T: TryInto<Join, Error = E>,
+        QueryError: From<E>,
+    {
+        Ok(self.push_join(JoinKind::Left, located(join.try_into())?))
     }
 
-    pub fn offset(mut self, offset: impl IntoOptional<u64>) -> Self {
-        self.offset = offset.into_optional();
-        self
+    /// Same as [Select::left_join], rendering `inner join`.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn inner_join<T, E>(self, join: T) -> QResult<Self>
+    where
+        T: TryInto<Join, Error = E>,
+        QueryError: From<E>,
+    {
+        Ok(self.push_join(JoinKind::Inner, located(join.try_into())?))
     }
 
-    /// An alais for [Select::offset]
-    pub fn skip(self, skip: impl IntoOptional<u64>) -> Self {
-        self.offset(skip)
+    /// Same as [Select::left_join], rendering `right join`.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn right_join<T, E>(self, join: T) -> QResult<Self>
+    where
+        T: TryInto<Join, Error = E>,
+        QueryError: From<E>,
+    {
+        Ok(self.push_join(JoinKind::Right, located(join.try_into())?))
     }
 
-    pub fn parts(self) -> (String, Vec<SQLValue>) {
-        let mut q = "select ".to_string();
-        let mut vals: Vec<SQLValue> = vec![];
+    /// Same as [Select::left_join], rendering `full join`.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn full_join<T, E>(self, join: T) -> QResult<Self>
+    where
+        T: TryInto<Join, Error = E>,
+        QueryError: From<E>,
+    {
+        Ok(self.push_join(JoinKind::Full, located(join.try_into())?))
+    }
 
-        // Select
-        if self.select.is_empty() {
-            q.push('*');
-        } else {
-            let l = self.select.len() - 1;
-            for (last, s) in self.select.into_iter().enumerate().map(|x| (x.0 == l, x.1)) {
-                q.push_str(s.as_str());
-                if !last {
-                    q.push_str(", ");
-                }
-            }
-        }
+    /// Renders `inner join table using (col, ...)`, for joins where the
+    /// columns have the same name on both sides.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users").inner_join_using("accounts", ["account_id"]);
+    /// assert_eq!(
+    ///     "select * from users inner join accounts using (account_id)",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn inner_join_using<C>(
+        self,
+        table: impl Into<String>,
+        cols: impl IntoIterator<Item = C>,
+    ) -> Self
+    where
+        C: Into<String>,
+    {
+        self.push_join(JoinKind::Inner, Join::using(table, cols))
+    }
 
-        // Table
-        q.push_str(" from ");
-        match self.table {
-            Some(TableType::Simple(s)) => q.push_str(s.as_str()),
-            Some(TableType::Complex(s, v)) => {
-                // println!("q at the start is {}", q);
-                // println!("vals at the start are {:?}", vals);
+    /// Same as [Select::inner_join_using], rendering `left join`.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn left_join_using<C>(
+        self,
+        table: impl Into<String>,
+        cols: impl IntoIterator<Item = C>,
+    ) -> Self
+    where
+        C: Into<String>,
+    {
+        self.push_join(JoinKind::Left, Join::using(table, cols))
+    }
 
-                let mut parts = s.split("?");
-                if let Some(part) = parts.next() {
-                    q.push_str(part);
-                }
-                for select in v.iter() {
-                    let (sub_q, sub_vals) = select.clone().parts();
-                    // println!("sub_q: {}", sub_q);
-                    // println!("sub_vals: {:?}", sub_vals);
-                    // q.push_str(" (");
-                    q.push_str(sub_q.as_str());
-                    // q.push(')');
-                    // if i < v.len() - 1 {
-                    //     q.push_str(", ");
-                    // }
-                    vals.extend(sub_vals);
+    /// Renders `inner join table as alias on ...`, mostly for self-joins.
+    /// The `on` condition accepts the same forms as [Select::where_].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users")
+    ///     .inner_join_as("users", "managers", "managers.id = users.manager_id")?;
+    /// assert_eq!(
+    ///     "select * from users inner join users as managers on managers.id = users.manager_id",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn inner_join_as<T, E>(self, table: &str, alias: &str, on: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        let on = located(on.try_into())?;
+        Ok(self.join(JoinBuilder::aliased(JoinKind::Inner, table, alias)?.push_on(on)?))
+    }
 
-                    if let Some(part) = parts.next() {
-                        q.push_str(part);
-                    }
-                }
+    /// Same as [Select::inner_join_as], rendering `left join`.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn left_join_as<T, E>(self, table: &str, alias: &str, on: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        let on = located(on.try_into())?;
+        Ok(self.join(JoinBuilder::aliased(JoinKind::Left, table, alias)?.push_on(on)?))
+    }
 
-                for part in parts {
-                    q.push_str(part);
-                }
-            }
-            None => panic!("No table specified"),
-        }
+    /// Joins `source` under a generated alias, so the same table or
+    /// subquery can be joined any number of times without alias clashes.
+    /// The `on` closure is passed the generated alias, which renders as
+    /// `t1`, `t2`, ... numbered per statement, see [Select::aliases].
+    ///
+    /// The generated aliases are listed by [Select::aliases].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("transfers")
+    ///     .inner_join_auto("accounts", |a| format!("{a}.id = transfers.from_id"))?
+    ///     .inner_join_auto("accounts", |a| format!("{a}.id = transfers.to_id"))?;
+    /// assert_eq!(
+    ///     "select * from transfers inner join accounts as t1 on t1.id = transfers.from_id inner join accounts as t2 on t2.id = transfers.to_id",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn inner_join_auto<T, E>(
+        self,
+        source: impl Into<TableType>,
+        on: impl FnOnce(&str) -> T,
+    ) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.join_auto(JoinKind::Inner, source.into(), on)
+    }
 
-        // Joins
-        for (kind, join) in self.join {
-            match join {
-                Join::Simple(s) => {
-                    q.push(' ');
-                    q.push_str(kind.as_str());
-                    q.push_str(" join ");
-                    q.push_str(&s);
-                }
-                Join::SubQuery(s, select) => {
-                    q.push(' ');
-                    q.push_str(kind.as_str());
-                    q.push_str(" join ");
-                    let (sub_q, sub_vals) = select.parts();
-
-                    let mut parts = s.split('?');
-
-                    // When creating the join we check to ensure we have
-                    // at least one `?`, so this unwrap should be safe.
-                    q.push_str(parts.next().unwrap());
-                    q.push_str(sub_q.trim());
-                    if let Some(part) = parts.next() {
-                        q.push_str(part);
-                    }
+    /// Same as [Select::inner_join_auto], rendering `left join`.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn left_join_auto<T, E>(
+        self,
+        source: impl Into<TableType>,
+        on: impl FnOnce(&str) -> T,
+    ) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.join_auto(JoinKind::Left, source.into(), on)
+    }
 
-                    vals.extend(sub_vals);
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn join_auto<T, E>(
+        mut self,
+        kind: JoinKind,
+        source: TableType,
+        on: impl FnOnce(&str) -> T,
+    ) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        let alias = alias::next_alias();
+        let (mut expr, mut values) = (String::new(), vec![]);
+        source.push_to(&mut expr, &mut values);
+        self.aliases.push((alias.clone(), expr.clone()));
+
+        let on = located(on(&alias).try_into())?;
+        expr.push_str(" as ");
+        expr.push_str(&alias);
+        Ok(self.join(JoinBuilder::bound(kind, expr, values).push_on(on)?))
+    }
+
+    /// Keeps the rows with a matching row in `source`, rendering a
+    /// correlated `exists (select 1 from source ...)`. Each key pairs a
+    /// column of this query with a column of `source`. Unlike a join, each
+    /// row is returned once however many rows match.
+    ///
+    /// `source` gets a generated alias, as with [Select::inner_join_auto],
+    /// so the keys can't be mis-correlated when it is the same table.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let paid = Select::from("orders").where_(("status = ?", "paid"))?;
+    /// let q = Select::from("users").where_exists_join(paid, [("users.id", "user_id")])?;
+    /// assert_eq!(
+    ///     "select * from users where exists (select 1 from (select * from orders where status = $1 ) as t1 where t1.user_id = users.id) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_exists_join<'k>(
+        self,
+        source: impl Into<TableType>,
+        keys: impl IntoIterator<Item = (&'k str, &'k str)>,
+    ) -> QResult<Self> {
+        self.exists_join("exists", source.into(), keys)
+    }
+
+    /// Keeps the rows without a matching row in `source`, rendering
+    /// `not exists`. See [Select::where_exists_join]. Unlike `not in`, a
+    /// null key in `source` doesn't exclude every row.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users").where_not_exists_join("orders", [("users.id", "user_id")])?;
+    /// assert_eq!(
+    ///     "select * from users where not exists (select 1 from orders as t1 where t1.user_id = users.id) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_not_exists_join<'k>(
+        self,
+        source: impl Into<TableType>,
+        keys: impl IntoIterator<Item = (&'k str, &'k str)>,
+    ) -> QResult<Self> {
+        self.exists_join("not exists", source.into(), keys)
+    }
+
+    /// Same as [Select::where_exists_join].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn semi_join<'k>(
+        self,
+        source: impl Into<TableType>,
+        keys: impl IntoIterator<Item = (&'k str, &'k str)>,
+    ) -> QResult<Self> {
+        self.where_exists_join(source, keys)
+    }
+
+    /// Same as [Select::where_not_exists_join].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn anti_join<'k>(
+        self,
+        source: impl Into<TableType>,
+        keys: impl IntoIterator<Item = (&'k str, &'k str)>,
+    ) -> QResult<Self> {
+        self.where_not_exists_join(source, keys)
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn exists_join<'k>(
+        mut self,
+        exists: &str,
+        source: TableType,
+        keys: impl IntoIterator<Item = (&'k str, &'k str)>,
+    ) -> QResult<Self> {
+        let alias = alias::next_alias();
+        let mut on = vec![];
+        for (col, source_col) in keys {
+            located(util::validate_column(col))?;
+            if !util::is_identifier(source_col) {
+                return located(Err(QueryError::InvalidIdentifier(source_col.to_string())));
+            }
+            on.push(format!("{alias}.{source_col} = {col}"));
+        }
+        if on.is_empty() {
+            return located(Err(QueryError::Unsupported(format!(
+                "{exists} join without keys"
+            ))));
+        }
+
+        let (mut from, mut values) = (String::new(), vec![]);
+        source.push_to(&mut from, &mut values);
+        self.record("where");
+        self.where_.push(Where::Simple {
+            expr: format!(
+                "{exists} (select 1 from {from} as {alias} where {})",
+                on.join(" and ")
+            ),
+            values,
+            kind: BoolKind::And,
+        });
+        Ok(self)
+    }
+
+    /// The aliases generated by [Select::inner_join_auto] and
+    /// [Select::left_join_auto], each with the table or subquery sql it
+    /// stands for.
+    ///
+    /// These are unique placeholders, which rendering replaces with `t1`,
+    /// `t2`, ... in order of appearance, so the same query always renders
+    /// the same SQL. Use them in clauses passed back to this query rather
+    /// than in SQL rendered separately.
+    pub fn aliases(&self) -> &[(String, String)] {
+        &self.aliases
+    }
+
+    /// Renders `left join lateral`, letting the subquery reference columns
+    /// of the tables before it. The usual way to get the top N rows per row.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, Select};
+    /// let latest = Select::from("posts")
+    ///     .where_("posts.user_id = users.id")?
+    ///     .order_by("created_at", OrderDir::Desc)
+    ///     .limit(3);
+    /// let q = Select::from("users").left_join_lateral(("(?) as latest on true", latest))?;
+    /// assert_eq!(
+    ///     "select * from users left join lateral (select * from posts where posts.user_id = users.id  order by created_at desc  limit $1) as latest on true",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn left_join_lateral<T, E>(self, join: T) -> QResult<Self>
+    where
+        T: TryInto<Join, Error = E>,
+        QueryError: From<E>,
+    {
+        let join = Join::Lateral(Box::new(located(join.try_into())?));
+        Ok(self.push_join(JoinKind::Left, join))
+    }
+
+    /// Same as [Select::left_join_lateral], rendering `inner join lateral`.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn inner_join_lateral<T, E>(self, join: T) -> QResult<Self>
+    where
+        T: TryInto<Join, Error = E>,
+        QueryError: From<E>,
+    {
+        let join = Join::Lateral(Box::new(located(join.try_into())?));
+        Ok(self.push_join(JoinKind::Inner, join))
+    }
+
+    /// Renders `cross join`. The passed item is only the table or
+    /// subquery, there is no `on` condition.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let days = Select::from("generate_series(1, 7) as d");
+    /// let q = Select::from("users")
+    ///     .cross_join("calendar")?
+    ///     .cross_join(("(?) as days", days))?;
+    /// assert_eq!(
+    ///     "select * from users cross join calendar cross join (select * from generate_series(1, 7) as d) as days",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn cross_join<T, E>(self, join: T) -> QResult<Self>
+    where
+        T: TryInto<Join, Error = E>,
+        QueryError: From<E>,
+    {
+        Ok(self.push_join(JoinKind::Cross, located(join.try_into())?))
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn push_join(mut self, kind: JoinKind, join: Join) -> Self {
+        self.record("join");
+        self.join.push((kind, join));
+        self
+    }
+
+    /// Where expressions are constructed as either strings or tuples.
+    /// The first value in the tuple is the query fragment, and the remaining
+    /// are the values to pass in.
+    ///
+    /// Placeholders are `?` and _not_ the normal Postgres `$1, $2, $3` style.
+    /// They will be converted to the Postgres `$1` style when the query is built.
+    ///
+    /// One, two, or three values can be passed in, in addition to
+    /// the first string value.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// Select::from("my_table").where_("id > 0")?;
+    /// Select::from("my_table").where_(("id > ?", 0))?;
+    /// Select::from("my_table").where_(("id > ? and id < ?", 0, 10))?;
+    /// Select::from("my_table").where_(("id > ? and id < ? and other_col > ?", 0, 10, 5))?;
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    ///
+    /// Where clauses can also be composed:
+    /// ```
+    /// use composable_query_builder2::{Where, Select};
+    /// let sub: Where = ("id > ? and id < ?", 1, 10).try_into()?;
+    /// Select::from("my_table").where_(("id = 20 or (?)", sub))?;
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_<T, E>(mut self, where_: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.record("where");
+        self.where_.push(located(where_.try_into())?);
+        Ok(self)
+    }
+
+    /// Adds the clauses joined with `or`, each in parentheses, e.g. for
+    /// filters built in a loop. Each clause's own `and`/`or` kind is ignored. With
+    /// no clauses nothing can match, and `1 = 0` is rendered.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{QueryError, Select, Where};
+    /// let tags = ["rust", "sql"];
+    /// let clauses = tags
+    ///     .iter()
+    ///     .map(|tag| Where::try_from(("? = any(tags)", *tag)))
+    ///     .collect::<Result<Vec<_>, QueryError>>()?;
+    /// let q = Select::from("posts")
+    ///     .where_(("published = ?", true))?
+    ///     .where_any(clauses);
+    /// assert_eq!(
+    ///     "select * from posts where published = $1 and (($2 = any(tags)) or ($3 = any(tags))) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), QueryError>(())
+    /// ```
+    pub fn where_any(self, clauses: impl IntoIterator<Item = Where>) -> Self {
+        self.where_joined(clauses, "or", "1 = 0")
+    }
+
+    /// Adds the clauses joined with `and`, each in parentheses. With no clauses
+    /// nothing is added. See [Select::where_any].
+    pub fn where_all(self, clauses: impl IntoIterator<Item = Where>) -> Self {
+        self.where_joined(clauses, "and", "")
+    }
+
+    fn where_joined(
+        mut self,
+        clauses: impl IntoIterator<Item = Where>,
+        op: &str,
+        if_empty: &str,
+    ) -> Self {
+        let (mut exprs, mut values) = (vec![], vec![]);
+        for clause in clauses {
+            let Where::Simple {
+                expr, values: v, ..
+            } = clause;
+            // Each clause keeps its own precedence, e.g. an `or` inside
+            // one can't take the neighbouring clauses with it.
+            exprs.push(format!("({expr})"));
+            values.extend(v);
+        }
+        let expr = match exprs.len() {
+            0 if if_empty.is_empty() => return self,
+            0 => if_empty.to_string(),
+            _ => format!("({})", exprs.join(&format!(" {op} "))),
+        };
+        self.where_.push(Where::Simple {
+            expr,
+            values,
+            kind: BoolKind::And,
+        });
+        self
+    }
+
+    /// Renders `exists (subquery)`, merging the subquery's values in order.
+    /// The subquery can refer to this query's tables, for a correlated
+    /// check. See [Select::where_exists_join] for the common case of
+    /// matching on keys.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let recent = Select::from("orders")
+    ///     .select("1")
+    ///     .where_("orders.user_id = users.id")?
+    ///     .where_(("orders.created_at > ?", "2024-01-01"))?;
+    /// let q = Select::from("users")
+    ///     .where_(("users.active = ?", true))?
+    ///     .where_exists(recent);
+    /// assert_eq!(
+    ///     "select * from users where users.active = $1 and exists (select 1 from orders where orders.user_id = users.id and orders.created_at > $2) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_exists(self, subquery: Select) -> Self {
+        self.where_subquery("exists".to_string(), subquery)
+    }
+
+    /// Renders `not exists (subquery)`, see [Select::where_exists].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_not_exists(self, subquery: Select) -> Self {
+        self.where_subquery("not exists".to_string(), subquery)
+    }
+
+    /// Pushes `{lhs} (subquery)` as an and-ed condition, merging the
+    /// subquery's values in order.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn where_subquery(mut self, lhs: String, subquery: Select) -> Self {
+        if let Err(QueryError::DisallowedColumn(column)) = subquery.check_policy() {
+            self.policy_violation.get_or_insert(column);
+        }
+        let (sql, values) = subquery.render();
+        self.record("where");
+        self.where_.push(Where::Simple {
+            expr: format!("{lhs} ({})", sql.trim_end()),
+            values,
+            kind: BoolKind::And,
+        });
+        self
+    }
+
+    /// Adds a negated where condition, see [Where::not].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Select, WhereBuilder};
+    /// let trial = WhereBuilder::new()
+    ///     .where_(("plan = ?", "trial"))?
+    ///     .where_("expires_at < now()")?
+    ///     .build();
+    /// let q = Select::from("accounts").where_not(trial)?;
+    /// assert_eq!(
+    ///     "select * from accounts where not (plan = $1 and expires_at < now()) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_not<T, E>(mut self, where_: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.record("where");
+        self.where_.push(Where::not(located(where_.try_into())?));
+        Ok(self)
+    }
+
+    /// Adds `(expr)::ty op ?`, comparing a cast expression to `value`.
+    /// See [Select::select_cast] for what is validated.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Op, Select};
+    /// let q = Select::from("events").where_cast("metadata->>'count'", "int", Op::Gte, 5)?;
+    /// assert_eq!(
+    ///     "select * from events where (metadata->>'count')::int >= $1 ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_cast(
+        self,
+        expr: impl Into<String>,
+        ty: &str,
+        op: Op,
+        value: impl IntoWhere,
+    ) -> QResult<Self> {
+        let cast = located(cast(expr.into(), ty))?;
+        self.where_((format!("{cast} {} ?", op.as_str()), value))
+    }
+
+    /// Adds the clause returned by `callback` only when `cond` is true.
+    /// The callback isn't called otherwise, see [Select::where_when] to
+    /// pass the clause directly.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_if<T, E>(mut self, cond: bool, callback: impl Fn() -> T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        if cond {
+            self.record("where");
+            self.where_.push(located(callback().try_into())?);
+        }
+        Ok(self)
+    }
+
+    /// Adds the where clause only when `cond` is true, so optional filters
+    /// don't need an `if` around the builder. The clause is validated
+    /// either way, so a placeholder mistake errors even when it's skipped.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let status_id: Option<i64> = None;
+    /// let q = Select::from("users")
+    ///     .where_when(status_id.is_some(), ("status_id = ?", status_id.unwrap_or_default()))?
+    ///     .where_when(true, ("active = ?", true))?;
+    /// assert_eq!("select * from users where active = $1 ", q.into_builder().sql());
+    /// assert!(Select::from("users").where_when(false, ("id = ? and ?", 1)).is_err());
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_when<T, E>(mut self, cond: bool, where_: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        let where_ = located(where_.try_into())?;
+        if cond {
+            self.record("where");
+            self.where_.push(where_);
+        }
+        Ok(self)
+    }
+
+    /// Adds `expr` with `value` bound to its placeholder, or nothing when
+    /// `value` is `None`. Binding `NULL` instead would make `email = ?`
+    /// match no rows at all.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let email: Option<&str> = Some("a@example.com");
+    /// let name: Option<&str> = None;
+    /// let q = Select::from("users")
+    ///     .where_opt("email = ?", email)?
+    ///     .where_opt("name = ?", name)?;
+    /// assert_eq!("select * from users where email = $1 ", q.into_builder().sql());
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_opt<V: IntoWhere>(
+        self,
+        expr: impl Into<String>,
+        value: Option<V>,
+    ) -> QResult<Self> {
+        match value {
+            Some(value) => self.where_((expr, value)),
+            None => Ok(self),
+        }
+    }
+
+    /// Adds the where clause after rewriting comparisons with a bound
+    /// `NULL` to `is null`/`is not null`, see [Where::nullable].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let deleted_at: Option<&str> = None;
+    /// let q = Select::from("users").where_nullable(("deleted_at = ?", deleted_at))?;
+    /// assert_eq!("select * from users where deleted_at is null ", q.into_builder().sql());
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_nullable<T, E>(mut self, where_: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.record("where");
+        self.where_
+            .push(Where::nullable(located(where_.try_into())?));
+        Ok(self)
+    }
+
+    /// Renders `col = ANY(?)`, binding `values` as an array of their type,
+    /// e.g. `text[]` for strings. An empty list matches no rows and renders
+    /// `1 = 0`, as an empty array has no element type to bind.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users")
+    ///     .where_in("id", vec![1, 2, 3])
+    ///     .where_in("email", vec!["a@example.com", "b@example.com"]);
+    /// assert_eq!(
+    ///     "select * from users where id = ANY($1) and email = ANY($2) ",
+    ///     q.into_builder().sql()
+    /// );
+    ///
+    /// let q = Select::from("users").where_in("id", Vec::<i64>::new());
+    /// assert_eq!("select * from users where 1 = 0 ", q.into_builder().sql());
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_in<T: ArrayElement>(self, col: impl Into<String>, values: Vec<T>) -> Self {
+        self.where_array(col.into(), "= ANY", "1 = 0", T::into_array(values))
+    }
+
+    /// Same as [Select::where_in] for a list of [SQLValue]s, which may
+    /// differ in type. Fails with [QueryError::BindTypeMismatch] if they
+    /// do, see [TypedArray::new].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{QueryError, SQLValue, Select};
+    /// let ids = vec![SQLValue::from(1), SQLValue::Null];
+    /// let q = Select::from("users").try_where_in("id", ids)?;
+    /// assert_eq!("select * from users where id = ANY($1) ", q.into_builder().sql());
+    ///
+    /// let mixed = vec![SQLValue::from(1), SQLValue::from("a")];
+    /// assert!(matches!(
+    ///     Select::from("users").try_where_in("id", mixed).unwrap_err().root(),
+    ///     QueryError::BindTypeMismatch { index: 1, .. }
+    /// ));
+    /// # Ok::<(), QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_where_in<T: Into<SQLValue>>(
+        self,
+        col: impl Into<String>,
+        values: Vec<T>,
+    ) -> QResult<Self> {
+        let values = values.into_iter().map(Into::into).collect::<Vec<_>>();
+        let values = located(TypedArray::new(&values))?;
+        Ok(self.where_array(col.into(), "= ANY", "1 = 0", values))
+    }
+
+    /// The negation of [Select::where_in], rendering `col != ALL(?)`. An
+    /// empty list matches every row and renders `1 = 1`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("orders").where_not_in("status_id", vec![4, 5]);
+    /// assert_eq!(
+    ///     "select * from orders where status_id != ALL($1) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_not_in<T: ArrayElement>(self, col: impl Into<String>, values: Vec<T>) -> Self {
+        self.where_array(col.into(), "!= ALL", "1 = 1", T::into_array(values))
+    }
+
+    /// Same as [Select::where_not_in] for a list of [SQLValue]s, see
+    /// [Select::try_where_in].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn try_where_not_in<T: Into<SQLValue>>(
+        self,
+        col: impl Into<String>,
+        values: Vec<T>,
+    ) -> QResult<Self> {
+        let values = values.into_iter().map(Into::into).collect::<Vec<_>>();
+        let values = located(TypedArray::new(&values))?;
+        Ok(self.where_array(col.into(), "!= ALL", "1 = 1", values))
+    }
+
+    /// Renders `col between ? and ?`, which includes both bounds.
+    ///
+    /// Example:
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use composable_query_builder2::Select;
+    /// let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    /// let end = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+    /// let q = Select::from("orders")
+    ///     .where_between("created_at", start, end)
+    ///     .where_not_between("total", 0, 10);
+    /// assert_eq!(
+    ///     "select * from orders where created_at between $1 and $2 and total not between $3 and $4 ",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_between(
+        self,
+        col: impl Into<String>,
+        start: impl Into<SQLValue>,
+        end: impl Into<SQLValue>,
+    ) -> Self {
+        self.where_range(col.into(), "between", start.into(), end.into())
+    }
+
+    /// Renders `col not between ? and ?`, see [Select::where_between].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_not_between(
+        self,
+        col: impl Into<String>,
+        start: impl Into<SQLValue>,
+        end: impl Into<SQLValue>,
+    ) -> Self {
+        self.where_range(col.into(), "not between", start.into(), end.into())
+    }
+
+    /// Renders `col op ?` from a validated column, see
+    /// [Where::from_conditions]. The helpers below cover the usual
+    /// operators.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Op, Select};
+    /// let q = Select::from("orders")
+    ///     .where_eq("status_id", 2)?
+    ///     .where_gt("orders.total", 100.0)?
+    ///     .where_op("currency", Op::NotEq, "EUR")?
+    ///     .where_like("note", "%gift%")?;
+    /// assert_eq!(
+    ///     "select * from orders where status_id = $1 and orders.total > $2 and currency != $3 and note like $4 ",
+    ///     q.into_builder().sql()
+    /// );
+    /// assert!(Select::from("orders").where_eq("id = 1 or id", 2).is_err());
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_op(self, col: &str, op: Op, value: impl Into<SQLValue>) -> QResult<Self> {
+        let w = located(Where::from_conditions(vec![(
+            col.to_string(),
+            op,
+            value.into(),
+        )]))?;
+        self.where_(w)
+    }
+
+    /// Renders `col op any (subquery)`, merging the subquery's values in
+    /// order. `op` is an [Op] or its SQL spelling, e.g. `">"`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Op, Select};
+    /// let refunds = Select::from("refunds")
+    ///     .select("amount")
+    ///     .where_(("created_at > ?", "2024-01-01"))?;
+    /// let q = Select::from("orders")
+    ///     .where_(("status = ?", "paid"))?
+    ///     .where_op_any("amount", ">", refunds.clone())?
+    ///     .where_op_all("orders.amount", Op::Lte, refunds)?;
+    /// assert_eq!(
+    ///     "select * from orders where status = $1 and amount > any (select amount from refunds where created_at > $2) and orders.amount <= all (select amount from refunds where created_at > $3) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_op_any<O, E>(self, col: &str, op: O, subquery: Select) -> QResult<Self>
+    where
+        O: TryInto<Op, Error = E>,
+        QueryError: From<E>,
+    {
+        self.where_quantified(col, located(op.try_into())?, "any", subquery)
+    }
+
+    /// Renders `col op all (subquery)`, see [Select::where_op_any].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_op_all<O, E>(self, col: &str, op: O, subquery: Select) -> QResult<Self>
+    where
+        O: TryInto<Op, Error = E>,
+        QueryError: From<E>,
+    {
+        self.where_quantified(col, located(op.try_into())?, "all", subquery)
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn where_quantified(
+        self,
+        col: &str,
+        op: Op,
+        quantifier: &str,
+        subquery: Select,
+    ) -> QResult<Self> {
+        located(util::validate_column(col))?;
+        self.where_((format!("{col} {} {quantifier} (?)", op.as_str()), subquery))
+    }
+
+    /// Renders `col = ?`, see [Select::where_op].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_eq(self, col: &str, value: impl Into<SQLValue>) -> QResult<Self> {
+        self.where_op(col, Op::Eq, value)
+    }
+
+    /// Renders `col != ?`, see [Select::where_op].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_ne(self, col: &str, value: impl Into<SQLValue>) -> QResult<Self> {
+        self.where_op(col, Op::NotEq, value)
+    }
+
+    /// Renders `col > ?`, see [Select::where_op].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_gt(self, col: &str, value: impl Into<SQLValue>) -> QResult<Self> {
+        self.where_op(col, Op::Gt, value)
+    }
+
+    /// Renders `col >= ?`, see [Select::where_op].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_gte(self, col: &str, value: impl Into<SQLValue>) -> QResult<Self> {
+        self.where_op(col, Op::Gte, value)
+    }
+
+    /// Renders `col < ?`, see [Select::where_op].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_lt(self, col: &str, value: impl Into<SQLValue>) -> QResult<Self> {
+        self.where_op(col, Op::Lt, value)
+    }
+
+    /// Renders `col <= ?`, see [Select::where_op].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_lte(self, col: &str, value: impl Into<SQLValue>) -> QResult<Self> {
+        self.where_op(col, Op::Lte, value)
+    }
+
+    /// Renders `col like ?`, see [Select::where_op].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_like(self, col: &str, pattern: impl Into<SQLValue>) -> QResult<Self> {
+        self.where_op(col, Op::Like, pattern)
+    }
+
+    /// Renders `col ilike ?`, see [Select::where_op].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_ilike(self, col: &str, pattern: impl Into<SQLValue>) -> QResult<Self> {
+        self.where_op(col, Op::ILike, pattern)
+    }
+
+    /// Renders `col is null`. `col` must be a plain or qualified column
+    /// name, anything else returns [QueryError::InvalidIdentifier].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users")
+    ///     .where_null("deleted_at")?
+    ///     .where_not_null("users.email")?;
+    /// assert_eq!(
+    ///     "select * from users where deleted_at is null and users.email is not null ",
+    ///     q.into_builder().sql()
+    /// );
+    /// assert!(Select::from("users").where_null("1 = 1 or deleted_at").is_err());
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_null(self, col: &str) -> QResult<Self> {
+        self.where_is(col, "is null")
+    }
+
+    /// Renders `col is not null`, see [Select::where_null].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_not_null(self, col: &str) -> QResult<Self> {
+        self.where_is(col, "is not null")
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn where_is(mut self, col: &str, is: &str) -> QResult<Self> {
+        located(util::validate_column(col))?;
+        self.record("where");
+        self.where_.push(Where::Simple {
+            expr: format!("{col} {is}"),
+            values: vec![],
+            kind: BoolKind::And,
+        });
+        Ok(self)
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn where_range(mut self, col: String, op: &str, start: SQLValue, end: SQLValue) -> Self {
+        self.record("where");
+        self.where_.push(Where::Simple {
+            expr: format!("{col} {op} ? and ?"),
+            values: vec![start, end],
+            kind: BoolKind::And,
+        });
+        self
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn where_array(mut self, col: String, op: &str, if_empty: &str, values: TypedArray) -> Self {
+        let (expr, values) = if values.is_empty() {
+            (if_empty.to_string(), vec![])
+        } else {
+            (format!("{col} {op}(?)"), vec![SQLValue::Array(values)])
+        };
+        self.record("where");
+        self.where_.push(Where::Simple {
+            expr,
+            values,
+            kind: BoolKind::And,
+        });
+        self
+    }
+
+    /// Renders `col in (subquery)`, merging the subquery's values in order.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let admins = Select::from("memberships")
+    ///     .select("user_id")
+    ///     .where_(("role = ?", "admin"))?;
+    /// let q = Select::from("users")
+    ///     .where_(("active = ?", true))?
+    ///     .where_in_subquery("id", admins);
+    /// assert_eq!(
+    ///     "select * from users where active = $1 and id in (select user_id from memberships where role = $2) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_in_subquery(self, col: impl Into<String>, subquery: Select) -> Self {
+        self.where_subquery(format!("{} in", col.into()), subquery)
+    }
+
+    /// Renders `col not in (subquery)`, see [Select::where_in_subquery].
+    /// Like any `not in`, no rows match if the subquery returns a null.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_not_in_subquery(self, col: impl Into<String>, subquery: Select) -> Self {
+        self.where_subquery(format!("{} not in", col.into()), subquery)
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn or_where<T>(mut self, where_: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = QueryError>,
+    {
+        self.record("where");
+        let mut w = located(where_.try_into())?;
+        w.kind(BoolKind::Or);
+        self.where_.push(w);
+        Ok(self)
+    }
+
+    /// Adds the conditions built by `group` in parentheses, joined to the
+    /// rest of the where clause with `and`. A group with no conditions adds
+    /// nothing.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users")
+    ///     .where_(("active = ?", true))?
+    ///     .where_group(|w| w.where_(("role = ?", "admin"))?.or_where(("karma > ?", 100)))?;
+    /// assert_eq!(
+    ///     "select * from users where active = $1 and (role = $2 or karma > $3) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_group(
+        self,
+        group: impl FnOnce(WhereBuilder) -> QResult<WhereBuilder>,
+    ) -> QResult<Self> {
+        self.push_group(BoolKind::And, group)
+    }
+
+    /// Same as [Select::where_group], joined with `or`.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn or_where_group(
+        self,
+        group: impl FnOnce(WhereBuilder) -> QResult<WhereBuilder>,
+    ) -> QResult<Self> {
+        self.push_group(BoolKind::Or, group)
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn push_group(
+        mut self,
+        kind: BoolKind,
+        group: impl FnOnce(WhereBuilder) -> QResult<WhereBuilder>,
+    ) -> QResult<Self> {
+        let Where::Simple { expr, values, .. } = located(group(WhereBuilder::new()))?.build();
+        if expr.is_empty() {
+            return Ok(self);
+        }
+        self.record("where");
+        self.where_.push(Where::Simple {
+            expr: format!("({expr})"),
+            values,
+            kind,
+        });
+        Ok(self)
+    }
+
+    /// Adds one or more columns to the select statement.
+    ///
+    /// See [`IntoSelect`] for details on what can be passed in.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select(mut self, column: impl IntoSelect) -> Self {
+        self.record("select");
+        self.select.append(&mut column.into_select());
+        self
+    }
+
+    /// Adds `expr as alias` to the select list.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("orders")
+    ///     .select_as("count(*)", "total")
+    ///     .select_all_as([("sum(amount)", "revenue"), ("avg(amount)", "average")]);
+    /// assert_eq!(
+    ///     "select count(*) as total, sum(amount) as revenue, avg(amount) as average from orders",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_as(self, expr: impl Into<String>, alias: impl Into<String>) -> Self {
+        self.select(SelectExpr::new(expr, vec![]).alias(alias))
+    }
+
+    /// Adds each `(expr, alias)` pair, see [Select::select_as].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_all_as<E, A>(self, pairs: impl IntoIterator<Item = (E, A)>) -> Self
+    where
+        E: Into<String>,
+        A: Into<String>,
+    {
+        let exprs = pairs
+            .into_iter()
+            .map(|(expr, alias)| SelectExpr::new(expr, vec![]).alias(alias))
+            .collect::<Vec<_>>();
+        self.select(exprs)
+    }
+
+    /// Adds `count(*) over () as alias`, the number of rows the query
+    /// matches before limit and offset, to every row. Gets a page of rows
+    /// and the total in one query.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users").select("id").with_total_count("total_count").limit(20);
+    /// assert_eq!(
+    ///     "select id, count(*) over () as total_count from users limit $1",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn with_total_count(self, alias: impl Into<String>) -> Self {
+        self.select_as("count(*) over ()", alias)
+    }
+
+    /// Adds every column of `table` in `schema`, qualified with the table
+    /// name. See [Schema].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_all_of(self, schema: &Schema, table: &str) -> QResult<Self> {
+        self.select_all_of_as(schema, table, table)
+    }
+
+    /// Same as [Select::select_all_of], qualifying the columns with `alias`.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_all_of_as(self, schema: &Schema, table: &str, alias: &str) -> QResult<Self> {
+        let cols = schema
+            .columns(table)?
+            .iter()
+            .map(|c| format!("{alias}.{c}"))
+            .collect::<Vec<_>>();
+        Ok(self.select(cols))
+    }
+
+    /// Adds an expression with bound values to the select list. Accepts the
+    /// same forms as [Select::where_].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("players")
+    ///     .select("name")
+    ///     .select_expr(("coalesce(score, ?)", 0))?
+    ///     .where_(("team = ?", "red"))?;
+    /// assert_eq!(
+    ///     "select name, coalesce(score, $1) from players where team = $2 ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_expr<T, E>(mut self, expr: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.record("select");
+        let Where::Simple { expr, values, .. } = located(expr.try_into())?;
+        self.select.push(SelectExpr::new(expr, values));
+        Ok(self)
+    }
+
+    /// Adds `(expr)::ty as alias` to the select list.
+    ///
+    /// `ty` must be a type name, optionally with modifiers or as an array,
+    /// and `alias` a plain identifier. `expr` can't contain placeholders;
+    /// use [Select::select_expr] for casts of bound values.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("events")
+    ///     .select_cast("metadata->>'count'", "int", "count")?
+    ///     .select_cast("amount", "numeric(10, 2)", "amount")?;
+    /// assert_eq!(
+    ///     "select (metadata->>'count')::int as count, (amount)::numeric(10, 2) as amount from events",
+    ///     q.into_builder().sql()
+    /// );
+    /// assert!(Select::from("events").select_cast("id", "int; drop table events", "id").is_err());
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_cast(self, expr: impl Into<String>, ty: &str, alias: &str) -> QResult<Self> {
+        let cast = located(cast(expr.into(), ty))?;
+        if !util::is_identifier(alias) {
+            return located(Err(QueryError::InvalidIdentifier(alias.to_string())));
+        }
+        Ok(self.select_as(cast, alias))
+    }
+
+    /// Adds a scalar subquery to the select list. The `?` in the text is
+    /// replaced by the subquery, whose values are numbered along with the
+    /// rest of the query.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let orders = Select::from("orders")
+    ///     .select("count(*)")
+    ///     .where_(("orders.user_id = users.id and orders.status = ?", "paid"))?;
+    /// let q = Select::from("users")
+    ///     .select("users.id")
+    ///     .select_subquery(("(?) as order_count", orders))?
+    ///     .where_(("users.active = ?", true))?;
+    /// assert_eq!(
+    ///     "select users.id, (select count(*) from orders where orders.user_id = users.id and orders.status = $1) as order_count from users where users.active = $2 ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_subquery<S: Into<String>>(self, (expr, select): (S, Select)) -> QResult<Self> {
+        self.select_expr((expr.into(), select))
+    }
+
+    /// Adds a window function call to the select list.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, Partition, Select};
+    /// let q = Select::from("posts").select_window(
+    ///     "row_number()",
+    ///     Partition::by("user_id").order_by("created_at", OrderDir::Desc),
+    ///     "rn",
+    /// );
+    /// assert_eq!(
+    ///     "select row_number() over (partition by user_id order by created_at desc) as rn from posts",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_window(
+        mut self,
+        func: impl Into<String>,
+        window: Partition,
+        alias: impl Into<String>,
+    ) -> Self {
+        self.record("select");
+        let expr = format!("{} over ({})", func.into(), window.to_sql());
+        self.select.push(SelectExpr::new(expr, vec![]).alias(alias));
+        self
+    }
+
+    pub fn group_by(mut self, group_by: impl IntoGroupBy) -> Self {
+        self.group_by = Some(group_by.into_group_by());
+        self
+    }
+
+    /// Renders `group by rollup (a, b)`, which adds subtotal rows for each
+    /// prefix of the passed columns plus a grand total row.
+    pub fn group_by_rollup(mut self, group_by: impl IntoGroupBy) -> Self {
+        self.group_by = Some(format!("rollup ({})", group_by.into_group_by()));
+        self
+    }
+
+    /// Renders `group by cube (a, b)`, which adds subtotal rows for every
+    /// combination of the passed columns.
+    pub fn group_by_cube(mut self, group_by: impl IntoGroupBy) -> Self {
+        self.group_by = Some(format!("cube ({})", group_by.into_group_by()));
+        self
+    }
+
+    /// Renders `group by grouping sets (...)`, one set per item.
+    ///
+    /// An empty set renders as `()`, which is the grand total row.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("sales")
+    ///     .select(("region", "product", "sum(amount)"))
+    ///     .grouping_sets([vec!["region", "product"], vec!["region"], vec![]]);
+    /// assert_eq!(
+    ///     "select region, product, sum(amount) from sales group by grouping sets ((region, product), (region), ())",
+    ///     q.into_builder().sql().trim()
+    /// );
+    /// ```
+    pub fn grouping_sets<T: IntoGroupBy>(mut self, sets: impl IntoIterator<Item = T>) -> Self {
+        let sets = sets
+            .into_iter()
+            .map(|set| format!("({})", set.into_group_by()))
+            .join(", ");
+        self.group_by = Some(format!("grouping sets ({})", sets));
+        self
+    }
+
+    /// Adds a having condition, filtering groups after aggregation.
+    /// Accepts the same forms as [Select::where_].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("orders")
+    ///     .select(("user_id", "sum(total)"))
+    ///     .where_(("status = ?", "paid"))?
+    ///     .group_by("user_id")
+    ///     .having(("sum(total) > ?", 100))?;
+    /// assert_eq!(
+    ///     "select user_id, sum(total) from orders where status = $1  group by user_id  having sum(total) > $2 ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn having<T, E>(mut self, having: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.record("having");
+        self.having.push(located(having.try_into())?);
+        Ok(self)
+    }
+
+    /// Adds an already built condition as a having condition, so the same
+    /// predicate code can filter rows with [Select::where_] or groups here.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Select, Where, WhereBuilder};
+    /// fn min_total(total: i32) -> Where {
+    ///     WhereBuilder::new().where_(("sum(total) >= ?", total)).unwrap().build()
+    /// }
+    /// let q = Select::from("orders")
+    ///     .select("user_id")
+    ///     .group_by("user_id")
+    ///     .having_from(min_total(50));
+    /// assert_eq!(
+    ///     "select user_id from orders group by user_id  having sum(total) >= $1 ",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    pub fn having_from(mut self, having: Where) -> Self {
+        self.having.push(having);
+        self
+    }
+
+    /// Adds a sort key. Repeated calls add secondary keys, in order.
+    ///
+    /// ## Danger: SQL injection
+    ///
+    /// The passed `col` is _not_ sanitized. If this is taking
+    /// user input, use [Select::order_by_checked] instead.
+    pub fn order_by(mut self, col: impl Into<String>, dir: OrderDir) -> Self {
+        self.order_by.push((col.into(), vec![], dir));
+        self
+    }
+
+    /// Same as [Select::order_by], for a column supplied by the user, e.g.
+    /// an API sort parameter. Returns [QueryError::DisallowedColumn] unless
+    /// `col` is exactly one of `allowed`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, QueryError, Select};
+    /// let allowed = ["name", "created_at"];
+    /// let q = Select::from("users").order_by_checked("name", OrderDir::Asc, &allowed)?;
+    /// assert_eq!("select * from users order by name asc ", q.into_builder().sql());
+    ///
+    /// let res = Select::from("users").order_by_checked("name; drop table users", OrderDir::Asc, &allowed);
+    /// assert!(matches!(res, Err(QueryError::DisallowedColumn(_))));
+    /// # Ok::<(), QueryError>(())
+    /// ```
+    pub fn order_by_checked(self, col: &str, dir: OrderDir, allowed: &[&str]) -> QResult<Self> {
+        if !allowed.contains(&col) {
+            return Err(QueryError::DisallowedColumn(col.to_string()));
+        }
+        Ok(self.order_by(col, dir))
+    }
+
+    /// Adds a sort key compared using `collation`, e.g. `"und-x-icu"` for
+    /// locale aware sorting of names.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, Select};
+    /// let q = Select::from("users").order_by_collate("name", "und-x-icu", OrderDir::Asc);
+    /// assert_eq!(
+    ///     "select * from users order by name collate \"und-x-icu\" asc ",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    pub fn order_by_collate(self, col: impl Into<String>, collation: &str, dir: OrderDir) -> Self {
+        let collation = collation.replace('"', "\"\"");
+        self.order_by(format!("{} collate \"{}\"", col.into(), collation), dir)
+    }
+
+    /// Same as [Select::order_by_collate].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, Select};
+    /// let q = Select::from("people").order_by_collated("name", "de-DE-x-icu", OrderDir::Asc);
+    /// assert_eq!(
+    ///     "select * from people order by name collate \"de-DE-x-icu\" asc ",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    pub fn order_by_collated(self, col: impl Into<String>, collation: &str, dir: OrderDir) -> Self {
+        self.order_by_collate(col, collation, dir)
+    }
+
+    /// Orders rows randomly, rendering `order by random()`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("questions").order_random().limit(5);
+    /// assert_eq!(
+    ///     "select * from questions order by random() asc  limit $1",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    pub fn order_random(self) -> Self {
+        self.order_by("random()", OrderDir::Asc)
+    }
+
+    /// Orders rows in a random order that is the same for every query with
+    /// the same `seed`, by hashing `key_col` with it. Useful for picking
+    /// stable samples or A/B buckets.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users").order_random_seeded("id", "experiment-7")?;
+    /// assert_eq!(
+    ///     "select * from users order by md5(id::text || $1) asc ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn order_random_seeded(self, key_col: &str, seed: impl Into<String>) -> QResult<Self> {
+        located(util::validate_column(key_col))?;
+        let expr = format!("md5({key_col}::text || ?)");
+        self.order_by_expr((expr, seed.into()), OrderDir::Asc)
+    }
+
+    /// Adds each `(col, dir)` sort key, see [Select::order_by].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, Select};
+    /// let q = Select::from("users")
+    ///     .order_by_all(vec![("last_name", OrderDir::Asc), ("first_name", OrderDir::Asc)])
+    ///     .order_by("id", OrderDir::Desc);
+    /// assert_eq!(
+    ///     "select * from users order by last_name asc, first_name asc, id desc ",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    pub fn order_by_all<C: Into<String>>(
+        mut self,
+        keys: impl IntoIterator<Item = (C, OrderDir)>,
+    ) -> Self {
+        self.order_by
+            .extend(keys.into_iter().map(|(col, dir)| (col.into(), vec![], dir)));
+        self
+    }
+
+    /// Adds a sort key computed from an expression with bound values.
+    /// Accepts the same forms as [Select::where_].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, Select};
+    /// let q = Select::from("products")
+    ///     .where_(("name % ?", "lamp"))?
+    ///     .order_by_expr(("similarity(name, ?)", "lamp"), OrderDir::Desc)?
+    ///     .limit(5);
+    /// assert_eq!(
+    ///     "select * from products where name % $1  order by similarity(name, $2) desc  limit $3",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn order_by_expr<T, E>(mut self, expr: T, dir: OrderDir) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.record("order by");
+        let Where::Simple { expr, values, .. } = located(expr.try_into())?;
+        self.order_by.push((expr, values, dir));
+        Ok(self)
+    }
+
+    /// Orders by the column `field` maps to in `fields`, returning
+    /// [QueryError::UnknownField] when it isn't mapped. Safe to use with
+    /// user supplied sort fields.
+    pub fn order_by_field(self, field: &str, dir: OrderDir, fields: &FieldMap) -> QResult<Self> {
+        let col = fields.resolve(field)?.to_string();
+        Ok(self.order_by(col, dir))
+    }
+
+    pub fn limit(mut self, limit: impl IntoOptional<u64>) -> Self {
+        self.limit = limit.into_optional();
+        self.limit_percent = None;
+        self.with_ties = false;
+        self
+    }
+
+    /// Limits the result to the first `n` rows plus any rows tied with the
+    /// last of them, rendering `fetch first ? rows with ties`. Replaces any
+    /// [Select::limit]. Postgres requires an order by to decide ties.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, Select};
+    /// let q = Select::from("scores")
+    ///     .order_by("points", OrderDir::Desc)
+    ///     .fetch_first_with_ties(3);
+    /// assert_eq!(
+    ///     "select * from scores order by points desc  fetch first $1 rows with ties",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    pub fn fetch_first_with_ties(mut self, n: u64) -> Self {
+        self.limit = Some(n);
+        self.limit_percent = None;
+        self.with_ties = true;
+        self
+    }
+
+    /// Seeks to the rows after `cursor`, the key values of the last row
+    /// of the previous page, and orders by the keys. See [Keyset].
+    ///
+    /// The keys replace any existing [Select::order_by], since the seek
+    /// only skips the right rows when the page is sorted by exactly them.
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn after(self, keyset: &Keyset, cursor: impl ValuesRow) -> QResult<Self> {
+        self.seek(keyset, cursor, true)
+    }
+
+    /// Seeks to the rows before `cursor`, the key values of the first row
+    /// of the next page, for paging backwards. The order is reversed, so
+    /// the nearest rows come first; reverse the page before showing it.
+    /// Like [Select::after], the keys replace any existing order.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Keyset, OrderDir, Select};
+    /// let keyset = Keyset::new([("name", OrderDir::Asc), ("id", OrderDir::Desc)])?;
+    /// let q = Select::from("users").before(&keyset, ("m", 7))?.limit(20);
+    /// assert_eq!(
+    ///     "select * from users where ((name < $1) or (name = $2 and id > $3))  order by name desc, id asc  limit $4",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn before(self, keyset: &Keyset, cursor: impl ValuesRow) -> QResult<Self> {
+        self.seek(keyset, cursor, false)
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn seek(self, keyset: &Keyset, cursor: impl ValuesRow, forward: bool) -> QResult<Self> {
+        let seek = located(keyset.seek(cursor, forward))?;
+        let mut q = self.where_(seek)?;
+        q.order_by.clear();
+        Ok(q.order_by_all(keyset.order(forward)))
+    }
+
+    /// Runs the query twice: Postgres doesn't support `fetch first n
+    /// percent rows only`, so this emulates it with a `limit` computed from
+    /// a count over a copy of the same query, which binds every value
+    /// again. Prefer a plain [Select::limit] on large or costly queries.
+    ///
+    /// Limits the result to a percentage of the rows the query would
+    /// otherwise return, rounded up. Replaces any [Select::limit].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("events").where_(("kind = ?", "click"))?.limit_percent_emulated(10.0);
+    /// assert_eq!(
+    ///     "select * from events where kind = $1  limit (select ceil(count(*) * $2 / 100)::bigint from (select * from events where kind = $3) as limit_percent)",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn limit_percent_emulated(mut self, percent: impl IntoOptional<f64>) -> Self {
+        self.limit_percent = percent.into_optional();
+        self.limit = None;
+        self.with_ties = false;
+        self
+    }
+
+    /// Limits the query to `limit` rows unless [Select::limit] or
+    /// [Select::limit_percent_emulated] is also called, before or after.
+    /// Guards APIs against unbounded results when every other clause is
+    /// optional.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let base = Select::from("events").default_limit(100);
+    /// assert_eq!("select * from events limit $1", base.clone().into_builder().sql());
+    ///
+    /// let (_, values) = base.limit(10).parts();
+    /// assert!(matches!(values[..], [composable_query_builder2::SQLValue::U64(10)]));
+    /// ```
+    pub fn default_limit(mut self, limit: u64) -> Self {
+        self.default_limit = Some(limit);
+        self
+    }
+
+    /// An alias for [Select::limit]
+    pub fn take(self, take: impl IntoOptional<u64>) -> Self {
+        self.limit(take)
+    }
+
+    pub fn offset(mut self, offset: impl IntoOptional<u64>) -> Self {
+        self.offset = offset.into_optional();
+        self
+    }
+
+    /// An alais for [Select::offset]
+    pub fn skip(self, skip: impl IntoOptional<u64>) -> Self {
+        self.offset(skip)
+    }
+
+    /// Sets the limit and offset for a 1-based `page`, with the page size
+    /// taken from `limits` for this query's table. Page 0 is treated as
+    /// the first page.
+    ///
+    /// See [PageLimits] for an example.
+    pub fn page_with(
+        self,
+        page: u64,
+        per_page: impl IntoOptional<u64>,
+        limits: &PageLimits,
+    ) -> Self {
+        let per_page = limits.per_page(self.table_name(), per_page.into_optional());
+        self.page(page, per_page)
+    }
+
+    /// The table name [PageLimits] are looked up by.
+    fn table_name(&self) -> &str {
+        match &self.table {
+            Some(TableType::Simple(table)) => table.as_str(),
+            Some(TableType::Table(table)) => table.name.as_str(),
+            _ => "",
+        }
+    }
+
+    /// Sets the limit to `per_page` and the offset for a 1-based `page`.
+    /// Page 0 is treated as the first page, and a `per_page` of 0 as 1.
+    /// The offset is capped at the largest `bigint`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let (_, values) = Select::from("users").page(3, 50).parts();
+    /// assert_eq!(format!("{values:?}"), "[U64(50), U64(100)]");
+    ///
+    /// let (_, values) = Select::from("users").page(0, 50).parts();
+    /// assert_eq!(format!("{values:?}"), "[U64(50), U64(0)]");
+    /// ```
+    pub fn page(self, page: u64, per_page: u64) -> Self {
+        let per_page = per_page.max(1);
+        let offset = page
+            .saturating_sub(1)
+            .saturating_mul(per_page)
+            .min(i64::MAX as u64);
+        self.limit(per_page).offset(offset)
+    }
+
+    /// Applies a [ColumnPolicy] to the select list when rendering.
+    ///
+    /// ## Panics
+    ///
+    /// [Select::into_builder] and [Select::parts] panic if the select list
+    /// of the query or of a subquery would get around its policy, see
+    /// [ColumnPolicy::check]. When the select list comes from user input,
+    /// use [Select::try_into_builder] or [Select::validate], which return
+    /// the error instead, as do the execution helpers.
+    pub fn with_policy(mut self, policy: Arc<ColumnPolicy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Reads columns through a [ValueTransform], e.g. decrypting them.
+    /// Applied after the [ColumnPolicy], so masked columns aren't read.
+    pub fn with_transform(mut self, transform: Arc<dyn ValueTransform>) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// The select list as rendered, with the policy and transform applied.
+    fn select_list(&self, select: Vec<SelectExpr>) -> Vec<SelectExpr> {
+        let select = match &self.policy {
+            Some(policy) => policy.apply_all(select),
+            None => select,
+        };
+        match &self.transform {
+            Some(transform) => transform::read_all(transform.as_ref(), select),
+            None => select,
+        }
+    }
+
+    /// Attaches row level security settings. The execution helpers apply
+    /// them in the same transaction as the query. Other callers can run
+    /// [Select::rls_statements] themselves first.
+    pub fn with_rls(mut self, ctx: RlsContext) -> Self {
+        self.rls = Some(ctx);
+        self
+    }
+
+    /// Runs the query with `schema` as the search path, for schema per
+    /// tenant databases. Tables, joins and subqueries that aren't schema
+    /// qualified resolve to the tenant's tables.
+    ///
+    /// The search path is set after any [RlsContext] settings, in either
+    /// call order, so it takes precedence over a search path in the
+    /// context. It is applied by the execution helpers and is part of the
+    /// cache key. The rendered SQL doesn't change: callers of
+    /// [Select::into_builder] must run [Select::rls_statements] in the same
+    /// transaction first, or the query reads the default schema.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{RlsContext, Select};
+    /// let q = Select::from("invoices")
+    ///     .in_schema("tenant_42")
+    ///     .with_rls(RlsContext::new().role("tenant_user"));
+    /// assert_eq!("select * from invoices", q.clone().into_builder().sql());
+    ///
+    /// let statements = q.rls_statements();
+    /// assert_eq!(2, statements.len());
+    /// assert_eq!("select set_config(?, ?, true)", statements[1].0);
+    /// ```
+    pub fn in_schema(mut self, schema: &str) -> Self {
+        self.schema = Some(schema.to_string());
+        self
+    }
+
+    /// The statements applying the [RlsContext] and [Select::in_schema]
+    /// search path, if any, as `?` sql and values.
+    pub fn rls_statements(&self) -> Vec<(String, Vec<SQLValue>)> {
+        let mut statements = self
+            .rls
+            .as_ref()
+            .map(RlsContext::statements)
+            .unwrap_or_default();
+        if let Some(schema) = &self.schema {
+            statements.extend(RlsContext::new().search_path(schema).statements());
+        }
+        statements
+    }
+
+    /// Renders SQL keywords in upper case, `SELECT ... FROM`, for style
+    /// guides and tools expecting them. Applies to the whole statement,
+    /// subqueries and hand written fragments included. String literals,
+    /// quoted identifiers and comments are left as written.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, Select};
+    /// let q = Select::from("users")
+    ///     .select(("id", "'select me' as label"))
+    ///     .where_(("deleted_at is null and name like ?", "a%"))?
+    ///     .order_by("id", OrderDir::Desc)
+    ///     .uppercase_keywords();
+    /// assert_eq!(
+    ///     "SELECT id, 'select me' AS label FROM users WHERE deleted_at IS NULL AND name LIKE $1  ORDER BY id DESC ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn uppercase_keywords(mut self) -> Self {
+        self.uppercase = true;
+        self
+    }
+
+    /// Adds a clause rendered by downstream code. See [RenderClause].
+    pub fn push_clause(mut self, clause: Box<dyn RenderClause>) -> Self {
+        self.clauses.push(Arc::from(clause));
+        self
+    }
+
+    /// Locks the selected rows against updates and deletes until the end of
+    /// the transaction, rendering `for update`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("jobs")
+    ///     .inner_join("queues on queues.id = jobs.queue_id")?
+    ///     .where_(("jobs.state = ?", "pending"))?
+    ///     .limit(1)
+    ///     .for_update()
+    ///     .of(["jobs"]);
+    /// assert_eq!(
+    ///     "select * from jobs inner join queues on queues.id = jobs.queue_id where jobs.state = $1  limit $2 for update of jobs",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn for_update(mut self) -> Self {
+        self.lock = Some(Lock::new("update"));
+        self
+    }
+
+    /// Same as [Select::for_update], rendering `for share`, which only
+    /// blocks updates and deletes, not other `for share` readers.
+    pub fn for_share(mut self) -> Self {
+        self.lock = Some(Lock::new("share"));
+        self
+    }
+
+    /// Limits the lock from [Select::for_update] or [Select::for_share] to
+    /// rows of the given tables. Does nothing without a lock.
+    pub fn of<T: Into<String>>(mut self, tables: impl IntoIterator<Item = T>) -> Self {
+        if let Some(lock) = &mut self.lock {
+            lock.of.extend(tables.into_iter().map(Into::into));
+        }
+        self
+    }
+
+    /// Skips rows another transaction has locked instead of waiting for
+    /// them. Does nothing without a lock.
+    ///
+    /// With a limit, this is the usual way to claim work from a queue
+    /// table without workers blocking each other.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, Select};
+    /// let q = Select::from("jobs")
+    ///     .where_(("state = ?", "pending"))?
+    ///     .order_by("id", OrderDir::Asc)
+    ///     .limit(1)
+    ///     .for_update()
+    ///     .skip_locked();
+    /// assert_eq!(
+    ///     "select * from jobs where state = $1  order by id asc  limit $2 for update skip locked",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn skip_locked(mut self) -> Self {
+        if let Some(lock) = &mut self.lock {
+            lock.wait = Some("skip locked");
+        }
+        self
+    }
+
+    /// Fails with a lock error instead of waiting for rows another
+    /// transaction has locked. Does nothing without a lock.
+    pub fn nowait(mut self) -> Self {
+        if let Some(lock) = &mut self.lock {
+            lock.wait = Some("nowait");
+        }
+        self
+    }
+
+    /// Names the query for instrumentation. With the `metrics` feature
+    /// enabled, everything recorded in [Select::into_builder] is labelled
+    /// with this tag. Untagged queries are recorded as `untagged`.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// A stable hash of the rendered SQL, ignoring bound values. Two queries
+    /// with the same structure but different values share a fingerprint.
+    pub fn fingerprint(&self) -> u64 {
+        let (sql, _) = self.clone().parts();
+        util::fnv1a(sql.as_bytes())
+    }
+
+    /// Wraps the query so its results can be served from a [QueryCache].
+    #[cfg(feature = "cache")]
+    pub fn cached(self, ttl: std::time::Duration) -> CachedSelect {
+        CachedSelect::new(self, ttl)
+    }
+
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn record(&mut self, _clause: &'static str) {
+        #[cfg(feature = "track-caller")]
+        self.call_sites
+            .push((_clause, std::panic::Location::caller()));
+    }
+
+    fn push_ctes(&mut self, q: &mut String, vals: &mut Vec<SQLValue>) {
+        if self.ctes.is_empty() {
+            return;
+        }
+        for (i, (name, sql, values)) in std::mem::take(&mut self.ctes).into_iter().enumerate() {
+            q.push_str(if i == 0 { "with " } else { ", " });
+            q.push_str(&name);
+            q.push_str(" as (");
+            q.push_str(&sql);
+            q.push(')');
+            vals.extend(values);
+        }
+        q.push(' ');
+    }
+
+    fn push_order_by(&mut self, q: &mut String, vals: &mut Vec<SQLValue>) {
+        if self.order_by.is_empty() {
+            return;
+        }
+        q.push_str(" order by ");
+        for (i, (col, values, dir)) in std::mem::take(&mut self.order_by).into_iter().enumerate() {
+            if i > 0 {
+                q.push_str(", ");
+            }
+            q.push_str(&col);
+            q.push(' ');
+            q.push_str(dir.as_str());
+            vals.extend(values);
+        }
+        q.push(' ');
+    }
+
+    /// The percentage from [Select::limit_percent_emulated] and the query
+    /// to count.
+    /// Taken before rendering, which consumes the query.
+    fn limit_percent_counted(&self) -> Option<(f64, Select)> {
+        self.limit_percent.map(|percent| {
+            let mut counted = self.clone();
+            counted.order_by = vec![];
+            counted.limit = None;
+            counted.offset = None;
+            counted.limit_percent = None;
+            counted.default_limit = None;
+            counted.lock = None;
+            (percent, counted)
+        })
+    }
+
+    fn push_limit(
+        &self,
+        limit_percent: Option<(f64, Select)>,
+        q: &mut String,
+        vals: &mut Vec<SQLValue>,
+    ) {
+        if let Some((percent, counted)) = limit_percent {
+            q.push_str(" limit (select ceil(count(*) * ? / 100)::bigint from (");
+            vals.push(percent.into());
+            counted.render_trimmed_into(q, vals);
+            q.push_str(") as limit_percent)");
+        } else if let Some(limit) = self.limit.or(self.default_limit) {
+            if self.with_ties {
+                q.push_str(" fetch first ? rows with ties");
+            } else {
+                q.push_str(" limit ?");
+            }
+            vals.push(limit.into());
+        }
+    }
+
+    /// A table of each bound value: the clause it belongs to, an excerpt
+    /// of the clause, its placeholder number and its type. Clauses whose
+    /// `?` count doesn't match their values are flagged.
+    ///
+    /// Meant for printing when a query doesn't bind as expected. The
+    /// placeholder mismatch panic of [Select::into_builder] can't use it,
+    /// as the query is consumed by then, and lists the binds of the
+    /// rendered statement instead.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users")
+    ///     .where_(("name ilike ?", "a%"))?
+    ///     .where_(("org_id = ?", 7))?
+    ///     .limit(10);
+    /// assert_eq!(
+    ///     "\
+    /// clause | sql          | bind | type
+    /// where  | name ilike ? | $1   | string
+    /// where  | org_id = ?   | $2   | i32
+    /// limit  | limit ?      | $3   | u64
+    /// ",
+    ///     q.explain_binds()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn explain_binds(&self) -> String {
+        let mut q = self.clone();
+        let mut sections: Vec<(&str, String, Vec<SQLValue>)> = vec![];
+        let mut render = |clause, f: &mut dyn FnMut(&mut String, &mut Vec<SQLValue>)| {
+            let (mut sql, mut values) = (String::new(), vec![]);
+            f(&mut sql, &mut values);
+            sections.push((clause, sql.trim().to_string(), values));
+        };
+
+        let clauses = std::mem::take(&mut q.clauses);
+        let custom = |position| clauses.iter().filter(move |c| c.position() == position);
+        for clause in custom(ClausePosition::Start) {
+            render("custom", &mut |sql, vals| clause.render(sql, vals));
+        }
+        for (name, cte, values) in std::mem::take(&mut q.ctes) {
+            let mut values = Some(values);
+            render("with", &mut |sql, vals| {
+                sql.push_str(&format!("{name} as ({cte})"));
+                vals.extend(values.take().unwrap_or_default());
+            });
+        }
+        let select = std::mem::take(&mut q.select);
+        let select = q.select_list(select);
+        for s in select {
+            let mut s = Some(s);
+            render("select", &mut |sql, vals| {
+                if let Some(s) = s.take() {
+                    s.push_to(sql, vals)
+                }
+            });
+        }
+        for clause in custom(ClausePosition::SelectList) {
+            render("custom", &mut |sql, vals| clause.render(sql, vals));
+        }
+        let joins = std::mem::take(&mut q.join);
+        render("from", &mut |sql, vals| q.push_from(sql, vals));
+        for (kind, join) in joins {
+            let mut join = Some(join);
+            render("join", &mut |sql, vals| {
+                sql.push_str(kind.as_str());
+                sql.push_str(" join ");
+                if let Some(join) = join.take() {
+                    join.push_to(sql, vals)
+                }
+            });
+        }
+        for w in &q.where_ {
+            render("where", &mut |sql, vals| {
+                push_where_list(std::slice::from_ref(w), sql, vals)
+            });
+        }
+        for clause in custom(ClausePosition::AfterWhere) {
+            render("custom", &mut |sql, vals| clause.render(sql, vals));
+        }
+        for h in &q.having {
+            render("having", &mut |sql, vals| {
+                push_where_list(std::slice::from_ref(h), sql, vals)
+            });
+        }
+        render("order by", &mut |sql, vals| q.push_order_by(sql, vals));
+        if q.limit_percent.is_some() || q.limit.or(q.default_limit).is_some() {
+            let mut limit_percent = self.limit_percent_counted();
+            render("limit", &mut |sql, vals| {
+                q.push_limit(limit_percent.take(), sql, vals)
+            });
+        }
+        if let Some(offset) = q.offset {
+            render("offset", &mut |sql, vals| {
+                sql.push_str("offset ?");
+                vals.push(offset.into());
+            });
+        }
+        for clause in custom(ClausePosition::End) {
+            render("custom", &mut |sql, vals| clause.render(sql, vals));
+        }
+
+        let mut rows = vec![[
+            "clause".to_string(),
+            "sql".into(),
+            "bind".into(),
+            "type".into(),
+        ]];
+        let mut bind = 0;
+        for (clause, sql, values) in sections {
+            let excerpt = match sql.char_indices().nth(40) {
+                Some((end, _)) => format!("{}...", &sql[..end]),
+                None => sql.clone(),
+            };
+            let placeholders = sql.matches('?').count();
+            if placeholders != values.len() {
+                let problem = format!("{placeholders} placeholders, {} values", values.len());
+                rows.push([clause.into(), excerpt.clone(), "!".into(), problem]);
+            }
+            for value in values {
+                bind += 1;
+                let row = [
+                    clause.into(),
+                    excerpt.clone(),
+                    format!("${bind}"),
+                    value.type_name().into(),
+                ];
+                rows.push(row);
+            }
+        }
+
+        format_table(rows)
+    }
+
+    /// Lists where each where/join/select clause was added, for the
+    /// placeholder mismatch panic. Empty without the `track-caller` feature.
+    fn call_sites(&self) -> String {
+        #[cfg(feature = "track-caller")]
+        return self
+            .call_sites
+            .iter()
+            .map(|(clause, at)| format!("    {clause} at {at}\n"))
+            .collect();
+        #[cfg(not(feature = "track-caller"))]
+        String::new()
+    }
+
+    pub fn parts(self) -> (String, Vec<SQLValue>) {
+        let mut q = String::new();
+        let mut vals: Vec<SQLValue> = vec![];
+        self.parts_into(&mut q, &mut vals);
+        (q, vals)
+    }
+
+    /// Same as [Select::parts], appending to buffers owned by the caller.
+    ///
+    /// Services building many short-lived queries can clear and reuse the
+    /// same buffers instead of allocating a fresh pair for each query. With
+    /// the `buffer-reuse` feature [Select::into_builder] does this with
+    /// thread-local buffers.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let (mut sql, mut values) = (String::new(), Vec::new());
+    /// for id in 1..=3 {
+    ///     sql.clear();
+    ///     values.clear();
+    ///     Select::from("users").where_(("id = ?", id))?.parts_into(&mut sql, &mut values);
+    ///     assert_eq!("select * from users where id = ? ", sql);
+    /// }
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn parts_into(self, q: &mut String, vals: &mut Vec<SQLValue>) {
+        if let Err(e) = self.check_policy() {
+            panic!("{e}");
+        }
+        let start = q.len();
+        self.render_into(q, vals);
+        alias::renumber(q, start);
+    }
+
+    /// Renders a query nested in another, keeping generated aliases as
+    /// placeholders so the outer statement numbers them all at once.
+    pub(crate) fn render(self) -> (String, Vec<SQLValue>) {
+        let (mut q, mut vals) = (String::new(), vec![]);
+        self.render_into(&mut q, &mut vals);
+        (q, vals)
+    }
+
+    /// Renders a query nested in another straight into the outer buffers,
+    /// without the trailing space.
+    pub(crate) fn render_trimmed_into(self, q: &mut String, vals: &mut Vec<SQLValue>) {
+        self.render_into(q, vals);
+        q.truncate(q.trim_end().len());
+    }
+
+    fn render_into(mut self, q: &mut String, vals: &mut Vec<SQLValue>) {
+        let start = q.len();
+        push_clauses(&self.clauses, ClausePosition::Start, q, vals);
+        self.push_ctes(q, vals);
+        q.push_str("select ");
+
+        let limit_percent = self.limit_percent_counted();
+
+        // Select
+        let select = std::mem::take(&mut self.select);
+        let select = self.select_list(select);
+        let select_is_empty = select.is_empty();
+        if select_is_empty {
+            q.push('*');
+        } else {
+            let l = select.len() - 1;
+            for (last, s) in select.into_iter().enumerate().map(|x| (x.0 == l, x.1)) {
+                s.push_to(q, vals);
+                if !last {
+                    q.push_str(", ");
+                }
+            }
+        }
+        for clause in &self.clauses {
+            if clause.position() == ClausePosition::SelectList {
+                q.push_str(", ");
+                clause.render(q, vals);
+            }
+        }
+
+        // Table
+        match self.table {
+            Some(_) => q.push_str(" from "),
+            // A query of only expressions, e.g. `select exists(...)`.
+            None if !select_is_empty => {}
+            None => panic!("No table specified"),
+        }
+        self.push_from(q, vals);
+
+        // Where
+        if !self.where_.is_empty() {
+            q.push_str(" where ");
+            push_where_list(&self.where_, q, vals);
+        }
+        push_clauses(&self.clauses, ClausePosition::AfterWhere, q, vals);
+
+        // Group by
+        if let Some(group_by) = &self.group_by {
+            q.push_str(" group by ");
+            q.push_str(group_by);
+            q.push(' ');
+        }
+
+        // Having
+        if !self.having.is_empty() {
+            q.push_str(" having ");
+            push_where_list(&self.having, q, vals);
+        }
+
+        // Order by
+        self.push_order_by(q, vals);
+
+        // Limit
+        self.push_limit(limit_percent, q, vals);
+
+        // Offset
+        if let Some(offset) = self.offset {
+            q.push_str(" offset ?");
+            vals.push(offset.into());
+        }
+
+        // Locking
+        if let Some(lock) = self.lock {
+            q.push_str(" for ");
+            q.push_str(lock.strength);
+            if !lock.of.is_empty() {
+                q.push_str(" of ");
+                q.push_str(&lock.of.join(", "));
+            }
+            if let Some(wait) = lock.wait {
+                q.push(' ');
+                q.push_str(wait);
+            }
+        }
+
+        push_clauses(&self.clauses, ClausePosition::End, q, vals);
+
+        if self.uppercase {
+            let upper = util::uppercase_keywords(&q[start..]);
+            q.truncate(start);
+            q.push_str(&upper);
+        }
+        // println!("at the end q is {:?}", q);
+    }
+
+    /// Renders what follows ` from `, up to the where clause: the table,
+    /// its alias and sample, any other tables, and the joins.
+    fn push_from(&mut self, q: &mut String, vals: &mut Vec<SQLValue>) {
+        let bare_subquery = matches!(&self.table, Some(TableType::Complex(t, _)) if t == "(?)");
+        if let Some(table) = self.table.take() {
+            table.push_to(q, vals);
+        }
+        if let Some(alias) = self.alias.take() {
+            q.push_str(" as ");
+            q.push_str(&alias);
+        } else if bare_subquery {
+            // Postgres requires an alias on a subquery in from.
+            q.push_str(" as ");
+            q.push_str(&alias::next_alias());
+        }
+        if let Some(sample) = self.tablesample.take() {
+            q.push_str(" tablesample ");
+            q.push_str(sample.method());
+            q.push_str(" (?)");
+            vals.push(sample.percent().into());
+        }
+        for table in std::mem::take(&mut self.also_from) {
+            q.push_str(", ");
+            table.push_to(q, vals);
+        }
+        for (kind, join) in std::mem::take(&mut self.join) {
+            q.push(' ');
+            q.push_str(kind.as_str());
+            q.push_str(" join ");
+            join.push_to(q, vals);
+        }
+    }
+
+    /// A query counting the rows this one matches, ignoring order, limit
+    /// and offset. Joins and where clauses are kept.
+    ///
+    /// Grouped queries are wrapped, so the count is of groups rather than
+    /// one count per group. The wrapper keeps the [RlsContext], search
+    /// path and tag, so the count runs as the same tenant.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{OrderDir, Select};
+    /// let q = Select::from("users")
+    ///     .where_(("active = ?", true))?
+    ///     .order_by("created_at", OrderDir::Desc)
+    ///     .limit(20);
+    /// assert_eq!(
+    ///     "select count(*) from users where active = $1 ",
+    ///     q.to_count().into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn to_count(&self) -> Select {
+        let mut q = self.clone();
+        q.lock = None;
+        q.default_limit = None;
+        q.order_by = vec![];
+        q.limit = None;
+        q.offset = None;
+        q.limit_percent = None;
+        if q.group_by.is_some() {
+            Select::from(("(?) as counted", q))
+                .select("count(*)")
+                .with_context_of(self)
+        } else {
+            q.select = vec![];
+            q.select("count(*)")
+        }
+    }
+
+    /// A query returning whether this one matches any rows, as a single
+    /// boolean column. Ordering is dropped; limit and offset are kept as
+    /// they change the answer. Like [Select::to_count], the [RlsContext],
+    /// search path and tag carry over.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("users").where_(("email = ?", "a@example.com"))?;
+    /// assert_eq!(
+    ///     "select exists(select 1 from users where email = $1)",
+    ///     q.to_exists().into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn to_exists(&self) -> Select {
+        let mut q = self.clone();
+        q.lock = None;
+        q.default_limit = None;
+        q.order_by = vec![];
+        q.select = vec![];
+        q.policy = None;
+        let q = q.select("1");
+        Select::new()
+            .select_expr(("exists(?)", q))
+            .expect("exists(?) has one placeholder")
+            .with_context_of(self)
+    }
+
+    /// Copies the settings applied around the statement, rather than
+    /// rendered into it, from `inner` to a query wrapping it.
+    fn with_context_of(mut self, inner: &Select) -> Self {
+        self.rls = inner.rls.clone();
+        self.schema = inner.schema.clone();
+        self.tag = inner.tag.clone();
+        self
+    }
+
+    /// Renders `create temp table name as select ...`, keeping the binds,
+    /// to store intermediate results for later queries in the session.
+    /// Temporary tables belong to one connection, so run the statement and
+    /// the queries using it on the same connection, not through a pool.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("orders")
+    ///     .select(("user_id", "sum(total) as total"))
+    ///     .where_(("created_at >= ?", "2024-01-01"))?
+    ///     .group_by("user_id")
+    ///     .into_temp_table("tmp_results")?;
+    /// assert_eq!(
+    ///     "create temp table tmp_results as select user_id, sum(total) as total from orders where created_at >= $1  group by user_id ",
+    ///     q.sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn into_temp_table<'args>(self, name: &str) -> QResult<QueryBuilder<'args, Postgres>> {
+        if !util::is_identifier(name) {
+            return Err(QueryError::InvalidIdentifier(name.to_string()));
+        }
+        let (sql, values) = self.parts();
+        Ok(build_query(
+            &format!("create temp table {name} as {sql}"),
+            values,
+            String::new,
+        ))
+    }
+
+    /// Checks for mistakes Postgres would only report when the query runs,
+    /// or not at all.
+    ///
+    /// Currently catches grouping by a select list alias inside an
+    /// expression (e.g. `rollup (total)`), which Postgres rejects. Group by
+    /// the aliased expression instead. A bare `group by total` is allowed,
+    /// as Postgres resolves it to the alias. Also catches select lists getting around the
+    /// [ColumnPolicy], which would otherwise panic when rendering.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{QueryError, Select};
+    /// let q = Select::from("orders")
+    ///     .select_as("date_trunc('day', created_at)", "day")
+    ///     .select("count(*)")
+    ///     .group_by_rollup("day");
+    /// assert!(matches!(q.validate(), Err(QueryError::GroupByAlias { .. })));
+    /// ```
+    pub fn validate(&self) -> QResult<()> {
+        self.check_policy()?;
+        let Some(group_by) = &self.group_by else {
+            return Ok(());
+        };
+        // The names inside each grouping expression, such as rollup/cube/
+        // grouping sets. Top level elements that are just a name are left
+        // out, as they may name an alias.
+        let mut depth = 0;
+        let names = group_by
+            .split(|c| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                c == ',' && depth == 0
+            })
+            .map(str::trim)
+            .filter(|element| !util::is_identifier(element))
+            .flat_map(|element| element.split([',', '(', ')']).map(str::trim))
+            .collect::<Vec<_>>();
+        for s in &self.select {
+            let Some(alias) = &s.alias else { continue };
+            if alias != s.expr.trim() && names.contains(&alias.as_str()) {
+                return Err(QueryError::GroupByAlias {
+                    alias: alias.clone(),
+                    expr: s.expr.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// See [ColumnPolicy::check]. Subqueries are checked against their
+    /// own policies, including ones already rendered into this query.
+    pub(crate) fn check_policy(&self) -> QResult<()> {
+        if let Some(column) = &self.policy_violation {
+            return Err(QueryError::DisallowedColumn(column.clone()));
+        }
+        if let Some(policy) = &self.policy {
+            policy.check(&self.select)?;
+        }
+        self.table
+            .iter()
+            .chain(&self.also_from)
+            .try_for_each(TableType::check_policy)?;
+        self.join
+            .iter()
+            .try_for_each(|(_, join)| join.check_policy())
+    }
+
+    /// The names of the columns the query returns, as Postgres names them:
+    /// the alias, or the last part of a plain column reference. Other
+    /// expressions are returned as written, and `select *` as `*`.
+    pub fn output_columns(&self) -> Vec<String> {
+        let select = self.select_list(self.select.clone());
+        if select.is_empty() {
+            return vec!["*".to_string()];
+        }
+        select
+            .into_iter()
+            .map(|s| match s.alias {
+                Some(alias) => alias,
+                None => {
+                    let expr = s.expr.trim();
+                    match expr.rsplit_once('.') {
+                        Some((_, name)) if expr.split('.').all(util::is_identifier) => {
+                            name.to_string()
+                        }
+                        _ => expr.to_string(),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Checks that this query can replace `old`, e.g. a stored query
+    /// restored after an upgrade against the version it was saved from.
+    /// Both must bind values of the same types in the same order, and
+    /// return the same [Select::output_columns]. A null bind matches any
+    /// type.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{QueryError, Select};
+    /// let old = Select::from("users").select("id").where_(("team_id = ?", 1))?;
+    ///
+    /// let new = Select::from("users")
+    ///     .select("users.id")
+    ///     .where_(("team_id = ? and active", 2))?;
+    /// new.compatible_with(&old)?;
+    ///
+    /// let new = Select::from("users").select("id").where_(("team_id = ?", "1"))?;
+    /// assert!(matches!(new.compatible_with(&old), Err(QueryError::BindTypeMismatch { .. })));
+    ///
+    /// let new = Select::from("users").select(("id", "email")).where_(("team_id = ?", 1))?;
+    /// assert!(matches!(new.compatible_with(&old), Err(QueryError::IncompatibleColumns { .. })));
+    /// # Ok::<(), QueryError>(())
+    /// ```
+    pub fn compatible_with(&self, old: &Select) -> QResult<()> {
+        let (_, values) = self.clone().parts();
+        let (_, old_values) = old.clone().parts();
+        if values.len() != old_values.len() {
+            return Err(QueryError::ValueCountMismatch {
+                expected: old_values.len(),
+                found: values.len(),
+            });
+        }
+        for (index, (expected, found)) in old_values.iter().zip(&values).enumerate() {
+            let null = matches!(expected, SQLValue::Null) || matches!(found, SQLValue::Null);
+            if !null && expected.type_name() != found.type_name() {
+                return Err(QueryError::BindTypeMismatch {
+                    index,
+                    expected: expected.type_name().to_string(),
+                    found: found.type_name().to_string(),
+                });
+            }
+        }
+
+        let (expected, found) = (old.output_columns(), self.output_columns());
+        if expected != found {
+            return Err(QueryError::IncompatibleColumns { expected, found });
+        }
+        Ok(())
+    }
+
+    /// Splits the rendered query into its shape and values. See
+    /// [QueryShape::rebind] to run the same query with other values.
+    pub fn split(self) -> (QueryShape, Vec<SQLValue>) {
+        let (sql, values) = self.parts();
+        (QueryShape::new(sql, &values), values)
+    }
+
+    /// Renders the query into a sqlx [QueryBuilder]. Only the statement is
+    /// rendered: a [Select::with_rls] context and [Select::in_schema]
+    /// search path need [Select::rls_statements] to be run first, in the
+    /// same transaction, as the execution helpers do.
+    pub fn into_builder<'args>(self) -> QueryBuilder<'args, Postgres> {
+        #[cfg(feature = "metrics")]
+        let (started, tag) = (std::time::Instant::now(), self.tag.clone());
+
+        let call_sites = self.call_sites();
+        let context = || format!("Select::explain_binds lists the binds by clause.\n{call_sites}");
+        #[cfg(feature = "buffer-reuse")]
+        #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+        let (binds, qb) = buffer::with_buffers(|p, v| {
+            self.parts_into(p, v);
+            (v.len(), build_query(p, v.drain(..), context))
+        });
+        #[cfg(not(feature = "buffer-reuse"))]
+        #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+        let (binds, qb) = {
+            let (p, v) = self.parts();
+            (v.len(), build_query(&p, v, context))
+        };
+
+        #[cfg(feature = "metrics")]
+        instrument::record_build(tag.as_deref(), binds, started.elapsed());
+
+        qb
+    }
+
+    /// Same as [Select::into_builder], returning
+    /// [QueryError::DisallowedColumn] rather than panicking when the query
+    /// or a subquery gets around its [ColumnPolicy].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{ColumnPolicy, QueryError, Select};
+    /// use std::sync::Arc;
+    ///
+    /// let policy = Arc::new(ColumnPolicy::new().mask("email", "'***'"));
+    /// let sub = Select::from("users")
+    ///     .select("lower(email)")
+    ///     .with_policy(policy);
+    /// let q = Select::from(("(?) as u", sub));
+    /// assert!(matches!(
+    ///     q.try_into_builder(),
+    ///     Err(QueryError::DisallowedColumn(column)) if column == "email"
+    /// ));
+    /// ```
+    pub fn try_into_builder<'args>(self) -> QResult<QueryBuilder<'args, Postgres>> {
+        self.check_policy()?;
+        Ok(self.into_builder())
+    }
+}
+
+/// Renders `(expr)::ty` after checking `ty` and that `expr` has no
+/// placeholders.
+fn cast(expr: String, ty: &str) -> QResult<String> {
+    util::placeholder_count(&expr, 0)?;
+    util::validate_type(ty)?;
+    Ok(format!("({expr})::{}", ty.trim()))
+}
+
+/// Turns `?` placeholder sql and its values into a [QueryBuilder] with
+/// numbered placeholders. Shared by all of the statement builders.
+///
+/// When the placeholders and values don't line up, the panic message lists
+/// each bind, followed by `context`.
+pub(crate) fn build_query<'args, V>(
+    sql: &str,
+    values: V,
+    context: impl FnOnce() -> String,
+) -> QueryBuilder<'args, Postgres>
+where
+    V: IntoIterator<Item = SQLValue>,
+    V::IntoIter: ExactSizeIterator + AsRef<[SQLValue]>,
+{
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new("");
+    let values = values.into_iter();
+    assert_query_part_and_placeholder_lengths_correct(sql, values.as_ref(), context);
+
+    for pair in sql.split('?').zip_longest(values) {
+        use EitherOrBoth::*;
+        match pair {
+            Both(part, v) => {
+                qb.push(part);
+                v.push_bind(&mut qb);
+            }
+            Left(part) => {
+                qb.push(part);
+            }
+            Right(v) => {
+                v.push_bind(&mut qb);
+            }
+        }
+    }
+    qb
+}
+
+/// There's one more part than placeholders, or the same number when the
+/// query ends with a placeholder (`select * from users limit ?`).
+fn assert_query_part_and_placeholder_lengths_correct(
+    sql: &str,
+    values: &[SQLValue],
+    context: impl FnOnce() -> String,
+) {
+    let parts = sql.matches('?').count() + 1;
+    let placeholders = values.len();
+    assert!(
+        parts == placeholders + 1 || parts == placeholders,
+        "Query has {} placeholders but {} values: {:?}\n{}{}",
+        parts - 1,
+        placeholders,
+        sql,
+        explain_rendered_binds(sql, values),
+        context(),
+    );
+}
+
+/// A table of each placeholder in rendered `?` sql: its number, the sql
+/// leading up to it and the type of the value bound to it, if any.
+fn explain_rendered_binds(sql: &str, values: &[SQLValue]) -> String {
+    let parts = sql.split('?').collect::<Vec<_>>();
+    let placeholders = parts.len() - 1;
+    let mut rows = vec![["bind".to_string(), "sql".into(), "type".into()]];
+    for i in 0..placeholders.max(values.len()) {
+        let excerpt = match parts.get(i).filter(|_| i < placeholders) {
+            Some(part) => {
+                let part = part.trim_start();
+                match part.char_indices().rev().nth(39) {
+                    Some((start, _)) => format!("...{}?", &part[start..]),
+                    None => format!("{part}?"),
                 }
             }
+            None => "-".to_string(),
+        };
+        let ty = values.get(i).map_or("missing", SQLValue::type_name);
+        rows.push([format!("${}", i + 1), excerpt, ty.to_string()]);
+    }
+    format_table(rows)
+}
+
+/// Lays out rows as a table with `|` separated, padded columns.
+fn format_table<const N: usize>(rows: Vec<[String; N]>) -> String {
+    let widths = (0..N)
+        .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+    let mut out = String::new();
+    for row in rows {
+        let cells = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .join(" | ");
+        out.push_str(cells.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#where::WhereBuilder;
+
+    #[test]
+    fn basic_select_star() {
+        let q = Select::from("users").into_builder();
+        let sql = q.sql();
+        assert_eq!("select * from users", sql);
+    }
+
+    #[test]
+    fn basic_select_named() {
+        let q = Select::from("users").select("id").into_builder();
+        let sql = q.sql();
+        assert_eq!("select id from users", sql);
+
+        // Multiple selects append
+        let q = Select::from("users")
+            .select("id")
+            .select("name")
+            .into_builder();
+        let sql = q.sql();
+        assert_eq!("select id, name from users", sql);
+
+        let q = Select::from("users")
+            .select(vec!["id", "name"])
+            .into_builder();
+        let sql = q.sql();
+        assert_eq!("select id, name from users", sql);
+
+        let q = Select::from("users").select(("id", "name")).into_builder();
+        let sql = q.sql();
+        assert_eq!("select id, name from users", sql);
+
+        let q = Select::from("users")
+            .select(("id", "name", "email"))
+            .into_builder();
+        let sql = q.sql();
+        assert_eq!("select id, name, email from users", sql);
+    }
+
+    #[test]
+    fn basic_where() -> QResult<()> {
+        let q = Select::from("users")
+            .where_(("orders > ?", 1))?
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!("select * from users where orders > $1 ", query);
+
+        let q = Select::from("users")
+            .where_(("orders > ?", 1))?
+            .where_(("orders < ?", 10))?
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!(
+            "select * from users where orders > $1 and orders < $2 ",
+            query
+        );
+
+        let q = Select::from("users")
+            .where_(("orders > ? and orders < ?", 1, 10))?
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!(
+            "select * from users where orders > $1 and orders < $2 ",
+            query
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn basic_where_in() {
+        let q = Select::from("users")
+            .where_in("id", vec![1, 2, 3])
+            .into_builder();
+        let sql = q.sql();
+        assert_eq!("select * from users where id = ANY($1) ", sql);
+    }
+
+    #[test]
+    fn where_in_binds_typed_arrays() -> QResult<()> {
+        let id = uuid::Uuid::nil();
+        let (_, values) = Select::from("users")
+            .where_in("email", vec!["a@example.com".to_string()])
+            .where_in("id", vec![1_i64, 2])
+            .where_in("account_id", vec![id])
+            .parts();
+        assert!(matches!(&values[0], SQLValue::Array(TypedArray::String(v)) if v.len() == 1));
+        assert!(matches!(&values[1], SQLValue::Array(TypedArray::I64(_))));
+        assert!(matches!(&values[2], SQLValue::Array(TypedArray::Uuid(v)) if v == &[Some(id)]));
+
+        let with_null = [SQLValue::Null, "a".into()];
+        assert!(
+            matches!(TypedArray::new(&with_null)?, TypedArray::String(v) if v == [None, Some("a".to_string())])
+        );
+        let mixed = vec![SQLValue::Null, "a".into(), 1.into()];
+        assert!(matches!(
+            TypedArray::new(&mixed),
+            Err(QueryError::BindTypeMismatch { index: 2, .. })
+        ));
+        let err = Select::from("users")
+            .try_where_not_in("id", mixed)
+            .unwrap_err();
+        assert!(matches!(
+            err.root(),
+            QueryError::BindTypeMismatch { index: 2, expected, found }
+                if expected == "string" && found == "i32"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn where_in_subquery_merges_binds() -> QResult<()> {
+        let banned = Select::from("bans")
+            .select("user_id")
+            .where_(("until > ?", "2024-01-01"))?;
+        let (sql, values) = Select::from("users")
+            .where_(("team_id = ?", 1))?
+            .where_not_in_subquery("id", banned)
+            .where_(("age > ?", 18))?
+            .parts();
+        assert_eq!(
+            "select * from users where team_id = ? and id not in (select user_id from bans where until > ?) and age > ? ",
+            sql
+        );
+        assert!(matches!(
+            &values[..],
+            [SQLValue::I32(1), SQLValue::String(_), SQLValue::I32(18)]
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn empty_in_lists_are_constant() -> QResult<()> {
+        let (sql, values) = Select::from("users")
+            .where_in("id", Vec::<i64>::new())
+            .where_not_in("email", Vec::<String>::new())
+            .parts();
+        assert_eq!("select * from users where 1 = 0 and 1 = 1 ", sql);
+        assert!(values.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn where_not_in_binds_one_array() -> QResult<()> {
+        let (sql, values) = Select::from("users")
+            .where_in("team_id", vec![1, 2])
+            .where_not_in("id", vec![3])
+            .parts();
+        assert_eq!(
+            "select * from users where team_id = ANY(?) and id != ALL(?) ",
+            sql
+        );
+        assert!(
+            matches!(&values[..], [SQLValue::Array(a), SQLValue::Array(b)] if a.len() == 2 && matches!(b, TypedArray::I32(v) if v == &[Some(3)]))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn triple_where_different_types() -> QResult<()> {
+        let q = Select::from("users")
+            .where_(("(orders > ? and orders < ?) or sales > ?", 10, 100, 123.45))?
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!(
+            "select * from users where (orders > $1 and orders < $2) or sales > $3 ",
+            query
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn or_where_works() -> QResult<()> {
+        let q = Select::from("users")
+            .or_where(("status_id = ?", 1))?
+            .or_where(("status_id = ?", 2))?
+            .or_where(("status_id = ?", 3))?
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!(
+            "select * from users where status_id = $1 or status_id = $2 or status_id = $3 ",
+            query
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn mixed_and_or_where() -> QResult<()> {
+        let q = Select::from("users")
+            .where_(("status_id = ?", 1))?
+            .or_where(("status_id = ?", 2))?
+            .or_where(("status_id = ?", 3))?
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!(
+            "select * from users where status_id = $1 or status_id = $2 or status_id = $3 ",
+            query
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn order_by_works() {
+        let q = Select::from("users")
+            .order_by("email", OrderDir::Desc)
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!("select * from users order by email desc ", query);
+
+        let q = Select::from("users")
+            .order_by("email", OrderDir::Asc)
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!("select * from users order by email asc ", query);
+    }
+
+    #[test]
+    fn order_by_appends_keys() {
+        let q = Select::from("users")
+            .order_by("email", OrderDir::Desc)
+            .order_by("id", OrderDir::Asc);
+        assert_eq!(
+            "select * from users order by email desc, id asc ",
+            q.into_builder().sql()
+        );
+    }
+
+    #[test]
+    fn order_by_expr_binds_in_sequence() -> QResult<()> {
+        let q = Select::from("docs")
+            .select_expr(("ts_rank(body, to_tsquery(?)) as rank", "rust"))?
+            .where_(("lang = ?", "en"))?
+            .order_by_expr(("body <-> ?", "async"), OrderDir::Asc)?
+            .limit(3);
+        let stored = StoredSelect::try_from(q.clone())?;
+        let json = serde_json::to_string(&stored).unwrap();
+        let restored: StoredSelect = serde_json::from_str(&json).unwrap();
+
+        for q in [q, restored.into_select()] {
+            let (sql, values) = q.parts();
+            assert_eq!(
+                "select ts_rank(body, to_tsquery(?)) as rank from docs where lang = ?  order by body <-> ? asc  limit ?",
+                sql
+            );
+            assert!(matches!(
+                &values[..],
+                [SQLValue::String(a), SQLValue::String(b), SQLValue::String(c), SQLValue::U64(3)]
+                    if a == "rust" && b == "en" && c == "async"
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cast_validation() {
+        for ty in [
+            "int",
+            "double precision",
+            "varchar(255)",
+            "numeric(10,2)",
+            "text[]",
+            "pg_catalog.int8",
+        ] {
+            assert!(util::validate_type(ty).is_ok(), "{ty}");
+        }
+        for ty in ["", "int)", "numeric(a)", "int --", "text[]; select 1"] {
+            assert!(util::validate_type(ty).is_err(), "{ty}");
+        }
+
+        let err = Select::from("events")
+            .select_cast("coalesce(x, ?)", "int", "x")
+            .unwrap_err();
+        assert!(matches!(
+            err.root(),
+            QueryError::IncorrectPlaceholderCount(..)
+        ));
+        let err = Select::from("events")
+            .select_cast("x", "int", "x y")
+            .unwrap_err();
+        assert!(matches!(err.root(), QueryError::InvalidIdentifier(_)));
+    }
+
+    #[test]
+    fn array_subscripts() -> QResult<()> {
+        let q = Select::from("readings")
+            .order_by_expr(array::index("samples", 2)?, OrderDir::Desc)?
+            .where_(("cardinality(?) = ?", array::slice("samples", 1, 4)?, 4))?;
+        let (sql, values) = q.parts();
+        assert_eq!(
+            "select * from readings where cardinality(samples[?:?]) = ?  order by samples[?] desc ",
+            sql
+        );
+        assert!(matches!(
+            values[..],
+            [
+                SQLValue::I32(1),
+                SQLValue::I32(4),
+                SQLValue::I32(4),
+                SQLValue::I32(2)
+            ]
+        ));
+        assert!(array::index("samples]; drop table readings; --", 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn where_shared_with_having() -> QResult<()> {
+        let not_test: Where = ("merchant_id <> ?", 7).try_into()?;
+        let rows = Select::from("payments").where_(not_test.clone())?;
+        let groups = Select::from("payments")
+            .select(("merchant_id", "max(amount)"))
+            .group_by("merchant_id")
+            .having_from(not_test);
+        assert_eq!(
+            "select * from payments where merchant_id <> $1 ",
+            rows.into_builder().sql()
+        );
+        assert_eq!(
+            "select count(*) from (select merchant_id, max(amount) from payments group by merchant_id  having merchant_id <> $1 ) as counted",
+            groups.to_count().into_builder().sql()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn order_dir_parsing() {
+        assert!(matches!("Descending".parse(), Ok(OrderDir::Desc)));
+        assert!(matches!(" asc ".parse(), Ok(OrderDir::Asc)));
+        assert!(matches!(
+            "up".parse::<OrderDir>(),
+            Err(QueryError::UnknownOrderDir(_))
+        ));
+
+        let json = serde_json::to_string(&OrderDir::Desc).unwrap();
+        assert_eq!(OrderDir::Desc, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn order_by_collated_per_key() {
+        let q = Select::from("people")
+            .order_by_collated("last_name", "de-DE-x-icu", OrderDir::Asc)
+            .order_by("id", OrderDir::Asc);
+        assert_eq!(
+            "select * from people order by last_name collate \"de-DE-x-icu\" asc, id asc ",
+            q.into_builder().sql()
+        );
+    }
+
+    #[test]
+    fn keyset_pagination() -> QResult<()> {
+        let keyset = Keyset::new([("id", OrderDir::Asc)])?;
+        let q = Select::from("events")
+            .where_(("kind = ?", "click"))?
+            .after(&keyset, (100,))?
+            .limit(10);
+        assert_eq!(
+            "select * from events where kind = $1 and (id) > ($2)  order by id asc  limit $3",
+            q.into_builder().sql()
+        );
+
+        let err = Select::from("events").after(&keyset, (1, 2)).unwrap_err();
+        assert!(matches!(
+            err.root(),
+            QueryError::ValueCountMismatch {
+                expected: 1,
+                found: 2
+            }
+        ));
+        assert!(Keyset::new([("id desc", OrderDir::Asc)]).is_err());
+        assert!(Keyset::new(Vec::<(&str, OrderDir)>::new()).is_err());
+
+        let q = Select::from("events")
+            .order_by("kind", OrderDir::Desc)
+            .before(&keyset, (100,))?;
+        assert_eq!(
+            "select * from events where (id) < ($1)  order by id desc ",
+            q.into_builder().sql()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keyset_pagination_with_nullable_key() -> QResult<()> {
+        let keyset =
+            Keyset::new([("score", OrderDir::Desc), ("id", OrderDir::Desc)])?.nullable("score")?;
+        let (sql, values) = Select::from("players")
+            .before(&keyset, (SQLValue::Null, 5))?
+            .parts();
+        assert_eq!(
+            "select * from players where ((score is not null) or (score is null and id > ?))  order by score is null desc, score asc, id asc ",
+            sql
+        );
+        assert!(matches!(&values[..], [SQLValue::I32(5)]));
+
+        let (sql, values) = Select::from("players").before(&keyset, (90, 5))?.parts();
+        assert_eq!(
+            "select * from players where ((score > ?) or (score = ? and id > ?))  order by score is null desc, score asc, id asc ",
+            sql
+        );
+        assert_eq!(3, values.len());
+
+        let only_nullable = Keyset::new([("score", OrderDir::Asc)])?.nullable("score")?;
+        let (sql, _) = Select::from("players")
+            .after(&only_nullable, (SQLValue::Null,))?
+            .parts();
+        assert_eq!(
+            "select * from players where false  order by score is null asc, score asc ",
+            sql
+        );
+        assert!(matches!(
+            keyset.nullable("rank"),
+            Err(QueryError::UnknownField(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn order_by_mapped_field() -> QResult<()> {
+        let fields = FieldMap::new().field("createdAt", "created_at");
+        let q = Select::from("users").order_by_field("createdAt", OrderDir::Asc, &fields)?;
+        assert_eq!(
+            "select * from users order by created_at asc ",
+            q.into_builder().sql()
+        );
+
+        let res = Select::from("users").order_by_field("created_at", OrderDir::Asc, &fields);
+        assert!(matches!(res, Err(QueryError::UnknownField(f)) if f == "created_at"));
+        Ok(())
+    }
+
+    #[test]
+    fn limit() {
+        let q = Select::from("users").limit(10).into_builder();
+        let query = q.sql();
+
+        assert_eq!("select * from users limit $1", query);
+
+        let q = Select::from("users").limit(Some(10)).into_builder();
+        let query = q.sql();
+
+        assert_eq!("select * from users limit $1", query);
+    }
+
+    #[test]
+    fn limit_percent_emulated() -> QResult<()> {
+        let q = Select::from("users")
+            .limit(5)
+            .limit_percent_emulated(2.5)
+            .offset(10)
+            .order_by("id", OrderDir::Asc);
+        let (sql, vals) = q.parts();
+        assert_eq!(
+            "select * from users order by id asc  limit (select ceil(count(*) * ? / 100)::bigint from (select * from users) as limit_percent) offset ?",
+            sql
+        );
+        assert!(matches!(vals.as_slice(), [SQLValue::F64(p), SQLValue::U64(10)] if *p == 2.5));
+
+        let q = Select::from("users").limit_percent_emulated(2.5).limit(5);
+        assert_eq!("select * from users limit $1", q.into_builder().sql());
+        Ok(())
+    }
+
+    #[test]
+    fn page_limits_per_table() {
+        let limits = PageLimits::new(25, 100).table("audit_log", 10, 50);
+
+        let (_, values) = Select::from("audit_log").page_with(3, 500, &limits).parts();
+        assert!(matches!(
+            values[..],
+            [SQLValue::U64(50), SQLValue::U64(100)]
+        ));
+
+        let (_, values) = Select::from("users").page_with(0, None, &limits).parts();
+        assert!(matches!(values[..], [SQLValue::U64(25), SQLValue::U64(0)]));
+    }
+
+    #[test]
+    fn list_params() -> QResult<()> {
+        let limits = PageLimits::new(20, 100);
+        let params: ListParams = serde_json::from_str(r#"{"page": 2}"#).unwrap();
+        let (sql, values) = Select::from("users")
+            .default_limit(50)
+            .apply_params(&params, &[], &limits)?
+            .parts();
+        assert_eq!("select * from users limit ? offset ?", sql);
+        assert!(matches!(values[..], [SQLValue::U64(50), SQLValue::U64(50)]));
+
+        let params: ListParams = serde_json::from_str(r#"{"page": 2, "per_page": 5000}"#).unwrap();
+        let (_, values) = Select::from("users")
+            .apply_params(&params, &[], &limits)?
+            .parts();
+        assert!(matches!(
+            values[..],
+            [SQLValue::U64(100), SQLValue::U64(100)]
+        ));
+
+        let params = ListParams {
+            page: Some(u64::MAX),
+            ..Default::default()
+        };
+        let res = Select::from("users").apply_params(&params, &[], &limits);
+        assert!(matches!(res, Err(QueryError::Unsupported(_))));
+
+        let params: ListParams = serde_json::from_str(r#"{"dir": "Descending"}"#).unwrap();
+        assert_eq!(Some(OrderDir::Desc), params.dir);
+        let res = serde_json::from_str::<ListParams>(r#"{"sort": "name", "dir": "sideways"}"#);
+        assert!(res
+            .unwrap_err()
+            .to_string()
+            .contains("unknown sort direction"));
+        assert_eq!("\"desc\"", serde_json::to_string(&OrderDir::Desc).unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn offset() {
+        let q = Select::from("users").offset(10).into_builder();
+        let query = q.sql();
+
+        assert_eq!("select * from users offset $1", query);
+
+        let q = Select::from("users").offset(Some(10)).into_builder();
+        let query = q.sql();
+
+        assert_eq!("select * from users offset $1", query);
+    }
+
+    #[test]
+    fn simple_join() -> QResult<()> {
+        let q = Select::from("users")
+            .left_join("posts on users.id = posts.user_id")?
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!(
+            "select * from users left join posts on users.id = posts.user_id",
+            query
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nested_join() -> QResult<()> {
+        let sub = Select::from("posts")
+            .select(("id", "user_id"))
+            .where_(("posts.id = ?", 1))?;
+
+        let q = Select::from("users")
+            .left_join(("(?) as sub on users.id = sub.user_id", sub))?
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!(
+            "select * from users left join (select id, user_id from posts where posts.id = $1) as sub on users.id = sub.user_id",
+            query
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn join_kinds() -> QResult<()> {
+        let sub = Select::from("posts").where_(("posts.id > ?", 1))?;
+        let q = Select::from("users")
+            .inner_join("accounts on accounts.id = users.account_id")?
+            .right_join(("(?) as p on p.user_id = users.id", sub))?
+            .full_join("teams on teams.id = users.team_id")?
+            .into_builder();
+
+        assert_eq!(
+            "select * from users inner join accounts on accounts.id = users.account_id right join (select * from posts where posts.id > $1) as p on p.user_id = users.id full join teams on teams.id = users.team_id",
+            q.sql()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cross_join() -> QResult<()> {
+        let q = Select::from("users")
+            .cross_join("calendar")?
+            .where_(("calendar.day > ?", 3))?
+            .into_builder();
+        assert_eq!(
+            "select * from users cross join calendar where calendar.day > $1 ",
+            q.sql()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lateral_joins() -> QResult<()> {
+        let latest = Select::from("orders")
+            .where_(("orders.user_id = users.id and orders.total > ?", 10))?
+            .limit(1);
+        let q = Select::from("users")
+            .where_(("users.id < ?", 5))?
+            .inner_join_lateral(("(?) as o on true", latest))?;
+        assert_eq!(
+            "select * from users inner join lateral (select * from orders where orders.user_id = users.id and orders.total > $1  limit $2) as o on true where users.id < $3 ",
+            q.into_builder().sql()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn join_using() {
+        let q = Select::from("users")
+            .left_join_using("accounts", vec!["account_id", "region_id"])
+            .inner_join_using("teams", ["team_id"]);
+        assert_eq!(
+            "select * from users left join accounts using (account_id, region_id) inner join teams using (team_id)",
+            q.into_builder().sql()
+        );
+    }
+
+    #[test]
+    fn self_join_with_alias() -> QResult<()> {
+        let q = Select::from("users").left_join_as(
+            "users",
+            "managers",
+            (
+                "managers.id = users.manager_id and managers.active = ?",
+                true,
+            ),
+        )?;
+        assert_eq!(
+            "select * from users left join users as managers on managers.id = users.manager_id and managers.active = $1",
+            q.into_builder().sql()
+        );
+
+        assert!(matches!(
+            Select::from("users").inner_join_as("users", "m; drop", "true"),
+            Err(QueryError::InvalidIdentifier(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn join_builder_binds_on_values() -> QResult<()> {
+        let q = Select::from("users")
+            .select(agg::count_filter(("posts.score > ?", 5))?)
+            .join(
+                Join::inner("posts")
+                    .on("posts.user_id = users.id")?
+                    .on(("posts.created_at > ?", "2024-01-01"))?,
+            )
+            .join(Join::full("teams").on(("teams.id = users.team_id and teams.kind = ?", 2))?)
+            .where_(("users.id > ?", 10))?;
+        assert_eq!(
+            "select count(*) filter (where posts.score > $1) from users inner join posts on posts.user_id = users.id and posts.created_at > $2 full join teams on teams.id = users.team_id and teams.kind = $3 where users.id > $4 ",
+            q.into_builder().sql()
+        );
+
+        assert!(Join::right("posts").on(("a = ? and b = ?", 1)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn join_subquery_with_extra_values() -> QResult<()> {
+        let sub = Select::from("accounts").where_(("accounts.active = ?", true))?;
+        let q = Select::from("users")
+            .where_(("users.id > ?", 1))?
+            .left_join((
+                "(?) as a on a.user_id = users.id and a.kind = ?",
+                sub.clone(),
+                2,
+            ))?
+            .inner_join((
+                "(?) as b on b.user_id = users.id and b.kind between ? and ?",
+                sub,
+                3,
+                4,
+            ))?;
+        assert_eq!(
+            "select * from users left join (select * from accounts where accounts.active = $1) as a on a.user_id = users.id and a.kind = $2 inner join (select * from accounts where accounts.active = $3) as b on b.user_id = users.id and b.kind between $4 and $5 where users.id > $6 ",
+            q.into_builder().sql()
+        );
+
+        let sub = Select::from("accounts");
+        assert!(Select::from("users")
+            .left_join(("(?) as a on a.kind = ? or a.kind = ?", sub, 2))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn nested_where() -> QResult<()> {
+        let w: Where = ("(orders > ? and orders < ?)", 1, 10).try_into()?;
+        let q = Select::from("users")
+            .where_(("id = ? or ?", 1, w))?
+            .into_builder();
+        let query = q.sql();
+
+        assert_eq!(
+            "select * from users where id = $1 or (orders > $2 and orders < $3) ",
+            query
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn where_from_conditions() -> QResult<()> {
+        let w = Where::from_conditions(vec![
+            ("name".to_string(), Op::try_from("ILIKE")?, "%bob%".into()),
+            ("users.age".to_string(), Op::try_from("<>")?, 30.into()),
+        ])?;
+        let q = Select::from("users")
+            .where_(("id > ?", 1))?
+            .where_(w)?
+            .into_builder();
+        assert_eq!(
+            "select * from users where id > $1 and name ilike $2 and users.age != $3 ",
+            q.sql()
+        );
+
+        let q = Select::from("users").where_(Where::from_conditions(vec![])?)?;
+        assert_eq!("select * from users where true ", q.into_builder().sql());
+
+        let res =
+            Where::from_conditions(vec![("id; drop table users".to_string(), Op::Eq, 1.into())]);
+        assert!(matches!(res, Err(QueryError::InvalidIdentifier(_))));
+        assert!(matches!(
+            Op::try_from("~"),
+            Err(QueryError::UnknownOperator(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn search_binds_query_once() -> QResult<()> {
+        let (sql, values) = Select::from("docs")
+            .where_(("lang = ?", "en"))?
+            .search("body_tsv", "postgres")?
+            .select_ts_rank("body_tsv", "rank")?
+            .order_by_ts_rank("body_tsv")?
+            .parts();
+        assert_eq!(
+            "select ts_rank_cd(body_tsv, ts_query) as rank from docs cross join websearch_to_tsquery(?) as ts_query where lang = ? and body_tsv @@ ts_query  order by ts_rank_cd(body_tsv, ts_query) desc ",
+            sql
+        );
+        assert!(matches!(
+            &values[..],
+            [SQLValue::String(q), SQLValue::String(_)] if q == "postgres"
+        ));
+
+        let err = Select::from("docs")
+            .order_by_ts_rank("body_tsv")
+            .unwrap_err();
+        assert!(matches!(err.root(), QueryError::Unsupported(_)));
+        assert!(fts::setweight("title", 'E').is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn comparison_helpers() -> QResult<()> {
+        let (sql, values) = Select::from("users")
+            .where_gte("age", 18)?
+            .where_lt("age", 65)?
+            .where_ilike("users.name", "%bob%")?
+            .parts();
+        assert_eq!(
+            "select * from users where age >= ? and age < ? and users.name ilike ? ",
+            sql
+        );
+        assert_eq!(3, values.len());
+
+        let err = Select::from("users").where_eq("id) or (1", 1).unwrap_err();
+        assert!(matches!(err.root(), QueryError::InvalidIdentifier(_)));
+        Ok(())
+    }
+
+    #[cfg(feature = "track-caller")]
+    #[test]
+    fn errors_point_at_the_calling_line() {
+        let q = Select::from("users");
+        let (err, line) = (q.where_(("id = ? and name = ?", 1)).unwrap_err(), line!());
+        match err {
+            QueryError::Located(at, inner) => {
+                assert_eq!(file!(), at.file());
+                assert_eq!(line, at.line());
+                assert!(matches!(*inner, QueryError::IncorrectPlaceholderCount(..)));
+            }
+            other => panic!("expected a located error, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "track-caller")]
+    #[test]
+    fn subquery_and_range_conditions_record_call_sites() {
+        let line = line!() + 2;
+        let q = Select::from("users")
+            .where_exists(Select::from("orders"))
+            .where_not_in_subquery("id", Select::from("bans").select("user_id"))
+            .where_between("age", 18, 65);
+        let sites = q.call_sites();
+        assert_eq!(3, sites.lines().count());
+        for (i, site) in sites.lines().enumerate() {
+            let at = format!("where at {}:{}:", file!(), line + i as u32);
+            assert!(site.trim_start().starts_with(&at), "{site}");
+        }
+    }
+
+    #[test]
+    fn count_and_exists_keep_the_rls_context() -> QResult<()> {
+        let q = Select::from("events")
+            .select(("user_id", "count(*)"))
+            .group_by("user_id")
+            .with_rls(
+                RlsContext::new()
+                    .role("tenant_user")
+                    .setting("app.tenant_id", 7),
+            )
+            .tag("events_by_user");
+        let statements = q.rls_statements();
+        assert_eq!(2, statements.len());
+
+        for wrapped in [q.to_count(), q.to_exists()] {
+            assert_eq!(
+                format!("{statements:?}"),
+                format!("{:?}", wrapped.rls_statements())
+            );
+            assert_eq!(Some("events_by_user"), wrapped.tag.as_deref());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "exec")]
+    #[test]
+    fn paginated_grouped_count_runs_in_the_tenant_schema() {
+        let page = Select::from("events")
+            .select(("user_id", "count(*)"))
+            .group_by("user_id")
+            .in_schema("tenant_7")
+            .paginate(2, 25);
+        let count = page.count_query();
+        assert_eq!(
+            "select count(*) from (select user_id, count(*) from events group by user_id ) as counted",
+            count.clone().into_builder().sql()
+        );
+        let statements = count.rls_statements();
+        assert_eq!(1, statements.len());
+        assert_eq!("select set_config(?, ?, true)", statements[0].0);
+        assert_eq!(
+            r#"[String("search_path"), String("tenant_7")]"#,
+            format!("{:?}", statements[0].1)
+        );
+    }
+
+    #[test]
+    fn extend_from_shared_template() -> QResult<()> {
+        let base = std::sync::Arc::new(
+            Select::from("users")
+                .where_(("org_id = ?", 7))?
+                .order_by("id", OrderDir::Asc),
+        );
+
+        let handles = (1..=2)
+            .map(|i| {
+                let base = base.clone();
+                std::thread::spawn(move || {
+                    Select::new()
+                        .extend_from(&base)
+                        .where_(("id > ?", i))
+                        .map(|q| q.parts())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            let (sql, values) = handle.join().unwrap()?;
+            assert_eq!(
+                "select * from users where org_id = ? and id > ?  order by id asc ",
+                sql
+            );
+            assert_eq!(2, values.len());
         }
+        Ok(())
+    }
+
+    #[test]
+    fn table_alias() -> QResult<()> {
+        let q = Select::from_as("users", "u")?;
+        assert_eq!(Some("u"), q.qualifier().as_deref());
+        assert_eq!("users.id", Select::from("users").qualify("id"));
+
+        let q = q
+            .clone()
+            .select_prefixed(["id", "email"])
+            .inner_join_as("users", "m", "m.id = u.manager_id")?
+            .where_((format!("{} = ?", q.qualify("id")), 1))?;
+        assert_eq!(
+            "select u.id, u.email from users as u inner join users as m on m.id = u.manager_id where u.id = $1 ",
+            q.into_builder().sql()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_from_tables() -> QResult<()> {
+        let accounts = Select::from("accounts").where_(("active = ?", true))?;
+        let q = Select::from_as("users", "u")?
+            .also_from(("(?) as a", accounts))
+            .where_("a.id = u.account_id")?;
+        assert_eq!(
+            "select * from users as u, (select * from accounts where active = $1 ) as a where a.id = u.account_id ",
+            q.into_builder().sql()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn schema_qualified_tables() {
+        let q = Select::from(Table::new("events").schema("analytics"))
+            .left_join_using(Table::new("Sessions").schema("analytics"), ["session_id"]);
+        assert_eq!(
+            "select * from analytics.events left join analytics.\"Sessions\" using (session_id)",
+            q.into_builder().sql()
+        );
+
+        let q = Select::from(Table::new("events").alias("e")).select_prefixed(["id"]);
+        assert_eq!("select e.id from events as e", q.into_builder().sql());
+
+        let q = Select::from(Table::new("user").schema("Crm")).select_prefixed(["id"]);
+        assert_eq!(
+            "select \"user\".id from \"Crm\".\"user\"",
+            q.into_builder().sql()
+        );
+        let q = Select::from(Table::new("Users")).select_prefixed(["id"]);
+        assert_eq!("select \"Users\".id from \"Users\"", q.into_builder().sql());
+
+        assert!(matches!(
+            Select::from(Table::new("events").alias("e")).alias("x"),
+            Err(QueryError::Unsupported(_))
+        ));
+        let sub = || Select::from("events");
+        assert!(matches!(
+            Select::from(("(?) as e", sub())).alias("x"),
+            Err(QueryError::Unsupported(_))
+        ));
+        let q = Select::from(("(?)", sub())).alias("x").unwrap();
+        assert_eq!(
+            "select * from (select * from events) as x",
+            q.into_builder().sql()
+        );
+    }
+
+    #[test]
+    fn from_values_list() -> QResult<()> {
+        let ids = Select::from_values([(1, "admin"), (2, "owner")], ["user_id", "role"])?;
+        let q = Select::from("users").inner_join(("(?) as r on r.user_id = users.id", ids))?;
+        assert_eq!(
+            "select * from users inner join (select * from (values ($1,$2),($3,$4)) as v(user_id, role)) as r on r.user_id = users.id",
+            q.into_builder().sql()
+        );
+
+        let rows: Vec<Vec<SQLValue>> = vec![vec![1.into()]];
+        assert!(matches!(
+            Select::from_values(rows, ["id", "name"]),
+            Err(QueryError::ValueCountMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+        assert!(Select::from_values(Vec::<(i64,)>::new(), ["id"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn insert_value_expressions_check_placeholders() {
+        let q = Insert::into("posts").value_expr("created_at", "now() - ?");
+        assert!(matches!(q, Err(QueryError::IncorrectPlaceholderCount(..))));
+        let q = Insert::into("posts").value_expr_bound("slug", "lower(?) || ?", "a");
+        assert!(matches!(q, Err(QueryError::IncorrectPlaceholderCount(..))));
+    }
+
+    #[test]
+    fn insert_conflict_where_binds_after_rows() -> QResult<()> {
+        let (sql, values) = Insert::into("tokens")
+            .columns(["user_id", "kind"])
+            .row((1, "reset"))?
+            .on_conflict_do_nothing(["user_id", "kind"])
+            .on_conflict_where(("revoked = ?", false))?
+            .parts();
+        assert_eq!(
+            "insert into tokens (user_id, kind) values (?, ?) on conflict (user_id, kind) where revoked = ? do nothing",
+            sql
+        );
+        assert!(matches!(
+            values[..],
+            [SQLValue::I32(1), SQLValue::String(_), SQLValue::Bool(false)]
+        ));
 
-        // Where
-        if !self.where_.is_empty() {
-            q.push_str(" where ");
-            let last_index = self.where_.len() - 1;
-            for (index, clause) in self.where_.iter().enumerate() {
-                match clause {
-                    Where::Simple {
-                        expr,
-                        values,
-                        kind: _,
-                    } => {
-                        q.push_str(expr);
-                        vals.extend(values.clone());
-                        if index != last_index {
-                            // Get the next kind
-                            let next_kind = self.where_.get(index + 1).unwrap().get_kind();
-                            q.push(' ');
-                            q.push_str(next_kind.as_str());
-                            q.push(' ');
-                        } else {
-                            q.push(' ');
-                        }
-                    }
-                }
-            }
-        }
+        let res = Insert::into("tokens")
+            .value("user_id", 1)
+            .on_conflict_constraint("tokens_pkey")
+            .on_conflict_where(("revoked = ?", false));
+        assert!(matches!(
+            res.unwrap_err().root(),
+            QueryError::Unsupported(_)
+        ));
+        Ok(())
+    }
 
-        // Group by
-        if let Some(group_by) = self.group_by {
-            q.push_str(" group by ");
-            q.push_str(&group_by);
-            q.push(' ');
-        }
+    #[test]
+    fn money_binds_amount_then_currency() -> QResult<()> {
+        let (sql, values) = Insert::into("payments")
+            .columns(["amount_minor", "currency"])
+            .rows([Money::new(500, "EUR"), Money::new(-20, "GBP")])?
+            .parts();
+        assert_eq!(
+            "insert into payments (amount_minor, currency) values (?, ?), (?, ?)",
+            sql
+        );
+        assert!(matches!(values[2], SQLValue::I64(-20)));
 
-        // Order by
-        if let Some((col, dir)) = self.order_by {
-            q.push_str(" order by ");
-            q.push_str(&col);
-            q.push(' ');
-            q.push_str(dir.as_str());
-            q.push(' ');
-        }
+        let (sql, values) = Update::table("payments")
+            .set_money("amount_minor", "currency", Money::new(750, "EUR"))
+            .parts();
+        assert_eq!("update payments set amount_minor = ?, currency = ?", sql);
+        assert!(matches!(&values[..], [SQLValue::I64(750), SQLValue::String(c)] if c == "EUR"));
+        Ok(())
+    }
 
-        // Limit
-        if let Some(limit) = self.limit {
-            q.push_str(" limit ?");
-            vals.push(limit.into());
-        }
+    #[test]
+    fn update_where_version() -> QResult<()> {
+        let (sql, values) = Update::table("accounts")
+            .set("balance", 90)
+            .where_version("accounts.version", 4_i64)?
+            .returning(["version"])
+            .parts();
+        assert_eq!(
+            "update accounts set balance = ?, version = accounts.version + 1 where accounts.version = ? returning version",
+            sql
+        );
+        assert!(matches!(values[..], [SQLValue::I32(90), SQLValue::I64(4)]));
+        assert!(Update::table("accounts").where_version("v; --", 1).is_err());
+        Ok(())
+    }
 
-        // Offset
-        if let Some(offset) = self.offset {
-            q.push_str(" offset ?");
-            vals.push(offset.into());
-        }
+    #[test]
+    fn delete_batch_keeps_conditions_and_returning() -> QResult<()> {
+        let (sql, values) = Delete::from("jobs")
+            .where_(("state = ?", "done"))?
+            .where_(("queue = ?", "mail"))?
+            .returning(["id"])
+            .batch("jobs.id", 500)?
+            .parts();
+        assert_eq!(
+            "delete from jobs where jobs.id in (select jobs.id from jobs where state = ? and queue = ?  limit ?) returning id",
+            sql
+        );
+        assert!(matches!(
+            values[..],
+            [SQLValue::String(_), SQLValue::String(_), SQLValue::U64(500)]
+        ));
+        assert!(Delete::from("jobs").batch("id)", 1).is_err());
+        Ok(())
+    }
 
-        // println!("at the end q is {:?}", q);
+    #[test]
+    fn ctes_merge_binds_in_order() -> QResult<()> {
+        let archived = Update::table("orders")
+            .set("state", "archived")
+            .where_(("placed_at < ?", "2020-01-01"))?
+            .returning(["id"]);
+        let recent = Select::from("orders").where_(("placed_at >= ?", "2024-01-01"))?;
+        let (sql, values) = Select::new()
+            .with("archived", archived)?
+            .with("recent", recent)?
+            .table("recent")
+            .where_((
+                "recent.id not in (select id from archived) and recent.total > ?",
+                10,
+            ))?
+            .parts();
+        assert_eq!(
+            "with archived as (update orders set state = ? where placed_at < ? returning id), recent as (select * from orders where placed_at >= ?) select * from recent where recent.id not in (select id from archived) and recent.total > ? ",
+            sql
+        );
+        assert_eq!(4, values.len());
+        assert!(Select::new().with("bad name", Select::from("t")).is_err());
+        Ok(())
+    }
 
-        (q, vals)
+    #[test]
+    fn insert_rows_and_sync() -> QResult<()> {
+        let q = Insert::into("tags")
+            .columns(["name"])
+            .rows([("rust",), ("sql",)])?
+            .on_conflict_do_nothing(["name"]);
+        assert_eq!(
+            "insert into tags (name) values ($1), ($2) on conflict (name) do nothing",
+            q.into_builder().sql()
+        );
+
+        assert!(matches!(
+            Insert::into("tags").columns(["id", "name"]).row((1,)),
+            Err(QueryError::ValueCountMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            Insert::into("tags")
+                .columns(["name"])
+                .sync_table([("rust",)], ["id"]),
+            Err(QueryError::UnknownField(_))
+        ));
+        assert!(matches!(
+            Insert::into("tags")
+                .columns(["id", "name"])
+                .sync_table(Vec::<(i32, &str)>::new(), ["id"]),
+            Err(QueryError::Unsupported(_))
+        ));
+        Ok(())
     }
 
-    pub fn into_builder<'args>(self) -> QueryBuilder<'args, Postgres> {
-        let mut qb: QueryBuilder<Postgres> = QueryBuilder::new("");
-
-        let (p, v) = self.parts();
-        let parts = p.split('?').collect::<Vec<_>>();
-        assert_query_part_and_placeholder_lengths_correct(&parts, v.len());
-
-        for pair in parts.into_iter().zip_longest(v.into_iter()) {
-            use EitherOrBoth::*;
-            match pair {
-                Both(part, v) => {
-                    qb.push(part);
-                    v.push_bind(&mut qb);
-                }
-                Left(part) => {
-                    qb.push(part);
-                }
-                Right(v) => {
-                    v.push_bind(&mut qb);
-                }
-            }
-        }
+    #[test]
+    fn registered_views() -> QResult<()> {
+        let views = QueryRegistry::new().register(
+            "recent_orders",
+            Select::from("orders").where_(("created_at > ?", "2024-01-01"))?,
+        );
+        let q = views
+            .from_view("recent_orders")?
+            .where_(("recent_orders.total > ?", 100))?;
+        assert_eq!(
+            "select * from (select * from orders where created_at > $1 ) as recent_orders where recent_orders.total > $2 ",
+            q.into_builder().sql()
+        );
+        assert!(matches!(
+            views.join_view("old_orders", "true"),
+            Err(QueryError::UnknownView(_))
+        ));
+        Ok(())
+    }
 
-        qb
+    #[test]
+    fn from_set_returning_function() -> QResult<()> {
+        let q = Select::from_fn(("unnest(?::int8[]) as ids(id)", vec![1_i64, 2, 3]))?
+            .inner_join("users on users.id = ids.id")?;
+        assert_eq!(
+            "select * from unnest($1::int8[]) as ids(id) inner join users on users.id = ids.id",
+            q.into_builder().sql()
+        );
+        let err = Select::from_fn(("generate_series(?, ?)", 1)).unwrap_err();
+        assert!(matches!(
+            err.root(),
+            QueryError::IncorrectPlaceholderCount(..)
+        ));
+        Ok(())
     }
-}
 
-fn assert_query_part_and_placeholder_lengths_correct(query_parts: &[&str], placeholders: usize) {
-    assert!(
-        query_parts.len() == placeholders + 1 || query_parts.len() == placeholders,
-        "Query part count and placeholder count mismatch.
+    #[test]
+    fn generated_join_aliases() -> QResult<()> {
+        let recent = Select::from("orders").where_(("created_at > ?", "2024-01-01"))?;
+        let q = Select::from("users")
+            .left_join_auto(recent.clone(), |a| format!("{a}.user_id = users.id"))?
+            .left_join_auto(recent, |a| format!("{a}.referrer_id = users.id"))?;
 
-The query should always have either exactly the same, or one
-more placeholder than the number of query parts.
+        let aliases = q.aliases().to_vec();
+        assert_eq!(2, aliases.len());
+        assert_ne!(aliases[0].0, aliases[1].0);
+        assert_eq!("(select * from orders where created_at > ? )", aliases[0].1);
 
-In most cases, we'll have something like \"select * from users where id = ? order by id desc\",
-giving 2 query parts and one placeholder.
+        let (sql, values) = q.parts();
+        assert!(sql.contains(") as t1 on t1.user_id = users.id left join ("));
+        assert!(sql.contains(") as t2 on t2.referrer_id"));
+        assert_eq!(2, values.len());
+        Ok(())
+    }
 
-In cases where we have a trailing placeholder, the number of query parts and placeholders
-will be equal: \"select * from users limit $1\"
+    #[test]
+    fn generated_aliases_are_numbered_per_render() -> QResult<()> {
+        let build = || {
+            Select::from("a")
+                .inner_join_auto("b", |b| format!("{b}.a_id = a.id"))
+                .unwrap()
+        };
+        let (first, second) = (build(), build());
+        assert_ne!(first.aliases()[0].0, second.aliases()[0].0);
+        assert_eq!(first.fingerprint(), second.fingerprint());
 
-    {} Query parts: {:?}
-Placeholder count: {}
-",
-        query_parts.len(),
-        query_parts,
-        placeholders,
-    );
-}
+        // Subqueries keep their own aliases distinct from the outer ones.
+        let (sql, _) = build()
+            .where_exists_join(build(), [("a.id", "id")])?
+            .parts();
+        assert_eq!(
+            "select * from a inner join b as t1 on t1.a_id = a.id where exists (select 1 from (select * from a inner join b as t2 on t2.a_id = a.id) as t3 where t3.id = a.id) ",
+            sql
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::r#where::WhereBuilder;
+        let (sql, _) = Select::from(Select::from("a")).parts();
+        assert_eq!("select * from (select * from a) as t1", sql);
+        Ok(())
+    }
 
     #[test]
-    fn basic_select_star() {
-        let q = Select::from("users").into_builder();
-        let sql = q.sql();
-        assert_eq!("select * from users", sql);
+    fn exists_joins_check_keys() -> QResult<()> {
+        let (sql, values) = Select::from("users")
+            .where_(("active = ?", true))?
+            .anti_join(
+                "bans",
+                [("users.id", "user_id"), ("users.team_id", "team_id")],
+            )?
+            .parts();
+        assert_eq!(
+            "select * from users where active = ? and not exists (select 1 from bans as t1 where t1.user_id = users.id and t1.team_id = users.team_id) ",
+            sql
+        );
+        assert_eq!(1, values.len());
+
+        let err = Select::from("users")
+            .semi_join("orders", [("users.id", "o.user_id")])
+            .unwrap_err();
+        assert!(matches!(err.root(), QueryError::InvalidIdentifier(_)));
+        let err = Select::from("users")
+            .where_exists_join("orders", [])
+            .unwrap_err();
+        assert!(matches!(err.root(), QueryError::Unsupported(_)));
+        Ok(())
     }
 
     #[test]
-    fn basic_select_named() {
-        let q = Select::from("users").select("id").into_builder();
-        let sql = q.sql();
-        assert_eq!("select id from users", sql);
+    fn tablesample() -> QResult<()> {
+        let q = Select::from_as("events", "e")?
+            .tablesample(TableSample::Bernoulli(0.5))
+            .where_(("e.kind = ?", "click"))?;
+        assert_eq!(
+            "select * from events as e tablesample bernoulli ($1) where e.kind = $2 ",
+            q.into_builder().sql()
+        );
+        Ok(())
+    }
 
-        // Multiple selects append
+    #[test]
+    fn select_expr_binds_come_first() -> QResult<()> {
+        let q = Select::from("posts")
+            .where_(("author_id = ?", 5))?
+            .select_expr(("title ilike ? as matches", "%rust%"))?;
+        let (sql, values) = q.parts();
+        assert_eq!(
+            "select title ilike ? as matches from posts where author_id = ? ",
+            sql
+        );
+        assert!(matches!(
+            values[..],
+            [SQLValue::String(_), SQLValue::I32(5)]
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn split_and_rebind() -> QResult<()> {
+        let (shape, values) = Select::from("events")
+            .where_(("kind = ? and user_id = ?", "click", 1_i64))?
+            .limit(10)
+            .split();
+        assert_eq!(vec!["string", "i64", "u64"], shape.types);
+        assert_eq!(3, values.len());
+
+        let logged = serde_json::to_string(&shape)?;
+        let shape: QueryShape = serde_json::from_str(&logged)?;
+        let q = shape.rebind(vec!["view".into(), SQLValue::Null, 5_u64.into()])?;
+        assert_eq!(
+            "select * from events where kind = $1 and user_id = $2  limit $3",
+            q.sql()
+        );
+
+        let err = shape.rebind(vec![1_i64.into(), 2_i64.into(), 5_u64.into()]);
+        assert!(matches!(
+            err,
+            Err(QueryError::BindTypeMismatch { index: 0, .. })
+        ));
+
+        // A slot recorded from a null takes a value of any type later.
+        let (shape, _) = Select::from("users")
+            .where_(("manager_id = ?", None::<i64>))?
+            .split();
+        assert_eq!(vec!["null"], shape.types);
+        shape.rebind(vec![7_i64.into()])?;
+        Ok(())
+    }
+
+    #[test]
+    fn value_transform_binds_in_order() {
+        let pgp = Arc::new(PgpSymEncryption::new("k3y").column("ssn"));
+        assert!(!format!("{pgp:?}").contains("k3y"));
+
+        let (sql, values) = Update::table("users")
+            .set("ssn", "123")
+            .set("name", "A")
+            .where_(("id = ?", 1))
+            .unwrap()
+            .with_transform(pgp.clone())
+            .parts();
+        assert_eq!(
+            "update users set ssn = pgp_sym_encrypt(?::text, ?), name = ? where id = ?",
+            sql
+        );
+        assert!(matches!(
+            &values[..],
+            [SQLValue::String(ssn), SQLValue::String(key), SQLValue::String(_), SQLValue::I32(1)]
+                if ssn == "123" && key == "k3y"
+        ));
+
+        let (sql, values) = Update::table("users")
+            .set("ssn", SQLValue::Null)
+            .with_transform(pgp.clone())
+            .parts();
+        assert_eq!("update users set ssn = ?", sql);
+        assert!(matches!(&values[..], [SQLValue::Null]));
+
+        let policy = Arc::new(ColumnPolicy::new().mask("ssn", "'***'"));
         let q = Select::from("users")
-            .select("id")
-            .select("name")
-            .into_builder();
-        let sql = q.sql();
-        assert_eq!("select id, name from users", sql);
+            .select("ssn")
+            .with_policy(policy)
+            .with_transform(pgp);
+        assert_eq!("select '***' as ssn from users", q.into_builder().sql());
+    }
 
+    #[test]
+    fn column_policy() {
+        let policy = Arc::new(
+            ColumnPolicy::new()
+                .mask("email", "regexp_replace(email, '.+@', '***@')")
+                .remove("ssn"),
+        );
         let q = Select::from("users")
-            .select(vec!["id", "name"])
-            .into_builder();
-        let sql = q.sql();
-        assert_eq!("select id, name from users", sql);
+            .select(("u.id", "u.email"))
+            .select_as("ssn", "tax_id")
+            .with_policy(policy.clone());
+        assert_eq!(
+            "select u.id, regexp_replace(email, '.+@', '***@') as email from users",
+            q.into_builder().sql()
+        );
 
-        let q = Select::from("users").select(("id", "name")).into_builder();
-        let sql = q.sql();
-        assert_eq!("select id, name from users", sql);
+        let q = Select::from("users")
+            .select("ssn")
+            .with_policy(policy.clone());
+        assert_eq!("select null from users", q.into_builder().sql());
 
+        for select in ["*", "u.*", "lower(email)", "\"ssn\" || ''", "SSN || 'x'"] {
+            let q = Select::from("users u")
+                .select(select)
+                .with_policy(policy.clone());
+            assert!(
+                matches!(q.validate(), Err(QueryError::DisallowedColumn(_))),
+                "{select}"
+            );
+        }
+        let q = Select::from("users").with_policy(policy.clone());
+        assert!(matches!(q.validate(), Err(QueryError::DisallowedColumn(_))));
         let q = Select::from("users")
-            .select(("id", "name", "email"))
-            .into_builder();
-        let sql = q.sql();
-        assert_eq!("select id, name, email from users", sql);
+            .select("lower(name), 'ssn'")
+            .with_policy(policy);
+        q.validate().unwrap();
     }
 
     #[test]
-    fn basic_where() -> QResult<()> {
+    fn column_policy_and_transform_fold_unquoted_names() {
+        let policy = Arc::new(ColumnPolicy::new().mask("ssn", "'***'"));
         let q = Select::from("users")
-            .where_(("orders > ?", 1))?
-            .into_builder();
-        let query = q.sql();
+            .select(("id", "SSN"))
+            .with_policy(policy.clone());
+        q.validate().unwrap();
+        assert_eq!("select id, '***' as SSN from users", q.into_builder().sql());
 
-        assert_eq!("select * from users where orders > $1 ", query);
+        let q = Select::from("users")
+            .select("upper(Ssn)")
+            .with_policy(policy.clone());
+        assert!(matches!(q.validate(), Err(QueryError::DisallowedColumn(c)) if c == "ssn"));
 
+        // A quoted "SSN" is a different column from ssn.
         let q = Select::from("users")
-            .where_(("orders > ?", 1))?
-            .where_(("orders < ?", 10))?
-            .into_builder();
-        let query = q.sql();
+            .select("users.\"SSN\"")
+            .with_policy(policy);
+        assert_eq!("select users.\"SSN\" from users", q.into_builder().sql());
 
+        let pgp = Arc::new(PgpSymEncryption::new("k3y").column("ssn"));
+        let q = Select::from("users")
+            .select("users.SSN")
+            .with_transform(pgp.clone());
         assert_eq!(
-            "select * from users where orders > $1 and orders < $2 ",
-            query
+            "select pgp_sym_decrypt(users.SSN, $1) as SSN from users",
+            q.into_builder().sql()
         );
+        let (sql, _) = Insert::into("users")
+            .value("SSN", "123")
+            .with_transform(pgp)
+            .parts();
+        assert_eq!(
+            "insert into users (SSN) values (pgp_sym_encrypt(?::text, ?))",
+            sql
+        );
+    }
 
-        let q = Select::from("users")
-            .where_(("orders > ? and orders < ?", 1, 10))?
+    #[test]
+    fn column_policy_violations_in_subqueries_are_errors() -> QResult<()> {
+        let policy = Arc::new(ColumnPolicy::new().mask("email", "'***'"));
+        let sub = || {
+            Select::from("users")
+                .select("upper(email)")
+                .with_policy(policy.clone())
+        };
+        let disallowed = |q: Select| {
+            assert!(matches!(q.validate(), Err(QueryError::DisallowedColumn(_))));
+            assert!(matches!(
+                q.try_into_builder(),
+                Err(QueryError::DisallowedColumn(c)) if c == "email"
+            ));
+        };
+        disallowed(sub());
+        disallowed(Select::from(("(?) as u", sub())));
+        disallowed(Select::from("orders").left_join(("(?) as u on true", sub()))?);
+        disallowed(Select::from("orders").where_exists(sub()));
+        disallowed(Select::new().extend_from(&Select::from("orders").where_exists(sub())));
+
+        assert!(matches!(
+            Select::from("orders").with("u", sub()),
+            Err(QueryError::DisallowedColumn(_))
+        ));
+        let err = Select::from("orders")
+            .where_(("exists (?)", sub()))
+            .unwrap_err();
+        assert!(matches!(err.root(), QueryError::DisallowedColumn(_)));
+
+        let allowed = Select::from("users").select("email").with_policy(policy);
+        let q = Select::from("orders").where_in_subquery("email", allowed);
+        assert_eq!(
+            "select * from orders where email in (select '***' as email from users) ",
+            q.try_into_builder()?.sql()
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "column not allowed: email")]
+    fn column_policy_panics_when_rendering_around_it() {
+        let policy = Arc::new(ColumnPolicy::new().mask("email", "'***'"));
+        Select::from("users")
+            .select("upper(email)")
+            .with_policy(policy)
             .into_builder();
-        let query = q.sql();
+    }
 
+    #[test]
+    fn qualified_columns() -> QResult<()> {
+        let allowed = ["orders.total", "orders.created_at"];
+        let sort = col("orders.total");
+        sort.validate(&allowed)?;
+        assert!(matches!(
+            col("orders.secret").validate(&allowed),
+            Err(QueryError::UnknownField(_))
+        ));
+
+        let q = Select::from("orders")
+            .select(Col::unqualified("Total"))
+            .where_in(Col::new("orders", "id"), vec![1, 2])
+            .order_by(sort, OrderDir::Desc);
         assert_eq!(
-            "select * from users where orders > $1 and orders < $2 ",
-            query
+            "select \"Total\" from orders where orders.id = ANY($1)  order by orders.total desc",
+            q.into_builder().sql().trim()
         );
+        Ok(())
+    }
+
+    #[test]
+    fn fingerprint_allow_list() -> QResult<()> {
+        let search = |name: &str| Select::from("users").where_(("name ilike ?", name.to_string()));
+        let allowed = FingerprintAllowList::new().allow(&search("")?);
 
+        allowed.check(&search("%ann%")?)?;
+        let widened = search("%ann%")?.or_where("true")?;
+        assert!(matches!(
+            allowed.check(&widened),
+            Err(QueryError::NotAllowed(_))
+        ));
         Ok(())
     }
 
     #[test]
-    fn basic_where_in() {
-        let q = Select::from("users")
-            .where_in("id", vec![1, 2, 3])
-            .into_builder();
-        let sql = q.sql();
-        assert_eq!("select * from users where id = ANY($1) ", sql);
+    fn select_scalar_subquery() -> QResult<()> {
+        let latest = Select::from("logins")
+            .select("max(created_at)")
+            .where_("logins.user_id = users.id")?;
+        let (sql, _) = Select::from("users")
+            .select_subquery(("(?) as last_login", latest.clone()))?
+            .parts();
+        assert_eq!(
+            "select (select max(created_at) from logins where logins.user_id = users.id) as last_login from users",
+            sql
+        );
+        assert!(Select::from("users")
+            .select_subquery(("last_login", latest))
+            .is_err());
+        Ok(())
     }
 
     #[test]
-    fn triple_where_different_types() -> QResult<()> {
-        let q = Select::from("users")
-            .where_(("(orders > ? and orders < ?) or sales > ?", 10, 100, 123.45))?
-            .into_builder();
-        let query = q.sql();
+    fn validate_group_by_alias() {
+        let q = Select::from("orders")
+            .select_as("extract(year from created_at)", "year")
+            .select_as("sum(amount)", "revenue");
+        assert!(q
+            .clone()
+            .group_by("extract(year from created_at)")
+            .validate()
+            .is_ok());
 
+        q.clone().group_by("year").validate().unwrap();
+        let err = q
+            .group_by("grouping sets ((year), ())")
+            .validate()
+            .unwrap_err();
         assert_eq!(
-            "select * from users where (orders > $1 and orders < $2) or sales > $3 ",
-            query
+            "cannot group by select alias `year`, group by `extract(year from created_at)` instead",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn to_count() -> QResult<()> {
+        let q = Select::from("posts")
+            .select(("author_id", "count(*)"))
+            .inner_join("users on users.id = posts.author_id")?
+            .where_(("users.active = ?", true))?
+            .group_by("author_id")
+            .limit(10)
+            .offset(20);
+        let (sql, values) = q.to_count().parts();
+        assert_eq!(
+            "select count(*) from (select author_id, count(*) from posts inner join users on users.id = posts.author_id where users.active = ?  group by author_id ) as counted",
+            sql
         );
+        assert_eq!(1, values.len());
         Ok(())
     }
 
     #[test]
-    fn or_where_works() -> QResult<()> {
-        let q = Select::from("users")
-            .or_where(("status_id = ?", 1))?
-            .or_where(("status_id = ?", 2))?
-            .or_where(("status_id = ?", 3))?
-            .into_builder();
-        let query = q.sql();
+    fn select_all_of_schema_table() -> QResult<()> {
+        let schema = Schema::new().table("users", ["id", "email", "created_at"]);
+        let q = Select::from_as("users", "u")?.select_all_of_as(&schema, "users", "u")?;
+        assert_eq!(
+            "select u.id, u.email, u.created_at from users as u",
+            q.into_builder().sql()
+        );
+        assert!(matches!(
+            Select::from("users").select_all_of(&schema, "orders"),
+            Err(QueryError::UnknownTable(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn custom_clauses() -> QResult<()> {
+        #[derive(Debug)]
+        struct Raw(ClausePosition, &'static str, Option<i32>);
+
+        impl RenderClause for Raw {
+            fn position(&self) -> ClausePosition {
+                self.0
+            }
+
+            fn render(&self, sql: &mut String, values: &mut Vec<SQLValue>) {
+                sql.push_str(self.1);
+                values.extend(self.2.map(SQLValue::from));
+            }
+        }
 
+        let q = Select::from("events")
+            .where_(("kind = ?", "click"))?
+            .push_clause(Box::new(Raw(ClausePosition::End, "/* shard ? */", Some(3))))
+            .push_clause(Box::new(Raw(
+                ClausePosition::Start,
+                "/*+ SeqScan(events) */",
+                None,
+            )))
+            .push_clause(Box::new(Raw(
+                ClausePosition::AfterWhere,
+                "and user_id = ?",
+                Some(7),
+            )))
+            .limit(5);
         assert_eq!(
-            "select * from users where status_id = $1 or status_id = $2 or status_id = $3 ",
-            query
+            "/*+ SeqScan(events) */ select * from events where kind = $1  and user_id = $2 limit $3 /* shard $4 */",
+            q.into_builder().sql()
         );
         Ok(())
     }
 
     #[test]
-    fn mixed_and_or_where() -> QResult<()> {
-        let q = Select::from("users")
-            .where_(("status_id = ?", 1))?
-            .or_where(("status_id = ?", 2))?
-            .or_where(("status_id = ?", 3))?
-            .into_builder();
-        let query = q.sql();
+    fn to_exists() -> QResult<()> {
+        let q = Select::from("orders")
+            .select(("id", "total"))
+            .where_(("user_id = ?", 3))?
+            .order_by("created_at", OrderDir::Desc)
+            .offset(9);
+        let (sql, values) = q.to_exists().parts();
+        assert_eq!(
+            "select exists(select 1 from orders where user_id = ?  offset ?)",
+            sql
+        );
+        assert_eq!(2, values.len());
+        Ok(())
+    }
 
+    #[test]
+    fn row_locking() -> QResult<()> {
+        let q = Select::from("accounts")
+            .where_(("id = ?", 1))?
+            .for_share()
+            .nowait();
+        assert_eq!(
+            "select count(*) from accounts where id = $1 ",
+            q.to_count().into_builder().sql()
+        );
+        assert_eq!(
+            "select * from accounts where id = $1  for share nowait",
+            q.into_builder().sql()
+        );
         assert_eq!(
-            "select * from users where status_id = $1 or status_id = $2 or status_id = $3 ",
-            query
+            "select * from accounts",
+            Select::from("accounts")
+                .of(["accounts"])
+                .skip_locked()
+                .into_builder()
+                .sql()
         );
         Ok(())
     }
 
     #[test]
-    fn order_by_works() {
-        let q = Select::from("users")
-            .order_by("email", OrderDir::Desc)
-            .into_builder();
-        let query = q.sql();
-
-        assert_eq!("select * from users order by email desc ", query);
-
-        let q = Select::from("users")
-            .order_by("email", OrderDir::Asc)
-            .into_builder();
-        let query = q.sql();
+    fn stored_select_compatible_after_round_trip() -> QResult<()> {
+        let q = Select::from("orders")
+            .select("orders.id")
+            .select_as("sum(total)", "total")
+            .where_(("status = ?", "paid"))?
+            .group_by("orders.id")
+            .limit(10);
+        let restored = StoredSelect::try_from(q.clone())?.into_select();
+        restored.compatible_with(&q)?;
+        assert_eq!(vec!["id", "total"], restored.output_columns());
 
-        assert_eq!("select * from users order by email asc ", query);
+        let err = Select::from("orders")
+            .select("orders.id")
+            .select_as("sum(total)", "total")
+            .compatible_with(&q)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            QueryError::ValueCountMismatch {
+                expected: 2,
+                found: 0
+            }
+        ));
+        Ok(())
     }
 
     #[test]
-    fn limit() {
-        let q = Select::from("users").limit(10).into_builder();
-        let query = q.sql();
-
-        assert_eq!("select * from users limit $1", query);
+    fn stored_select_v1_compatibility() -> QResult<()> {
+        // Written by the first release of the format, must keep loading.
+        let json = r#"{
+            "version": "1",
+            "select": [{"sql": "count(*)", "alias": "total"}],
+            "from": {"sql": "orders as o tablesample bernoulli (?)", "values": [{"type": "f64", "value": 10.0}]},
+            "where": [
+                {"sql": "o.placed_at >= ?", "values": [{"type": "date", "value": "2024-01-01"}]},
+                {"sql": "o.status = ?", "values": [{"type": "string", "value": "open"}], "or": true}
+            ],
+            "group_by": "o.region",
+            "order_by": [["total", "desc"]],
+            "limit": 10,
+            "lock": {"strength": "share"}
+        }"#;
+        let stored: StoredSelect = serde_json::from_str(json).unwrap();
+        let (sql, values) = stored.into_select().parts();
+        assert_eq!(
+            "select count(*) as total from orders as o tablesample bernoulli (?) where o.placed_at >= ? or o.status = ?  group by o.region  order by total desc  limit ? for share",
+            sql
+        );
+        assert!(matches!(
+            values.as_slice(),
+            [
+                SQLValue::F64(_),
+                SQLValue::Date(_),
+                SQLValue::String(_),
+                SQLValue::U64(10)
+            ]
+        ));
 
-        let q = Select::from("users").limit(Some(10)).into_builder();
-        let query = q.sql();
+        let unknown = serde_json::from_str::<StoredSelect>(r#"{"version": "0"}"#);
+        assert!(unknown.is_err());
 
-        assert_eq!("select * from users limit $1", query);
+        let q = Select::from("users").with_policy(Arc::new(ColumnPolicy::new()));
+        assert!(matches!(
+            StoredSelect::try_from(q),
+            Err(QueryError::Unsupported(_))
+        ));
+        Ok(())
     }
 
     #[test]
-    fn offset() {
-        let q = Select::from("users").offset(10).into_builder();
-        let query = q.sql();
+    fn fetch_first_with_ties() {
+        let q = Select::from("scores")
+            .order_by("points", OrderDir::Desc)
+            .fetch_first_with_ties(3)
+            .offset(3);
+        assert_eq!(
+            "select * from scores order by points desc  fetch first ? rows with ties offset ?",
+            q.clone().parts().0
+        );
+        assert_eq!(
+            "select * from scores order by points desc  limit ? offset ?",
+            q.limit(3).parts().0
+        );
+    }
 
-        assert_eq!("select * from users offset $1", query);
+    #[test]
+    #[should_panic(expected = "$3   | 'x' limit ?                             | missing")]
+    fn placeholder_mismatch_panic_lists_the_binds() {
+        #[derive(Debug)]
+        struct Unbound;
 
-        let q = Select::from("users").offset(Some(10)).into_builder();
-        let query = q.sql();
+        impl RenderClause for Unbound {
+            fn position(&self) -> ClausePosition {
+                ClausePosition::AfterWhere
+            }
 
-        assert_eq!("select * from users offset $1", query);
-    }
+            fn render(&self, sql: &mut String, _values: &mut Vec<SQLValue>) {
+                sql.push_str(" and tags ? 'x'");
+            }
+        }
 
-    #[test]
-    fn simple_join() -> QResult<()> {
-        let q = Select::from("users")
-            .left_join("posts on users.id = posts.user_id")?
+        Select::from("posts")
+            .where_(("author_id = ?", 3))
+            .unwrap()
+            .push_clause(Box::new(Unbound))
+            .limit(10)
             .into_builder();
-        let query = q.sql();
-
-        assert_eq!(
-            "select * from users left join posts on users.id = posts.user_id",
-            query
-        );
-        Ok(())
     }
 
     #[test]
-    fn nested_join() -> QResult<()> {
-        let sub = Select::from("posts")
-            .select(("id", "user_id"))
-            .where_(("posts.id = ?", 1))?;
+    fn explain_binds_flags_mismatched_clauses() -> QResult<()> {
+        #[derive(Debug)]
+        struct Unbound;
 
-        let q = Select::from("users")
-            .left_join(("(?) as sub on users.id = sub.user_id", sub))?
-            .into_builder();
-        let query = q.sql();
+        impl RenderClause for Unbound {
+            fn position(&self) -> ClausePosition {
+                ClausePosition::AfterWhere
+            }
+
+            fn render(&self, sql: &mut String, _values: &mut Vec<SQLValue>) {
+                sql.push_str(" and tags ? 'x'");
+            }
+        }
 
+        let q = Select::from("posts")
+            .select_expr(("similarity(title, ?) as score", "rust"))?
+            .where_(("author_id = ?", 3))?
+            .push_clause(Box::new(Unbound))
+            .limit_percent_emulated(5.0);
         assert_eq!(
-            "select * from users left join (select id, user_id from posts where posts.id = $1) as sub on users.id = sub.user_id",
-            query
+            "\
+clause | sql                                         | bind | type
+select | similarity(title, ?) as score               | $1   | string
+where  | author_id = ?                               | $2   | i32
+custom | and tags ? 'x'                              | !    | 1 placeholders, 0 values
+limit  | limit (select ceil(count(*) * ? / 100)::... | !    | 4 placeholders, 3 values
+limit  | limit (select ceil(count(*) * ? / 100)::... | $3   | f64
+limit  | limit (select ceil(count(*) * ? / 100)::... | $4   | string
+limit  | limit (select ceil(count(*) * ? / 100)::... | $5   | i32
+",
+            q.explain_binds()
         );
         Ok(())
     }
 
     #[test]
-    fn nested_where() -> QResult<()> {
-        let w: Where = ("(orders > ? and orders < ?)", 1, 10).try_into()?;
-        let q = Select::from("users")
-            .where_(("id = ? or ?", 1, w))?
-            .into_builder();
-        let query = q.sql();
-
+    fn default_limit_yields_to_explicit_limits() {
+        let q = Select::from("events").default_limit(100);
         assert_eq!(
-            "select * from users where id = $1 or (orders > $2 and orders < $3) ",
-            query
+            "select * from events limit (select ceil(count(*) * ? / 100)::bigint from (select * from events) as limit_percent)",
+            q.clone().limit_percent_emulated(5.0).parts().0
         );
-        Ok(())
+        assert_eq!("select count(*) from events", q.to_count().parts().0);
     }
 
     #[test]
@@ -669,6 +5164,84 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn uppercase_keywords_skips_literals() {
+        let sql = util::uppercase_keywords(
+            "select \"from\", 'it''s or' as note, $q$ and $q$, a->>'in' -- where\nfrom t where x is not null",
+        );
+        assert_eq!(
+            "SELECT \"from\", 'it''s or' AS note, $q$ and $q$, a->>'in' -- where\nFROM t WHERE x IS NOT NULL",
+            sql
+        );
+        let sql = util::uppercase_keywords(r"select E'it\'s or' as a, e'\\' as b from t");
+        assert_eq!(r"SELECT E'it\'s or' AS a, e'\\' AS b FROM t", sql);
+
+        let inner = Select::from("orders").where_("total > 10").unwrap();
+        let (sql, _) = Select::from(("(?) as o", inner))
+            .uppercase_keywords()
+            .parts();
+        assert_eq!(
+            "SELECT * FROM (SELECT * FROM orders WHERE total > 10 ) AS o",
+            sql
+        );
+    }
+
+    #[test]
+    fn where_not_exists_merges_binds() -> QResult<()> {
+        let sub = Select::from("bans")
+            .where_("bans.user_id = users.id")?
+            .where_(("bans.until > ?", "2024-01-01"))?;
+        let (sql, values) = Select::from("users")
+            .where_not_exists(sub)
+            .where_(("users.team_id = ?", 7))?
+            .parts();
+        assert_eq!(
+            "select * from users where not exists (select * from bans where bans.user_id = users.id and bans.until > ?) and users.team_id = ? ",
+            sql
+        );
+        assert!(matches!(
+            &values[..],
+            [SQLValue::String(_), SQLValue::I32(7)]
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn where_any_and_all() -> QResult<()> {
+        let or = Where::try_from(("b = ?", 2)).map(|mut w| {
+            w.kind(BoolKind::Or);
+            w
+        })?;
+        let (sql, values) = Select::from("t")
+            .where_all([Where::try_from(("a = ?", 1))?, or])
+            .where_all([])
+            .where_any([])
+            .where_all([Where::try_from("c or d")?, Where::try_from("e")?])
+            .where_all([Where::try_from("f = 1\nor g = 2")?, Where::try_from("h")?])
+            .parts();
+        assert_eq!(
+            "select * from t where ((a = ?) and (b = ?)) and 1 = 0 and ((c or d) and (e)) and ((f = 1\nor g = 2) and (h)) ",
+            sql
+        );
+        assert_eq!(2, values.len());
+        Ok(())
+    }
+
+    #[test]
+    fn where_groups() -> QResult<()> {
+        let (sql, values) = Select::from("users")
+            .where_group(|w| w.where_(("a = ?", 1))?.or_where(("b = ?", 2)))?
+            .or_where_group(|w| w.where_(("c = ?", 3))?.where_("d is null"))?
+            .where_group(Ok)?
+            .parts();
+        assert_eq!(
+            "select * from users where (a = ? or b = ?) or (c = ? and d is null) ",
+            sql
+        );
+        assert_eq!(3, values.len());
+        Ok(())
+    }
+
     #[test]
     fn union() -> QResult<()> {
         let a = Select::from("users").select("id").where_(("id = ?", 1))?;
@@ -700,6 +5273,114 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn where_with_many_values() -> QResult<()> {
+        let q = Select::from("t").where_((
+            "a in (?, ?, ?, ?, ?, ?) and b in (?, ?, ?, ?, ?, ?)",
+            1,
+            2,
+            3,
+            4,
+            5,
+            6,
+            "a",
+            "b",
+            "c",
+            "d",
+            "e",
+            Some("f"),
+        ))?;
+        let (sql, values) = q.parts();
+        assert_eq!(
+            "select * from t where a in (?, ?, ?, ?, ?, ?) and b in (?, ?, ?, ?, ?, ?) ",
+            sql
+        );
+        assert_eq!(12, values.len());
+        assert!(matches!(&values[11], SQLValue::String(s) if s == "f"));
+
+        let err = Where::try_from(("a = ? and b = ?", 1, 2, 3, 4, 5)).unwrap_err();
+        assert!(matches!(err, QueryError::IncorrectPlaceholderCount(_, 5)));
+        Ok(())
+    }
+
+    #[test]
+    fn where_with_value_vec() -> QResult<()> {
+        let values: Vec<SQLValue> = vec![1.into(), "a".into(), SQLValue::Null];
+        let q =
+            Select::from("t").where_(("a = ? and b = ? and c is not distinct from ?", values))?;
+        let (sql, values) = q.parts();
+        assert_eq!(
+            "select * from t where a = ? and b = ? and c is not distinct from ? ",
+            sql
+        );
+        assert_eq!(3, values.len());
+
+        let err = Where::try_from(("a = ?", vec![SQLValue::Null; 2])).unwrap_err();
+        assert!(matches!(err, QueryError::IncorrectPlaceholderCount(_, 2)));
+        assert!(Where::try_from(("true", Vec::<SQLValue>::new())).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn where_with_expanded_list() -> QResult<()> {
+        let q = Select::from("users")
+            .where_(("id in (?)", List(vec![1, 2, 3])))?
+            .where_(("email in (?) or name = ?", List(vec!["a@example.com"]), "a"))?
+            .where_(("team_id = any(?)", vec![4_i64, 5]))?;
+        let (sql, values) = q.parts();
+        assert_eq!(
+            "select * from users where id in (?, ?, ?) and email in (?) or name = ? and team_id = any(?) ",
+            sql
+        );
+        assert!(matches!(
+            &values[..],
+            [
+                SQLValue::I32(1),
+                SQLValue::I32(2),
+                SQLValue::I32(3),
+                SQLValue::String(_),
+                SQLValue::String(_),
+                SQLValue::VecI64(_)
+            ]
+        ));
+
+        let err = Select::from("users")
+            .where_(("id in (?)", List(Vec::<i64>::new())))
+            .unwrap_err();
+        assert!(matches!(err.root(), QueryError::Unsupported(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn where_op_any_and_all() -> QResult<()> {
+        let sub = Select::from("limits")
+            .select("amount")
+            .where_(("region_id = ?", 4))?;
+        let (sql, values) = Select::from("orders")
+            .where_(("id > ?", 1))?
+            .where_op_all("amount", "<=", sub)?
+            .limit(10)
+            .parts();
+        assert_eq!(
+            "select * from orders where id > ? and amount <= all (select amount from limits where region_id = ?)  limit ?",
+            sql
+        );
+        assert!(matches!(
+            &values[..],
+            [SQLValue::I32(1), SQLValue::I32(4), SQLValue::U64(10)]
+        ));
+
+        let err = Select::from("orders")
+            .where_op_any("amount", "~", Select::from("limits"))
+            .unwrap_err();
+        assert!(matches!(err.root(), QueryError::UnknownOperator(_)));
+        let err = Select::from("orders")
+            .where_op_any("amount; drop table orders", Op::Eq, Select::from("limits"))
+            .unwrap_err();
+        assert!(matches!(err.root(), QueryError::InvalidIdentifier(_)));
+        Ok(())
+    }
+
     #[test]
     fn conditional_where() -> QResult<()> {
         let q = Select::from("users").where_if(true, || ("id > ?", 5))?;
@@ -710,6 +5391,79 @@ mod tests {
     }
 
     #[test]
+    fn conditional_where_value() -> QResult<()> {
+        let q = Select::from("users").where_when(true, ("id > ?", 5))?;
+        assert_eq!("select * from users where id > $1 ", q.into_builder().sql());
+        let q = Select::from("users").where_when(false, ("id > ?", 5))?;
+        assert_eq!("select * from users", q.into_builder().sql());
+        let err = Select::from("users")
+            .where_when(false, ("id > ? and ?", 5))
+            .unwrap_err();
+        assert!(matches!(
+            err.root(),
+            QueryError::IncorrectPlaceholderCount(..)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn optional_where() -> QResult<()> {
+        let q = Select::from("users")
+            .where_opt("email = ?", Some("a@example.com"))?
+            .where_opt("status_id = ?", None::<i64>)?;
+        let (sql, values) = q.parts();
+        assert_eq!("select * from users where email = ? ", sql);
+        assert!(matches!(&values[..], [SQLValue::String(s)] if s == "a@example.com"));
+
+        let err = Select::from("users")
+            .where_opt("email = ? or ?", Some("a@example.com"))
+            .unwrap_err();
+        assert!(matches!(
+            err.root(),
+            QueryError::IncorrectPlaceholderCount(..)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn nullable_where() -> QResult<()> {
+        let q = Select::from("users").where_nullable((
+            "deleted_at = ? and status_id != ? and age >= ?",
+            None::<String>,
+            None::<i64>,
+            None::<i64>,
+        ))?;
+        assert!(q.clone().where_nullable(("a = ? and b = ?", 1)).is_err());
+        let (sql, values) = q.where_nullable(("banned_by <> ?", Some(3)))?.parts();
+        assert_eq!(
+            "select * from users where deleted_at is null and status_id is not null and age >= ? and banned_by <> ? ",
+            sql
+        );
+        assert!(matches!(&values[..], [SQLValue::Null, SQLValue::I32(3)]));
+
+        let Where::Simple { expr, values, .. } = Where::nullable(Where::try_from((
+            "a = ?::varchar(20) and b <> ?::timestamp with time zone and c = ?::double precision[] or d = ?::int",
+            None::<String>,
+            None::<String>,
+            None::<f64>,
+            None::<i64>,
+        ))?);
+        assert_eq!(
+            "a is null and b is not null and c is null or d is null",
+            expr
+        );
+        assert!(values.is_empty());
+
+        // Not a type validate_type accepts, so the clause is left alone.
+        let Where::Simple { expr, values, .. } =
+            Where::nullable(Where::try_from(("a = ?::int[1]", None::<i64>))?);
+        assert_eq!("a = ?::int[1]", expr);
+        assert!(matches!(&values[..], [SQLValue::Null]));
+        Ok(())
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn can_select_from_slices_and_arrays() -> QResult<()> {
         let q = Select::from("users").select(["id", "email"].as_slice());
         assert_eq!("select id, email from users", q.into_builder().sql());
@@ -745,6 +5499,276 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn rollup_cube_and_grouping_sets() {
+        let q = Select::from("sales")
+            .select(("region", "product", "sum(amount)"))
+            .group_by_rollup(("region", "product"));
+        assert_eq!(
+            "select region, product, sum(amount) from sales group by rollup (region, product) ",
+            q.into_builder().sql()
+        );
+
+        let q = Select::from("sales").group_by_cube(["region", "product"]);
+        assert_eq!(
+            "select * from sales group by cube (region, product) ",
+            q.into_builder().sql()
+        );
+
+        let q = Select::from("sales").grouping_sets([vec!["region"], vec!["product"], vec![]]);
+        assert_eq!(
+            "select * from sales group by grouping sets ((region), (product), ()) ",
+            q.into_builder().sql()
+        );
+    }
+
+    #[test]
+    fn tag_does_not_change_sql() -> QResult<()> {
+        let q = Select::from("users")
+            .where_(("id = ?", 1))?
+            .tag("user_by_id")
+            .into_builder();
+        assert_eq!("select * from users where id = $1 ", q.sql());
+        Ok(())
+    }
+
+    #[test]
+    fn window_functions() {
+        let q = Select::from("orders")
+            .select("id")
+            .select_window(
+                "sum(amount)",
+                Partition::by(("user_id", "region")),
+                "user_total",
+            )
+            .select_window("count(*)", Partition::new(), "total");
+        assert_eq!(
+            "select id, sum(amount) over (partition by user_id, region) as user_total, count(*) over () as total from orders",
+            q.into_builder().sql()
+        );
+
+        let w = Partition::new()
+            .order_by("created_at", OrderDir::Asc)
+            .order_by("id", OrderDir::Asc);
+        assert_eq!("order by created_at asc, id asc", w.to_sql());
+    }
+
+    #[test]
+    fn fingerprint_ignores_values() -> QResult<()> {
+        let a = Select::from("users").where_(("id = ?", 1))?.limit(10);
+        let b = Select::from("users").where_(("id = ?", 2))?.limit(20);
+        let c = Select::from("users").where_(("id > ?", 1))?.limit(10);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_ne!(a.fingerprint(), c.fingerprint());
+        Ok(())
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn cache_key_includes_values() -> QResult<()> {
+        use std::time::Duration;
+
+        let ttl = Duration::from_secs(60);
+        let a = Select::from("users").where_(("id = ?", 1))?.cached(ttl);
+        let b = Select::from("users").where_(("id = ?", 1))?.cached(ttl);
+        let c = Select::from("users").where_(("id = ?", 2))?.cached(ttl);
+        assert_eq!(a.key()?, b.key()?);
+        assert_ne!(a.key()?, c.key()?);
+        assert!(a.key()?.contains("select * from users where id = ?"));
+        Ok(())
+    }
+
+    #[test]
+    fn in_schema_sets_quoted_search_path() {
+        let statements = Select::from("users")
+            .with_rls(RlsContext::new().role("tenant"))
+            .in_schema("Tenant 7, public")
+            .rls_statements();
+        assert_eq!(2, statements.len());
+        assert!(matches!(
+            &statements[1].1[..],
+            [SQLValue::String(name), SQLValue::String(path)]
+                if name == "search_path" && path == "\"Tenant 7, public\""
+        ));
+
+        // A later context doesn't drop the schema.
+        let statements = Select::from("users")
+            .in_schema("tenant_7")
+            .with_rls(RlsContext::new().search_path("public"))
+            .rls_statements();
+        assert!(matches!(
+            &statements[..],
+            [_, (_, last)] if matches!(&last[1], SQLValue::String(path) if path == "tenant_7")
+        ));
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn cache_key_includes_rls_context() -> QResult<()> {
+        let ttl = std::time::Duration::from_secs(60);
+        let q = Select::from("documents");
+        let a = q
+            .clone()
+            .with_rls(RlsContext::new().setting("app.user_id", 1));
+        let b = q.with_rls(RlsContext::new().setting("app.user_id", 2));
+        assert_ne!(a.cached(ttl).key()?, b.cached(ttl).key()?);
+        Ok(())
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn memory_cache_expires_entries() {
+        use std::time::Duration;
+
+        let cache = MemoryCache::new();
+        cache.set("a", "1".to_string(), Duration::from_secs(60));
+        cache.set("b", "2".to_string(), Duration::ZERO);
+        assert_eq!(Some("1".to_string()), cache.get("a"));
+        assert_eq!(None, cache.get("b"));
+        assert_eq!(None, cache.get("c"));
+
+        let cache = MemoryCache::with_capacity(2);
+        cache.set("a", "1".to_string(), Duration::from_secs(60));
+        cache.set("b", "2".to_string(), Duration::from_secs(30));
+        cache.set("a", "3".to_string(), Duration::from_secs(60));
+        cache.set("c", "4".to_string(), Duration::from_secs(90));
+        assert_eq!(Some("3".to_string()), cache.get("a"));
+        assert_eq!(None, cache.get("b"));
+        assert_eq!(Some("4".to_string()), cache.get("c"));
+    }
+
+    #[test]
+    fn aggregate_filters_number_binds_in_order() -> QResult<()> {
+        let q = Select::from("orders")
+            .select("user_id")
+            .select(agg::count_filter(("status = ?", 2))?.alias("shipped"))
+            .select(agg::sum_filter(
+                "amount",
+                ("status = ? and amount > ?", 3, 10),
+            )?)
+            .where_(("created_at > ?", "2024-01-01"))?
+            .group_by("user_id");
+        assert_eq!(
+            "select user_id, count(*) filter (where status = $1) as shipped, sum(amount) filter (where status = $2 and amount > $3) from orders where created_at > $4  group by user_id ",
+            q.into_builder().sql()
+        );
+
+        assert!(agg::count_filter(("status = ? or status = ?", 1)).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "exec")]
+    #[tokio::test]
+    async fn exec_policy_retries_transient_errors() {
+        use std::time::Duration;
+
+        let policy = ExecPolicy::new()
+            .retries(2)
+            .backoff(Duration::from_millis(1));
+        let mut attempts = 0;
+        let res = policy
+            .run(|| {
+                attempts += 1;
+                let n = attempts;
+                async move {
+                    match n {
+                        1 | 2 => Err(sqlx::Error::PoolTimedOut),
+                        _ => Ok(n),
+                    }
+                }
+            })
+            .await;
+        assert_eq!(3, res.unwrap());
+
+        let mut attempts = 0;
+        let res: QResult<()> = policy
+            .run(|| {
+                attempts += 1;
+                async { Err(sqlx::Error::RowNotFound) }
+            })
+            .await;
+        assert!(matches!(
+            res,
+            Err(QueryError::Database(sqlx::Error::RowNotFound))
+        ));
+        assert_eq!(1, attempts);
+    }
+
+    #[cfg(feature = "exec")]
+    #[tokio::test]
+    async fn exec_policy_times_out() {
+        use std::time::Duration;
+
+        let policy = ExecPolicy::new().timeout(Duration::from_millis(5));
+        let res: QResult<()> = policy
+            .run(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .await;
+        assert!(matches!(res, Err(QueryError::Timeout(_))));
+    }
+
+    #[cfg(feature = "sea-query")]
+    #[test]
+    fn converts_to_sea_query() -> QResult<()> {
+        use sea_query::{PostgresQueryBuilder, SelectStatement};
+
+        let sub = Select::from("posts")
+            .select("user_id")
+            .where_(("score > ?", 5))?;
+        let q = Select::from(("(?) as p", sub))
+            .select(("user_id", "count(*)"))
+            .select(agg::count_filter(("status = ?", 2))?.alias("shipped"))
+            .where_(("user_id > ? and user_id < ?", 1, 100))?
+            .or_where("user_id = 0")?
+            .group_by("user_id")
+            .order_by("user_id", OrderDir::Desc)
+            .limit(10)
+            .offset(20);
+
+        let stmt = SelectStatement::try_from(q)?;
+        let (sql, values) = stmt.build(PostgresQueryBuilder);
+        assert_eq!(
+            r#"SELECT user_id, count(*), count(*) filter (where status = $1) AS "shipped" FROM (SELECT user_id FROM "posts" WHERE score > $2) AS "p" WHERE user_id > $3 and user_id < $4 or user_id = 0 GROUP BY user_id ORDER BY user_id DESC LIMIT $5 OFFSET $6"#,
+            sql
+        );
+        assert_eq!(6, values.0.len());
+
+        let q = Select::from("users").left_join("posts on users.id = posts.user_id")?;
+        let (sql, _) = SelectStatement::try_from(q)?.build(PostgresQueryBuilder);
+        assert_eq!(
+            r#"SELECT * FROM "users" LEFT JOIN "posts" ON users.id = posts.user_id"#,
+            sql
+        );
+
+        let q = Select::union(Select::from("a"), Select::from("b"), "t");
+        assert!(matches!(
+            SelectStatement::try_from(q),
+            Err(QueryError::Unsupported(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-postgres")]
+    #[test]
+    fn tokio_postgres_output() -> QResult<()> {
+        use postgres_types::{ToSql, Type};
+
+        let q = Select::from("users")
+            .where_(("id > ? and email = ?", 1i64, "a@b.c"))?
+            .limit(10);
+        let expected = q.clone().into_builder().sql().to_string();
+        let q = q.to_tokio_postgres();
+        assert_eq!(expected, q.sql);
+        assert_eq!(3, q.params().len());
+
+        let mut buf = bytes::BytesMut::new();
+        assert!(q.values[0].to_sql_checked(&Type::INT8, &mut buf).is_ok());
+        assert!(q.values[0].to_sql_checked(&Type::TEXT, &mut buf).is_err());
+        Ok(())
+    }
+
     #[test]
     fn it_can_union() -> QResult<()> {
         let a = Select::from("users").where_(("id > ?", 5))?;
@@ -766,4 +5790,43 @@ mod tests {
         );
         Ok(())
     }
+
+    #[cfg(feature = "exec")]
+    #[tokio::test]
+    async fn exec_policy_refuses_unlisted_queries() {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let allowed = FingerprintAllowList::new().allow(&Select::from("users"));
+        let policy = ExecPolicy::new().allow_list(Arc::new(allowed));
+
+        let res: QResult<Vec<(i64,)>> =
+            Select::from("secrets").fetch_all_with(&pool, &policy).await;
+        assert!(matches!(res, Err(QueryError::NotAllowed(_))));
+    }
+
+    #[cfg(feature = "cache")]
+    #[tokio::test]
+    async fn cached_queries_are_checked_against_the_allow_list() -> QResult<()> {
+        use std::time::Duration;
+
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let allowed = FingerprintAllowList::new().allow(&Select::from("users"));
+        let policy = ExecPolicy::new().allow_list(Arc::new(allowed));
+        let cache = MemoryCache::new();
+
+        let q = Select::from("secrets").cached(Duration::from_secs(60));
+        cache.set(&q.key()?, "[[1]]".to_string(), Duration::from_secs(60));
+        let res: QResult<Vec<(i64,)>> = q.fetch_all_cached_with(&pool, &cache, &policy).await;
+        assert!(matches!(res, Err(QueryError::NotAllowed(_))));
+        Ok(())
+    }
+
+    #[cfg(feature = "exec")]
+    #[tokio::test]
+    async fn delete_in_batches_refuses_empty_batches() {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let res = Delete::from("events")
+            .delete_in_batches(&pool, "id", 0)
+            .await;
+        assert!(matches!(res, Err(QueryError::Unsupported(_))));
+    }
 }
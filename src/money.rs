@@ -0,0 +1,92 @@
+use crate::error::QResult;
+use crate::values::ValuesRow;
+use crate::{Insert, Op, SQLValue, Select, Update};
+
+/// An amount in minor units, e.g. cents, and its currency code, for the
+/// common schema storing money as an integer column and a currency column.
+///
+/// Binds as two values: the amount as a `bigint` and the currency as text.
+/// It is also a [ValuesRow], so it can fill two columns of an
+/// [Insert::row].
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{Insert, Money, Select};
+/// let price = Money::new(1999, "USD");
+///
+/// let q = Insert::into("products")
+///     .value("sku", "a-1")
+///     .value_money("price_minor", "price_currency", price.clone());
+/// assert_eq!(
+///     "insert into products (sku, price_minor, price_currency) values ($1, $2, $3)",
+///     q.into_builder().sql()
+/// );
+///
+/// let q = Select::from("products").where_money_eq("price_minor", "price_currency", price)?;
+/// assert_eq!(
+///     "select * from products where price_minor = $1 and price_currency = $2 ",
+///     q.into_builder().sql()
+/// );
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Money {
+    pub amount_minor: i64,
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new(amount_minor: i64, currency: impl Into<String>) -> Self {
+        Self {
+            amount_minor,
+            currency: currency.into(),
+        }
+    }
+}
+
+impl ValuesRow for Money {
+    fn into_row(self) -> Vec<SQLValue> {
+        vec![self.amount_minor.into(), self.currency.into()]
+    }
+}
+
+impl Select {
+    /// Renders `amount_col = ? and currency_col = ?`. Both columns are
+    /// validated as in [Select::where_op].
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_money_eq(
+        self,
+        amount_col: &str,
+        currency_col: &str,
+        money: Money,
+    ) -> QResult<Self> {
+        self.where_op(amount_col, Op::Eq, money.amount_minor)?
+            .where_op(currency_col, Op::Eq, money.currency)
+    }
+}
+
+impl Insert {
+    /// Adds the amount and currency columns to a single row insert.
+    pub fn value_money(
+        self,
+        amount_col: impl Into<String>,
+        currency_col: impl Into<String>,
+        money: Money,
+    ) -> Self {
+        self.value(amount_col, money.amount_minor)
+            .value(currency_col, money.currency)
+    }
+}
+
+impl Update {
+    /// Sets the amount and currency columns.
+    pub fn set_money(
+        self,
+        amount_col: impl Into<String>,
+        currency_col: impl Into<String>,
+        money: Money,
+    ) -> Self {
+        self.set(amount_col, money.amount_minor)
+            .set(currency_col, money.currency)
+    }
+}
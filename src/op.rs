@@ -0,0 +1,56 @@
+use crate::error::QueryError;
+
+/// A comparison operator, for building predicates from a validated column
+/// and a value rather than a hand written string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    NotEq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Like,
+    NotLike,
+    ILike,
+    NotILike,
+}
+
+impl Op {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::NotEq => "!=",
+            Op::Lt => "<",
+            Op::Lte => "<=",
+            Op::Gt => ">",
+            Op::Gte => ">=",
+            Op::Like => "like",
+            Op::NotLike => "not like",
+            Op::ILike => "ilike",
+            Op::NotILike => "not ilike",
+        }
+    }
+}
+
+/// Parses the SQL spelling of the operator, e.g. `>=` or `not ilike`.
+impl TryFrom<&str> for Op {
+    type Error = QueryError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let op = match value.trim().to_lowercase().as_str() {
+            "=" => Op::Eq,
+            "!=" | "<>" => Op::NotEq,
+            "<" => Op::Lt,
+            "<=" => Op::Lte,
+            ">" => Op::Gt,
+            ">=" => Op::Gte,
+            "like" => Op::Like,
+            "not like" => Op::NotLike,
+            "ilike" => Op::ILike,
+            "not ilike" => Op::NotILike,
+            _ => return Err(QueryError::UnknownOperator(value.to_string())),
+        };
+        Ok(op)
+    }
+}
@@ -1,8 +1,14 @@
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+use crate::error::{QResult, QueryError};
+
+/// Serialized as `asc` or `desc`, and deserialized from anything the
+/// [FromStr] impl accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", try_from = "String")]
 #[repr(u8)]
 pub enum OrderDir {
     Asc,
@@ -16,6 +22,42 @@ impl OrderDir {
             OrderDir::Desc => "desc",
         }
     }
+
+    /// Parses a sort direction, see the [FromStr] impl.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::OrderDir;
+    /// assert_eq!(OrderDir::Desc, OrderDir::try_from_str("DESC")?);
+    /// assert_eq!(OrderDir::Asc, OrderDir::try_from_str("ascending")?);
+    /// assert!(OrderDir::try_from_str("sideways").is_err());
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn try_from_str(s: &str) -> QResult<Self> {
+        s.parse()
+    }
+}
+
+/// Accepts `asc`, `desc`, `ascending` or `descending` in any case, e.g.
+/// from a query string sort parameter.
+impl FromStr for OrderDir {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "asc" | "ascending" => Ok(OrderDir::Asc),
+            "desc" | "descending" => Ok(OrderDir::Desc),
+            _ => Err(QueryError::UnknownOrderDir(s.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for OrderDir {
+    type Error = QueryError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
 }
 
 impl Display for OrderDir {
@@ -0,0 +1,121 @@
+use crate::error::{QResult, QueryError};
+use crate::{OrderDir, Select};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Page size policy, per table.
+///
+/// Each table gets a default page size, used when the caller doesn't ask
+/// for one, and a maximum, which larger requests are clamped to. Tables
+/// without an entry use the fallback passed to [PageLimits::new].
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{PageLimits, Select};
+/// let limits = PageLimits::new(20, 100).table("events", 50, 500);
+///
+/// let q = Select::from("events").page_with(3, None, &limits);
+/// assert_eq!("select * from events limit $1 offset $2", q.into_builder().sql());
+///
+/// assert_eq!(100, limits.per_page("users", Some(10_000)));
+/// assert_eq!(50, limits.per_page("events", None));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PageLimits {
+    fallback: (u64, u64),
+    tables: HashMap<String, (u64, u64)>,
+}
+
+impl PageLimits {
+    pub fn new(default: u64, max: u64) -> Self {
+        Self {
+            fallback: (default, max),
+            tables: HashMap::new(),
+        }
+    }
+
+    /// Sets the default and maximum page size for `table`.
+    pub fn table(mut self, table: impl Into<String>, default: u64, max: u64) -> Self {
+        self.tables.insert(table.into(), (default, max));
+        self
+    }
+
+    /// The page size to use for `table`, given the size the caller asked for.
+    /// A requested size of 0 is treated as 1.
+    pub fn per_page(&self, table: &str, requested: Option<u64>) -> u64 {
+        let (default, max) = self.tables.get(table).copied().unwrap_or(self.fallback);
+        requested.unwrap_or(default).clamp(1, max.max(1))
+    }
+}
+
+/// Paging and sorting parameters for a list endpoint, usually deserialized
+/// from the query string. Apply them with [Select::apply_params].
+///
+/// `dir` accepts anything [OrderDir] parses, in any case, and anything
+/// else fails to deserialize.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListParams {
+    #[serde(default)]
+    pub page: Option<u64>,
+    #[serde(default)]
+    pub per_page: Option<u64>,
+    #[serde(default)]
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub dir: Option<OrderDir>,
+}
+
+impl Select {
+    /// Applies [ListParams] from a request. The sort column must be one of
+    /// `sort_allowlist` and comes before any order already on the query,
+    /// which is kept as a tiebreaker.
+    ///
+    /// The page size is the requested `per_page`, else the query's own
+    /// limit or [Select::default_limit], clamped by `limits` for this
+    /// query's table, see [PageLimits::per_page]. A page too deep for its
+    /// offset to fit a `bigint` is [QueryError::Unsupported].
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{ListParams, OrderDir, PageLimits, QueryError, Select};
+    /// let limits = PageLimits::new(20, 100);
+    /// let params: ListParams =
+    ///     serde_json::from_str(r#"{"page": 3, "per_page": 20, "sort": "name", "dir": "DESC"}"#).unwrap();
+    ///
+    /// let q = Select::from("users")
+    ///     .order_by("id", OrderDir::Asc)
+    ///     .apply_params(&params, &["name", "created_at"], &limits)?;
+    /// assert_eq!(
+    ///     "select * from users order by name desc, id asc  limit $1 offset $2",
+    ///     q.into_builder().sql()
+    /// );
+    ///
+    /// let params = ListParams { sort: Some("password".into()), ..Default::default() };
+    /// let res = Select::from("users").apply_params(&params, &["name"], &limits);
+    /// assert!(matches!(res, Err(QueryError::DisallowedColumn(_))));
+    /// # Ok::<(), QueryError>(())
+    /// ```
+    pub fn apply_params(
+        mut self,
+        params: &ListParams,
+        sort_allowlist: &[&str],
+        limits: &PageLimits,
+    ) -> QResult<Self> {
+        let dir = params.dir.unwrap_or(OrderDir::Asc);
+        if let Some(sort) = &params.sort {
+            let existing = std::mem::take(&mut self.order_by);
+            self = self.order_by_checked(sort, dir, sort_allowlist)?;
+            self.order_by.extend(existing);
+        }
+
+        let requested = params.per_page.or(self.limit).or(self.default_limit);
+        let per_page = limits.per_page(self.table_name(), requested);
+        let page = params.page.unwrap_or(1);
+        let offset = page
+            .saturating_sub(1)
+            .checked_mul(per_page)
+            .filter(|offset| *offset <= i64::MAX as u64)
+            .ok_or_else(|| QueryError::Unsupported(format!("page {page} is out of range")))?;
+        Ok(self.limit(per_page).offset(offset))
+    }
+}
@@ -0,0 +1,93 @@
+use crate::error::QResult;
+use crate::{ExecPolicy, Select};
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgPool};
+
+/// A page of results and the total number of matching rows, see
+/// [Select::paginate].
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+}
+
+/// A query to run one page at a time, see [Select::paginate].
+#[derive(Debug, Clone)]
+pub struct Paginate {
+    select: Select,
+    page: u64,
+    per_page: u64,
+}
+
+impl Select {
+    /// Fetches page `page` (1-based) of `per_page` rows along with the
+    /// total, counted by [Select::to_count]. See [Select::page] for how
+    /// the page and size are handled.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # async fn run(pool: sqlx::PgPool) -> composable_query_builder2::QResult<()> {
+    /// use composable_query_builder2::{OrderDir, Select};
+    ///
+    /// let page = Select::from("users")
+    ///     .select(("id", "email"))
+    ///     .order_by("id", OrderDir::Asc)
+    ///     .paginate(2, 25)
+    ///     .fetch::<(i64, String)>(&pool)
+    ///     .await?;
+    /// println!("{} of {}", page.items.len(), page.total);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn paginate(self, page: u64, per_page: u64) -> Paginate {
+        Paginate {
+            select: self,
+            page: page.max(1),
+            per_page: per_page.max(1),
+        }
+    }
+}
+
+impl Paginate {
+    pub async fn fetch<T>(self, pool: &PgPool) -> QResult<Page<T>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        self.fetch_with(pool, &ExecPolicy::default()).await
+    }
+
+    /// Runs both queries under `policy`. Only the page query needs to be
+    /// on the policy's allow list: the count is derived from it, and runs
+    /// once the page query has passed. Both run with the query's
+    /// [RlsContext](crate::RlsContext) and search path.
+    pub async fn fetch_with<T>(self, pool: &PgPool, policy: &ExecPolicy) -> QResult<Page<T>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        let count = self.count_query();
+        let count_policy = ExecPolicy {
+            allow_list: None,
+            ..policy.clone()
+        };
+        let items = self
+            .select
+            .page(self.page, self.per_page)
+            .fetch_all_with(pool, policy)
+            .await?;
+        let (total,): (i64,) = count.fetch_one_with(pool, &count_policy).await?;
+        Ok(Page {
+            items,
+            total: total.max(0) as u64,
+            page: self.page,
+            per_page: self.per_page,
+        })
+    }
+
+    /// The query counting the total, see [Select::to_count].
+    pub(crate) fn count_query(&self) -> Select {
+        self.select.to_count()
+    }
+}
@@ -0,0 +1,142 @@
+use crate::error::{QResult, QueryError};
+use crate::util::{fold_identifier, is_identifier};
+use crate::SelectExpr;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Rewrites or removes sensitive columns from select lists when a query is
+/// rendered, so one policy can be applied to every query that reads a table.
+///
+/// Columns are matched by name, with or without a table qualifier, so a
+/// policy for `ssn` also covers `users.ssn`. Names are compared the way
+/// Postgres resolves them, so `SSN` is covered too but `"SSN"` is not. Only named columns can be
+/// rewritten, so `select *`, `users.*` and expressions using a covered
+/// column, e.g. `lower(ssn)`, are refused: see [ColumnPolicy::check].
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{ColumnPolicy, Select};
+/// use std::sync::Arc;
+///
+/// let policy = Arc::new(ColumnPolicy::new().mask("ssn", "'***'").remove("password_hash"));
+/// let q = Select::from("users")
+///     .select(("id", "users.ssn", "password_hash"))
+///     .with_policy(policy);
+/// assert_eq!("select id, '***' as ssn from users", q.into_builder().sql());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ColumnPolicy {
+    /// `None` removes the column, otherwise it is replaced by the expression.
+    columns: HashMap<String, Option<String>>,
+}
+
+impl ColumnPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces `column` with `expr`, keeping the column name as the alias.
+    ///
+    /// ## Danger: SQL injection
+    ///
+    /// The `expr` is used as is. It should never come from user input.
+    pub fn mask(mut self, column: impl Into<String>, expr: impl Into<String>) -> Self {
+        let column = fold_identifier(&column.into()).into_owned();
+        self.columns.insert(column, Some(expr.into()));
+        self
+    }
+
+    /// Drops `column` from select lists entirely.
+    pub fn remove(mut self, column: impl Into<String>) -> Self {
+        let column = fold_identifier(&column.into()).into_owned();
+        self.columns.insert(column, None);
+        self
+    }
+
+    /// Fails with [QueryError::DisallowedColumn] if the select list would
+    /// get around the policy: it is `*` (empty), has a `*` or `table.*`
+    /// entry, or an expression using a covered column other than as a
+    /// plain column reference.
+    pub fn check(&self, select: &[SelectExpr]) -> QResult<()> {
+        if select.is_empty() {
+            return Err(QueryError::DisallowedColumn("*".to_string()));
+        }
+        for s in select {
+            let expr = s.expr.trim();
+            if expr == "*" || expr.ends_with(".*") {
+                return Err(QueryError::DisallowedColumn(expr.to_string()));
+            }
+            if s.values.is_empty() && expr.split('.').all(is_identifier) {
+                continue;
+            }
+            let covered = |name: &Cow<str>| self.columns.contains_key(name.as_ref());
+            if let Some(name) = identifiers(expr).find(covered) {
+                return Err(QueryError::DisallowedColumn(name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the policy to a select list. If every column is removed the
+    /// list becomes `null`, rather than falling back to `*`.
+    pub(crate) fn apply_all(&self, select: Vec<SelectExpr>) -> Vec<SelectExpr> {
+        if select.is_empty() {
+            return select;
+        }
+        let select = select
+            .into_iter()
+            .filter_map(|s| self.apply(s))
+            .collect::<Vec<_>>();
+        if select.is_empty() {
+            vec![SelectExpr::new("null", vec![])]
+        } else {
+            select
+        }
+    }
+
+    /// Applies the policy to one select list entry. `None` if it should be
+    /// removed.
+    fn apply(&self, s: SelectExpr) -> Option<SelectExpr> {
+        if !s.values.is_empty() {
+            return Some(s);
+        }
+        let name = s.expr.trim();
+        let name = name.rsplit('.').next().unwrap_or(name);
+        match self.columns.get(fold_identifier(name).as_ref()) {
+            None => Some(s),
+            Some(None) => None,
+            Some(Some(mask)) => {
+                let alias = s.alias.unwrap_or_else(|| name.to_string());
+                Some(SelectExpr::new(mask.clone(), vec![]).alias(alias))
+            }
+        }
+    }
+}
+
+/// The identifiers in `expr`, skipping string literals, folded as by
+/// [fold_identifier].
+fn identifiers(expr: &str) -> impl Iterator<Item = Cow<'_, str>> {
+    let mut rest = expr;
+    std::iter::from_fn(move || loop {
+        let c = rest.chars().next()?;
+        match c {
+            '\'' | '"' => {
+                let len = rest[1..].find(c).map_or(rest.len(), |i| i + 2);
+                let (quoted, after) = rest.split_at(len);
+                rest = after;
+                if c == '"' {
+                    return Some(fold_identifier(quoted));
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                    .unwrap_or(rest.len());
+                let (word, after) = rest.split_at(len);
+                rest = after;
+                return Some(fold_identifier(word));
+            }
+            c => rest = &rest[c.len_utf8()..],
+        }
+    })
+}
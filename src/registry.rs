@@ -0,0 +1,66 @@
+use crate::error::{QResult, QueryError};
+use crate::{Join, Select};
+use std::collections::HashMap;
+
+/// Named queries which can be used like database views.
+///
+/// Register each query once, e.g. at startup, then select from or join
+/// against it by name. The view's bound values are merged into the query
+/// using it when that query is rendered.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{QueryRegistry, Select};
+/// let views = QueryRegistry::new().register(
+///     "active_users",
+///     Select::from("users").where_(("status = ?", "active"))?,
+/// );
+///
+/// let q = Select::from("orders")
+///     .inner_join(views.join_view("active_users", "active_users.id = orders.user_id")?)?;
+/// assert_eq!(
+///     "select * from orders inner join (select * from users where status = $1) as active_users on active_users.id = orders.user_id",
+///     q.into_builder().sql()
+/// );
+/// assert!(views.from_view("missing").is_err());
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryRegistry {
+    views: HashMap<String, Select>,
+}
+
+impl QueryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `select` under `name`, replacing any existing view.
+    ///
+    /// ## Danger: SQL injection
+    ///
+    /// The `name` is used as the subquery alias as is. It should never come
+    /// from user input.
+    pub fn register(mut self, name: impl Into<String>, select: Select) -> Self {
+        self.views.insert(name.into(), select);
+        self
+    }
+
+    /// A copy of the query registered as `name`.
+    pub fn view(&self, name: &str) -> QResult<Select> {
+        self.views
+            .get(name)
+            .cloned()
+            .ok_or_else(|| QueryError::UnknownView(name.to_string()))
+    }
+
+    /// Renders `select * from (<view>) as name`.
+    pub fn from_view(&self, name: &str) -> QResult<Select> {
+        Ok(Select::from((format!("(?) as {name}"), self.view(name)?)))
+    }
+
+    /// A join against the view, rendering `(<view>) as name on <on>`.
+    pub fn join_view(&self, name: &str, on: &str) -> QResult<Join> {
+        (format!("(?) as {name} on {on}"), self.view(name)?).try_into()
+    }
+}
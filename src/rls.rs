@@ -0,0 +1,79 @@
+use crate::table::quote;
+use crate::SQLValue;
+
+/// Transaction local settings for row level security policies, e.g. the
+/// role to run as and the current user id read by `current_setting`.
+///
+/// Each setting is applied with `select set_config(?, ?, true)`, as `set`
+/// can't take bound values. They only last until the end of the
+/// transaction, so the execution helpers run the settings and the query in
+/// one transaction.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{RlsContext, Select};
+/// let ctx = RlsContext::new()
+///     .role("tenant_user")
+///     .setting("app.current_user_id", 42);
+/// let q = Select::from("documents").with_rls(ctx);
+///
+/// let statements = q.rls_statements();
+/// assert_eq!(2, statements.len());
+/// assert_eq!("select set_config(?, ?, true)", statements[0].0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RlsContext {
+    settings: Vec<(String, String)>,
+}
+
+impl RlsContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the query as `role`, the same as `set local role`.
+    pub fn role(self, role: impl Into<String>) -> Self {
+        self.setting("role", role.into())
+    }
+
+    /// Resolves unqualified table names in `schema`, the same as
+    /// `set local search_path`. The name is quoted, so it can't add other
+    /// schemas to the path.
+    pub fn search_path(self, schema: &str) -> Self {
+        self.setting("search_path", quote(schema))
+    }
+
+    /// Sets `name` for the transaction, the same as `set local name = value`.
+    pub fn setting(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.settings.push((name.into(), value.to_string()));
+        self
+    }
+
+    pub(crate) fn statements(&self) -> Vec<(String, Vec<SQLValue>)> {
+        self.settings
+            .iter()
+            .map(|(name, value)| {
+                (
+                    "select set_config(?, ?, true)".to_string(),
+                    vec![name.clone().into(), value.clone().into()],
+                )
+            })
+            .collect()
+    }
+}
+
+/// Runs [crate::Select::rls_statements] on a connection, normally inside
+/// the transaction the query will run in.
+#[cfg(any(feature = "exec", feature = "cache"))]
+pub(crate) async fn set_context(
+    conn: &mut sqlx::PgConnection,
+    statements: Vec<(String, Vec<SQLValue>)>,
+) -> Result<(), sqlx::Error> {
+    for (sql, values) in statements {
+        crate::build_query(&sql, values, String::new)
+            .build()
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,24 @@
+/// The sampling method for [crate::Select::tablesample], with the
+/// percentage of the table to sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableSample {
+    /// Samples individual rows. Slower, but evenly spread.
+    Bernoulli(f64),
+    /// Samples whole pages. Fast, but rows on the same page come together.
+    System(f64),
+}
+
+impl TableSample {
+    pub(crate) fn method(&self) -> &'static str {
+        match self {
+            TableSample::Bernoulli(_) => "bernoulli",
+            TableSample::System(_) => "system",
+        }
+    }
+
+    pub(crate) fn percent(&self) -> f64 {
+        match self {
+            TableSample::Bernoulli(p) | TableSample::System(p) => *p,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::error::{QResult, QueryError};
+use std::collections::HashMap;
+
+/// The columns of each table, used to expand a table's columns without
+/// `select *`. See [crate::Select::select_all_of].
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{Schema, Select};
+/// let schema = Schema::new()
+///     .table("users", ["id", "email"])
+///     .table("accounts", ["id", "plan"]);
+///
+/// let q = Select::from("users")
+///     .inner_join("accounts a on a.id = users.account_id")?
+///     .select_all_of(&schema, "users")?
+///     .select_all_of_as(&schema, "accounts", "a")?;
+/// assert_eq!(
+///     "select users.id, users.email, a.id, a.plan from users inner join accounts a on a.id = users.account_id",
+///     q.into_builder().sql()
+/// );
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    tables: HashMap<String, Vec<String>>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the columns of `table`, in the order they should be selected.
+    pub fn table<C: Into<String>>(
+        mut self,
+        table: impl Into<String>,
+        columns: impl IntoIterator<Item = C>,
+    ) -> Self {
+        let columns = columns.into_iter().map(Into::into).collect();
+        self.tables.insert(table.into(), columns);
+        self
+    }
+
+    pub fn columns(&self, table: &str) -> QResult<&[String]> {
+        self.tables
+            .get(table)
+            .map(Vec::as_slice)
+            .ok_or_else(|| QueryError::UnknownTable(table.to_string()))
+    }
+}
@@ -0,0 +1,240 @@
+//! Conversion into [sea_query] statements, for codebases using both crates.
+//!
+//! Only the forward direction is supported. A sea-query statement can't be
+//! split back into the string fragments a [Select] is made of.
+//!
+//! Free-form fragments (select expressions, where clauses, join conditions)
+//! become sea-query custom expressions with their values attached. Tables
+//! and join targets must be plain identifiers so they can be quoted; a
+//! subquery is only accepted in the `(?) as alias` form.
+
+use crate::error::{QResult, QueryError};
+use crate::join::{Join, JoinKind};
+use crate::r#where::push_where_list;
+use crate::util::{is_identifier, number_placeholders};
+use crate::TypedArray;
+use crate::{OrderDir, SQLValue, Select, TableType};
+use sea_query::{
+    Alias, ArrayType, Asterisk, Expr, JoinType, Order, SelectStatement, SimpleExpr, Value,
+};
+
+impl From<SQLValue> for Value {
+    fn from(value: SQLValue) -> Self {
+        match value {
+            SQLValue::I16(v) => v.into(),
+            SQLValue::I32(v) => v.into(),
+            SQLValue::I64(v) => v.into(),
+            SQLValue::U64(v) => (v as i64).into(),
+            SQLValue::F64(v) => v.into(),
+            SQLValue::DateTime(v) => v.into(),
+            SQLValue::Date(v) => v.into(),
+            SQLValue::VecI64(v) => v.into(),
+            SQLValue::Array(v) => {
+                fn array<T: Into<Value> + sea_query::Nullable>(
+                    ty: ArrayType,
+                    v: Vec<Option<T>>,
+                ) -> Value {
+                    Value::Array(ty, Some(Box::new(v.into_iter().map(Into::into).collect())))
+                }
+                match v {
+                    TypedArray::I16(v) => array(ArrayType::SmallInt, v),
+                    TypedArray::I32(v) => array(ArrayType::Int, v),
+                    TypedArray::I64(v) => array(ArrayType::BigInt, v),
+                    TypedArray::F64(v) => array(ArrayType::Double, v),
+                    TypedArray::DateTime(v) => array(ArrayType::ChronoDateTime, v),
+                    TypedArray::Date(v) => array(ArrayType::ChronoDate, v),
+                    TypedArray::String(v) => array(ArrayType::String, v),
+                    TypedArray::Bool(v) => array(ArrayType::Bool, v),
+                    TypedArray::Json(v) => array(ArrayType::Json, v),
+                    TypedArray::Uuid(v) => array(ArrayType::Uuid, v),
+                }
+            }
+            SQLValue::String(v) => v.into(),
+            SQLValue::Bool(v) => v.into(),
+            SQLValue::Json(v) => v.into(),
+            SQLValue::Uuid(v) => v.into(),
+            SQLValue::Null => Value::String(None),
+        }
+    }
+}
+
+impl TryFrom<Select> for SelectStatement {
+    type Error = QueryError;
+
+    fn try_from(select: Select) -> Result<Self, Self::Error> {
+        if select.limit_percent.is_some() {
+            return Err(unsupported("limit", "percent"));
+        }
+        if !select.ctes.is_empty() {
+            return Err(unsupported("with", "common table expressions"));
+        }
+        if select.with_ties {
+            return Err(unsupported("fetch first", "with ties"));
+        }
+        if select.lock.is_some() {
+            return Err(unsupported("locking", "for update/share"));
+        }
+        if select.transform.is_some() {
+            return Err(unsupported("select", "value transform"));
+        }
+        if select.schema.is_some() {
+            return Err(unsupported("select", "schema search path"));
+        }
+        if let Some(clause) = select.clauses.first() {
+            return Err(unsupported("custom", &format!("{clause:?}")));
+        }
+
+        let mut stmt = SelectStatement::new();
+
+        // Select
+        if select.select.is_empty() {
+            stmt.column(Asterisk);
+        }
+        let exprs = match &select.policy {
+            Some(policy) => policy.apply_all(select.select),
+            None => select.select,
+        };
+        for s in exprs {
+            let expr = custom(&s.expr, s.values);
+            match s.alias {
+                Some(alias) => stmt.expr_as(expr, Alias::new(alias)),
+                None => stmt.expr(expr),
+            };
+        }
+
+        // Table
+        if select.tablesample.is_some() {
+            return Err(unsupported("from", "tablesample"));
+        }
+        if !select.also_from.is_empty() {
+            return Err(unsupported("from", "multiple tables"));
+        }
+        match select.table {
+            Some(TableType::Simple(table)) => match select.alias {
+                Some(alias) => {
+                    let table = Alias::new(identifier(&table)?);
+                    stmt.from_as(table, Alias::new(identifier(&alias)?));
+                }
+                None => {
+                    stmt.from(Alias::new(identifier(&table)?));
+                }
+            },
+            Some(TableType::Complex(..)) if select.alias.is_some() => {
+                return Err(unsupported("from", "alias on a subquery"));
+            }
+            Some(TableType::Complex(template, mut subs)) => {
+                let alias = template
+                    .trim()
+                    .strip_prefix("(?) as ")
+                    .filter(|alias| subs.len() == 1 && is_identifier(alias))
+                    .ok_or_else(|| unsupported("from", &template))?;
+                let sub = SelectStatement::try_from(subs.remove(0))?;
+                stmt.from_subquery(sub, Alias::new(alias));
+            }
+            Some(TableType::Table(table)) => {
+                let name = Alias::new(identifier(&table.name)?);
+                let alias = table.alias.or(select.alias);
+                match (table.schema, alias) {
+                    (Some(schema), Some(alias)) => {
+                        let schema = Alias::new(identifier(&schema)?);
+                        stmt.from_as((schema, name), Alias::new(identifier(&alias)?))
+                    }
+                    (Some(schema), None) => stmt.from((Alias::new(identifier(&schema)?), name)),
+                    (None, Some(alias)) => stmt.from_as(name, Alias::new(identifier(&alias)?)),
+                    (None, None) => stmt.from(name),
+                };
+            }
+            Some(TableType::Bound(..)) => return Err(unsupported("from", "bound table")),
+            Some(TableType::List(_)) => return Err(unsupported("from", "multiple tables")),
+            None => return Err(QueryError::Unsupported("no table specified".to_string())),
+        }
+
+        // Joins
+        for (kind, join) in select.join {
+            let kind = match kind {
+                JoinKind::Cross => return Err(unsupported("join", "cross join")),
+                JoinKind::Left => JoinType::LeftJoin,
+                JoinKind::Inner => JoinType::InnerJoin,
+                JoinKind::Right => JoinType::RightJoin,
+                JoinKind::Full => JoinType::FullOuterJoin,
+            };
+            match join {
+                Join::Simple(s) => {
+                    let (table, on) = s
+                        .split_once(" on ")
+                        .ok_or_else(|| unsupported("join", &s))?;
+                    stmt.join(kind, Alias::new(identifier(table)?), Expr::cust(on));
+                }
+                Join::SubQuery(s, _) => return Err(unsupported("join", &s)),
+                Join::Lateral(_) => return Err(unsupported("join", "lateral")),
+                Join::Bound(s, _) => return Err(unsupported("join", &s)),
+            }
+        }
+
+        // Where
+        if !select.where_.is_empty() {
+            let (mut expr, mut values) = (String::new(), vec![]);
+            push_where_list(&select.where_, &mut expr, &mut values);
+            stmt.and_where(custom(expr.trim(), values));
+        }
+
+        if let Some(group_by) = select.group_by {
+            stmt.add_group_by([Expr::cust(group_by)]);
+        }
+
+        if !select.having.is_empty() {
+            let (mut expr, mut values) = (String::new(), vec![]);
+            push_where_list(&select.having, &mut expr, &mut values);
+            stmt.and_having(custom(expr.trim(), values));
+        }
+
+        for (col, values, dir) in select.order_by {
+            let order = match dir {
+                OrderDir::Asc => Order::Asc,
+                OrderDir::Desc => Order::Desc,
+            };
+            stmt.order_by_expr(custom(&col, values), order);
+        }
+
+        if let Some(limit) = select.limit.or(select.default_limit) {
+            stmt.limit(limit);
+        }
+
+        if let Some(offset) = select.offset {
+            stmt.offset(offset);
+        }
+
+        Ok(stmt)
+    }
+}
+
+/// sea-query numbers custom expression placeholders per expression.
+fn custom(expr: &str, values: Vec<SQLValue>) -> SimpleExpr {
+    if values.is_empty() {
+        Expr::cust(expr)
+    } else {
+        Expr::cust_with_values(
+            number_placeholders(expr),
+            values.into_iter().map(Value::from),
+        )
+    }
+}
+
+fn identifier(s: &str) -> QResult<&str> {
+    let s = s.trim();
+    if is_identifier(s) {
+        Ok(s)
+    } else {
+        Err(QueryError::Unsupported(format!(
+            "`{}` is not a plain table name",
+            s
+        )))
+    }
+}
+
+fn unsupported(clause: &str, s: &str) -> QueryError {
+    QueryError::Unsupported(format!(
+        "{} clause `{}` has no sea-query equivalent",
+        clause, s
+    ))
+}
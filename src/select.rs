@@ -1,64 +1,121 @@
-/// Converts one or more values into a list of column names.
+use crate::sql_value::SQLValue;
+
+/// A single entry in the select list, along with any values bound inside it.
+#[derive(Debug, Clone)]
+pub struct SelectExpr {
+    pub(crate) expr: String,
+    pub(crate) values: Vec<SQLValue>,
+    pub(crate) alias: Option<String>,
+}
+
+impl SelectExpr {
+    pub(crate) fn new(expr: impl Into<String>, values: Vec<SQLValue>) -> Self {
+        Self {
+            expr: expr.into(),
+            values,
+            alias: None,
+        }
+    }
+
+    /// Renders the expression as `expr as alias`.
+    pub fn alias(mut self, alias: impl Into<String>) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+
+    pub(crate) fn push_to(self, q: &mut String, vals: &mut Vec<SQLValue>) {
+        q.push_str(&self.expr);
+        if let Some(alias) = self.alias {
+            q.push_str(" as ");
+            q.push_str(&alias);
+        }
+        vals.extend(self.values);
+    }
+}
+
+fn column(s: impl Into<String>) -> SelectExpr {
+    SelectExpr::new(s, vec![])
+}
+
+/// Converts one or more values into a list of select expressions.
 ///
 /// Accepts:
 ///   - &str
 ///   - String
 ///   - Vec<Into<String>>
 ///   - Tuple of 2, 3, or 4 Into<String>
+///   - [SelectExpr], e.g. from the [agg](crate::agg) helpers, or a Vec of them
 pub trait IntoSelect {
-    fn into_select(self) -> Vec<String>;
+    fn into_select(self) -> Vec<SelectExpr>;
+}
+
+impl IntoSelect for SelectExpr {
+    fn into_select(self) -> Vec<SelectExpr> {
+        vec![self]
+    }
+}
+
+impl IntoSelect for Vec<SelectExpr> {
+    fn into_select(self) -> Vec<SelectExpr> {
+        self
+    }
 }
 
 impl IntoSelect for &str {
-    fn into_select(self) -> Vec<String> {
-        vec![self.into()]
+    fn into_select(self) -> Vec<SelectExpr> {
+        vec![column(self)]
     }
 }
 
 impl IntoSelect for String {
-    fn into_select(self) -> Vec<String> {
-        vec![self]
+    fn into_select(self) -> Vec<SelectExpr> {
+        vec![column(self)]
     }
 }
 
 impl<T: Into<String>> IntoSelect for Vec<T> {
-    fn into_select(self) -> Vec<String> {
-        self.into_iter().map(|x| x.into()).collect()
+    fn into_select(self) -> Vec<SelectExpr> {
+        self.into_iter().map(column).collect()
     }
 }
 
 impl<T: Into<String> + Clone> IntoSelect for &[T] {
-    fn into_select(self) -> Vec<String> {
-        self.iter().map(|x| x.clone().into()).collect()
+    fn into_select(self) -> Vec<SelectExpr> {
+        self.iter().cloned().map(column).collect()
     }
 }
 
 impl<T: Into<String>, const N: usize> IntoSelect for [T; N] {
-    fn into_select(self) -> Vec<String> {
-        self.into_iter().map(|x| x.into()).collect()
+    fn into_select(self) -> Vec<SelectExpr> {
+        self.into_iter().map(column).collect()
     }
 }
 
 impl<T: Into<String> + Clone, const N: usize> IntoSelect for &[T; N] {
-    fn into_select(self) -> Vec<String> {
-        self.iter().map(|x| x.clone().into()).collect()
+    fn into_select(self) -> Vec<SelectExpr> {
+        self.iter().cloned().map(column).collect()
     }
 }
 
 impl<T: Into<String>> IntoSelect for (T, T) {
-    fn into_select(self) -> Vec<String> {
-        vec![self.0.into(), self.1.into()]
+    fn into_select(self) -> Vec<SelectExpr> {
+        vec![column(self.0), column(self.1)]
     }
 }
 
 impl<T: Into<String>> IntoSelect for (T, T, T) {
-    fn into_select(self) -> Vec<String> {
-        vec![self.0.into(), self.1.into(), self.2.into()]
+    fn into_select(self) -> Vec<SelectExpr> {
+        vec![column(self.0), column(self.1), column(self.2)]
     }
 }
 
 impl<T: Into<String>> IntoSelect for (T, T, T, T) {
-    fn into_select(self) -> Vec<String> {
-        vec![self.0.into(), self.1.into(), self.2.into(), self.3.into()]
+    fn into_select(self) -> Vec<SelectExpr> {
+        vec![
+            column(self.0),
+            column(self.1),
+            column(self.2),
+            column(self.3),
+        ]
     }
 }
@@ -0,0 +1,59 @@
+use crate::error::{QResult, QueryError};
+use crate::{build_query, SQLValue};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+
+/// A rendered query without its values, see [crate::Select::split].
+///
+/// The shape records the type of each bound value, so it can be stored or
+/// logged and later bound again with fresh values of the same types.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{SQLValue, Select};
+/// let (shape, _) = Select::from("users").where_(("id = ?", 1_i64))?.split();
+///
+/// let q = shape.rebind(vec![SQLValue::I64(2)])?;
+/// assert_eq!("select * from users where id = $1 ", q.sql());
+///
+/// assert!(shape.rebind(vec![SQLValue::String("2".into())]).is_err());
+/// assert!(shape.rebind(vec![]).is_err());
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryShape {
+    pub sql: String,
+    pub types: Vec<String>,
+}
+
+impl QueryShape {
+    pub(crate) fn new(sql: String, values: &[SQLValue]) -> Self {
+        let types = values.iter().map(|v| v.type_name().to_string()).collect();
+        Self { sql, types }
+    }
+
+    /// Binds `values` to the shape. There must be one value per placeholder
+    /// and each must have the same type as the value it replaces. A null is
+    /// accepted in place of any type, and a slot first bound to a null,
+    /// whose type is unknown, accepts any value.
+    pub fn rebind<'args>(&self, values: Vec<SQLValue>) -> QResult<QueryBuilder<'args, Postgres>> {
+        if values.len() != self.types.len() {
+            return Err(QueryError::IncorrectPlaceholderCount(
+                self.sql.clone(),
+                self.types.len(),
+            ));
+        }
+        for (index, (expected, value)) in self.types.iter().zip(&values).enumerate() {
+            let found = value.type_name();
+            let untyped = expected == SQLValue::Null.type_name();
+            if found != expected && !untyped && !matches!(value, SQLValue::Null) {
+                return Err(QueryError::BindTypeMismatch {
+                    index,
+                    expected: expected.clone(),
+                    found: found.to_string(),
+                });
+            }
+        }
+        Ok(build_query(&self.sql, values, String::new))
+    }
+}
@@ -1,6 +1,10 @@
 use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::error::{QResult, QueryError};
 
 /// SQLValue is an enum wrapper around the various types that can be bound to a query.
 ///
@@ -21,7 +25,11 @@ use sqlx::{Postgres, QueryBuilder};
 /// let sql = query.sql();
 /// assert_eq!("select * from users where status_id = $1 and email = $2", sql.trim());
 /// ```
-#[derive(Debug, Clone)]
+///
+/// More variants may be added in minor releases, so matches on it need a
+/// wildcard arm.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
 pub enum SQLValue {
     I16(i16),
     I32(i32),
@@ -31,9 +39,13 @@ pub enum SQLValue {
     DateTime(NaiveDateTime),
     Date(NaiveDate),
     VecI64(Vec<i64>),
+    /// An array of values of one type, bound as an array of that type, e.g.
+    /// `text[]` for strings. See [Select::where_in](crate::Select::where_in).
+    Array(TypedArray),
     String(String),
     Bool(bool),
     Json(Value),
+    Uuid(Uuid),
     Null,
 }
 
@@ -48,13 +60,46 @@ impl SQLValue {
             SQLValue::DateTime(v) => qb.push_bind(*v),
             SQLValue::Date(v) => qb.push_bind(*v),
             SQLValue::VecI64(v) => qb.push_bind(v.clone()),
+            SQLValue::Array(v) => match v.clone() {
+                TypedArray::I16(v) => qb.push_bind(v),
+                TypedArray::I32(v) => qb.push_bind(v),
+                TypedArray::I64(v) => qb.push_bind(v),
+                TypedArray::F64(v) => qb.push_bind(v),
+                TypedArray::DateTime(v) => qb.push_bind(v),
+                TypedArray::Date(v) => qb.push_bind(v),
+                TypedArray::String(v) => qb.push_bind(v),
+                TypedArray::Bool(v) => qb.push_bind(v),
+                TypedArray::Json(v) => qb.push_bind(v),
+                TypedArray::Uuid(v) => qb.push_bind(v),
+            },
             SQLValue::String(v) => qb.push_bind(v.clone()),
             SQLValue::Bool(v) => qb.push_bind(*v),
             SQLValue::Json(v) => qb.push_bind(v.clone()),
+            SQLValue::Uuid(v) => qb.push_bind(*v),
             SQLValue::Null => qb.push_bind("null"),
         };
     }
 
+    /// The name of the variant, e.g. `"i64"` or `"datetime"`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            SQLValue::I16(_) => "i16",
+            SQLValue::I32(_) => "i32",
+            SQLValue::I64(_) => "i64",
+            SQLValue::U64(_) => "u64",
+            SQLValue::F64(_) => "f64",
+            SQLValue::DateTime(_) => "datetime",
+            SQLValue::Date(_) => "date",
+            SQLValue::VecI64(_) => "vec_i64",
+            SQLValue::Array(_) => "array",
+            SQLValue::String(_) => "string",
+            SQLValue::Bool(_) => "bool",
+            SQLValue::Json(_) => "json",
+            SQLValue::Uuid(_) => "uuid",
+            SQLValue::Null => "null",
+        }
+    }
+
     /// This method isn't actually used, but is here to enable a compile time check
     /// that we have a From<T> implementation for every type that we want to use.
     #[allow(dead_code)]
@@ -68,14 +113,225 @@ impl SQLValue {
             SQLValue::DateTime(v) => v.into(),
             SQLValue::Date(v) => v.into(),
             SQLValue::VecI64(v) => v.into(),
+            SQLValue::Array(v) => SQLValue::Array(v),
             SQLValue::String(v) => v.into(),
             SQLValue::Bool(v) => v.into(),
             SQLValue::Json(v) => v.into(),
+            SQLValue::Uuid(v) => v.into(),
             SQLValue::Null => ().into(),
         }
     }
 }
 
+/// The elements of a [SQLValue::Array], all of one type. Elements may be
+/// null.
+///
+/// Build one with [TypedArray::new] from [SQLValue]s, or from a `Vec` of
+/// an [ArrayElement] type, which can't mix types.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{ArrayElement, QueryError, SQLValue, TypedArray};
+/// let ids = i64::into_array(vec![1, 2]);
+/// assert_eq!(2, ids.len());
+///
+/// let mixed = [SQLValue::from(1), SQLValue::from("a")];
+/// assert!(matches!(
+///     TypedArray::new(&mixed),
+///     Err(QueryError::BindTypeMismatch { index: 1, .. })
+/// ));
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "values", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum TypedArray {
+    I16(Vec<Option<i16>>),
+    I32(Vec<Option<i32>>),
+    I64(Vec<Option<i64>>),
+    F64(Vec<Option<f64>>),
+    DateTime(Vec<Option<NaiveDateTime>>),
+    Date(Vec<Option<NaiveDate>>),
+    String(Vec<Option<String>>),
+    Bool(Vec<Option<bool>>),
+    Json(Vec<Option<Value>>),
+    Uuid(Vec<Option<Uuid>>),
+}
+
+impl TypedArray {
+    /// The array of `values`, typed by the first non-null one. Empty and
+    /// all null arrays are `bigint[]`, like [SQLValue::VecI64]. Fails with
+    /// [QueryError::BindTypeMismatch] when a non-null element has another
+    /// type than the first, rather than binding it as null.
+    pub fn new(values: &[SQLValue]) -> QResult<Self> {
+        fn elements<T>(
+            values: &[SQLValue],
+            expected: &str,
+            f: impl Fn(&SQLValue) -> Option<T>,
+        ) -> QResult<Vec<Option<T>>> {
+            values
+                .iter()
+                .enumerate()
+                .map(|(index, v)| match (v, f(v)) {
+                    (SQLValue::Null, _) => Ok(None),
+                    (_, Some(v)) => Ok(Some(v)),
+                    (v, None) => Err(QueryError::BindTypeMismatch {
+                        index,
+                        expected: expected.to_string(),
+                        found: v.type_name().to_string(),
+                    }),
+                })
+                .collect()
+        }
+
+        let first = values.iter().find(|v| !matches!(v, SQLValue::Null));
+        let expected = first.map_or("i64", SQLValue::type_name);
+        Ok(match first {
+            Some(SQLValue::I16(_)) => TypedArray::I16(elements(values, expected, |v| match v {
+                SQLValue::I16(v) => Some(*v),
+                _ => None,
+            })?),
+            Some(SQLValue::I32(_)) => TypedArray::I32(elements(values, expected, |v| match v {
+                SQLValue::I32(v) => Some(*v),
+                _ => None,
+            })?),
+            Some(SQLValue::F64(_)) => TypedArray::F64(elements(values, expected, |v| match v {
+                SQLValue::F64(v) => Some(*v),
+                _ => None,
+            })?),
+            Some(SQLValue::DateTime(_)) => {
+                TypedArray::DateTime(elements(values, expected, |v| match v {
+                    SQLValue::DateTime(v) => Some(*v),
+                    _ => None,
+                })?)
+            }
+            Some(SQLValue::Date(_)) => TypedArray::Date(elements(values, expected, |v| match v {
+                SQLValue::Date(v) => Some(*v),
+                _ => None,
+            })?),
+            Some(SQLValue::String(_)) => {
+                TypedArray::String(elements(values, expected, |v| match v {
+                    SQLValue::String(v) => Some(v.clone()),
+                    _ => None,
+                })?)
+            }
+            Some(SQLValue::Bool(_)) => TypedArray::Bool(elements(values, expected, |v| match v {
+                SQLValue::Bool(v) => Some(*v),
+                _ => None,
+            })?),
+            Some(SQLValue::Json(_)) => TypedArray::Json(elements(values, expected, |v| match v {
+                SQLValue::Json(v) => Some(v.clone()),
+                _ => None,
+            })?),
+            Some(SQLValue::Uuid(_)) => TypedArray::Uuid(elements(values, expected, |v| match v {
+                SQLValue::Uuid(v) => Some(*v),
+                _ => None,
+            })?),
+            Some(SQLValue::I64(_) | SQLValue::U64(_)) | None => {
+                TypedArray::I64(elements(values, expected, |v| match v {
+                    SQLValue::I64(v) => Some(*v),
+                    SQLValue::U64(v) => Some(*v as i64),
+                    _ => None,
+                })?)
+            }
+            Some(v) => {
+                return Err(QueryError::Unsupported(format!(
+                    "arrays of {}",
+                    v.type_name()
+                )))
+            }
+        })
+    }
+}
+
+impl TypedArray {
+    pub fn len(&self) -> usize {
+        match self {
+            TypedArray::I16(v) => v.len(),
+            TypedArray::I32(v) => v.len(),
+            TypedArray::I64(v) => v.len(),
+            TypedArray::F64(v) => v.len(),
+            TypedArray::DateTime(v) => v.len(),
+            TypedArray::Date(v) => v.len(),
+            TypedArray::String(v) => v.len(),
+            TypedArray::Bool(v) => v.len(),
+            TypedArray::Json(v) => v.len(),
+            TypedArray::Uuid(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The elements as [SQLValue]s, nulls as [SQLValue::Null].
+    pub fn into_values(self) -> Vec<SQLValue> {
+        fn values<T: Into<SQLValue>>(v: Vec<Option<T>>) -> Vec<SQLValue> {
+            v.into_iter()
+                .map(|v| v.map_or(SQLValue::Null, Into::into))
+                .collect()
+        }
+        match self {
+            TypedArray::I16(v) => values(v),
+            TypedArray::I32(v) => values(v),
+            TypedArray::I64(v) => values(v),
+            TypedArray::F64(v) => values(v),
+            TypedArray::DateTime(v) => values(v),
+            TypedArray::Date(v) => values(v),
+            TypedArray::String(v) => values(v),
+            TypedArray::Bool(v) => values(v),
+            TypedArray::Json(v) => values(v),
+            TypedArray::Uuid(v) => values(v),
+        }
+    }
+}
+
+/// A type whose values bind as one [TypedArray], so a `Vec` of it can be
+/// passed to [Select::where_in](crate::Select::where_in) without a type
+/// check.
+pub trait ArrayElement: Sized {
+    fn into_array(values: Vec<Self>) -> TypedArray;
+}
+
+macro_rules! impl_array_element {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl ArrayElement for $ty {
+                fn into_array(values: Vec<Self>) -> TypedArray {
+                    TypedArray::$variant(values.into_iter().map(|v| Some(v.into())).collect())
+                }
+            }
+        )*
+    };
+}
+
+impl_array_element! {
+    i16 => I16,
+    i32 => I32,
+    i64 => I64,
+    f64 => F64,
+    NaiveDateTime => DateTime,
+    NaiveDate => Date,
+    String => String,
+    &str => String,
+    &String => String,
+    bool => Bool,
+    Value => Json,
+    Uuid => Uuid,
+}
+
+/// Bound as `bigint[]`, like [SQLValue::U64] is bound as a `bigint`.
+impl ArrayElement for u64 {
+    fn into_array(values: Vec<Self>) -> TypedArray {
+        TypedArray::I64(values.into_iter().map(|v| Some(v as i64)).collect())
+    }
+}
+
+impl From<TypedArray> for SQLValue {
+    fn from(v: TypedArray) -> Self {
+        SQLValue::Array(v)
+    }
+}
+
 impl From<i16> for SQLValue {
     fn from(v: i16) -> Self {
         SQLValue::I16(v)
@@ -112,6 +368,12 @@ impl From<Vec<i64>> for SQLValue {
     }
 }
 
+impl From<Uuid> for SQLValue {
+    fn from(v: Uuid) -> Self {
+        SQLValue::Uuid(v)
+    }
+}
+
 impl From<u64> for SQLValue {
     fn from(v: u64) -> Self {
         SQLValue::U64(v)
@@ -0,0 +1,406 @@
+//! A versioned serde format for storing queries, e.g. saved reports.
+//!
+//! The in-memory [Select] changes shape between releases, so it isn't
+//! serialized directly. A [StoredSelect] keeps each clause as rendered SQL
+//! with its values, tagged with the format version.
+//!
+//! New fields are added to the current version with a default, so older
+//! documents still deserialize. A change that can't be expressed that way
+//! adds a new version variant, and [StoredSelect::into_latest] migrates
+//! older versions forward.
+
+use crate::bool_kind::BoolKind;
+use crate::error::QueryError;
+use crate::r#where::Where;
+use crate::{Lock, OrderDir, SQLValue, Select, SelectExpr, TableType, TypedArray};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+/// A [Select] in a format that survives crate upgrades.
+///
+/// Custom clauses, column policies, value transforms and RLS contexts
+/// aren't stored, and a query using them can't be converted. The restored query renders the
+/// same SQL and can be extended like any other.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{Select, StoredSelect};
+/// let q = Select::from("orders")
+///     .inner_join("users on users.id = orders.user_id")?
+///     .where_(("orders.total > ?", 100))?;
+///
+/// let json = serde_json::to_string(&StoredSelect::try_from(q)?).unwrap();
+/// let stored: StoredSelect = serde_json::from_str(&json).unwrap();
+/// let q = stored.into_select().where_(("users.active = ?", true))?;
+/// assert_eq!(
+///     "select * from orders inner join users on users.id = orders.user_id where orders.total > $1 and users.active = $2 ",
+///     q.into_builder().sql()
+/// );
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "version")]
+pub enum StoredSelect {
+    #[serde(rename = "1")]
+    V1(StoredSelectV1),
+}
+
+impl StoredSelect {
+    /// Migrates the stored query to the current version.
+    pub fn into_latest(self) -> StoredSelectV1 {
+        match self {
+            StoredSelect::V1(v1) => v1,
+        }
+    }
+}
+
+/// Version 1 of [StoredSelect].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredSelectV1 {
+    /// Common table expressions, with their names as the aliases.
+    #[serde(default)]
+    with: Vec<StoredExpr>,
+    #[serde(default)]
+    select: Vec<StoredExpr>,
+    /// Everything after ` from ` up to the where clause, joins included.
+    #[serde(default)]
+    from: Option<StoredExpr>,
+    #[serde(default, rename = "where")]
+    where_: Vec<StoredWhere>,
+    #[serde(default)]
+    group_by: Option<String>,
+    #[serde(default)]
+    having: Vec<StoredWhere>,
+    #[serde(default)]
+    order_by: Vec<StoredOrderKey>,
+    #[serde(default)]
+    limit: Option<u64>,
+    #[serde(default)]
+    default_limit: Option<u64>,
+    #[serde(default)]
+    offset: Option<u64>,
+    #[serde(default)]
+    limit_percent: Option<f64>,
+    #[serde(default)]
+    with_ties: bool,
+    #[serde(default)]
+    lock: Option<StoredLock>,
+    #[serde(default)]
+    tag: Option<String>,
+    #[serde(default)]
+    uppercase_keywords: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredExpr {
+    sql: String,
+    #[serde(default)]
+    values: Vec<StoredValue>,
+    #[serde(default)]
+    alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredWhere {
+    sql: String,
+    #[serde(default)]
+    values: Vec<StoredValue>,
+    #[serde(default)]
+    or: bool,
+}
+
+/// Keys without values keep the original `[sql, dir]` form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredOrderKey {
+    Plain(String, StoredOrder),
+    Bound {
+        sql: String,
+        values: Vec<StoredValue>,
+        dir: StoredOrder,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StoredOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredLock {
+    strength: StoredLockStrength,
+    #[serde(default)]
+    of: Vec<String>,
+    #[serde(default)]
+    wait: Option<StoredLockWait>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StoredLockStrength {
+    Update,
+    Share,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StoredLockWait {
+    SkipLocked,
+    Nowait,
+}
+
+/// Mirrors [SQLValue], with names that don't follow its variants.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+enum StoredValue {
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    DateTime(NaiveDateTime),
+    Date(NaiveDate),
+    VecI64(Vec<i64>),
+    Array(TypedArray),
+    String(String),
+    Bool(bool),
+    Json(serde_json::Value),
+    Uuid(uuid::Uuid),
+    Null,
+}
+
+impl From<SQLValue> for StoredValue {
+    fn from(value: SQLValue) -> Self {
+        match value {
+            SQLValue::I16(v) => StoredValue::I16(v),
+            SQLValue::I32(v) => StoredValue::I32(v),
+            SQLValue::I64(v) => StoredValue::I64(v),
+            SQLValue::U64(v) => StoredValue::U64(v),
+            SQLValue::F64(v) => StoredValue::F64(v),
+            SQLValue::DateTime(v) => StoredValue::DateTime(v),
+            SQLValue::Date(v) => StoredValue::Date(v),
+            SQLValue::VecI64(v) => StoredValue::VecI64(v),
+            SQLValue::Array(v) => StoredValue::Array(v),
+            SQLValue::String(v) => StoredValue::String(v),
+            SQLValue::Bool(v) => StoredValue::Bool(v),
+            SQLValue::Json(v) => StoredValue::Json(v),
+            SQLValue::Uuid(v) => StoredValue::Uuid(v),
+            SQLValue::Null => StoredValue::Null,
+        }
+    }
+}
+
+impl From<StoredValue> for SQLValue {
+    fn from(value: StoredValue) -> Self {
+        match value {
+            StoredValue::I16(v) => SQLValue::I16(v),
+            StoredValue::I32(v) => SQLValue::I32(v),
+            StoredValue::I64(v) => SQLValue::I64(v),
+            StoredValue::U64(v) => SQLValue::U64(v),
+            StoredValue::F64(v) => SQLValue::F64(v),
+            StoredValue::DateTime(v) => SQLValue::DateTime(v),
+            StoredValue::Date(v) => SQLValue::Date(v),
+            StoredValue::VecI64(v) => SQLValue::VecI64(v),
+            StoredValue::Array(v) => SQLValue::Array(v),
+            StoredValue::String(v) => SQLValue::String(v),
+            StoredValue::Bool(v) => SQLValue::Bool(v),
+            StoredValue::Json(v) => SQLValue::Json(v),
+            StoredValue::Uuid(v) => SQLValue::Uuid(v),
+            StoredValue::Null => SQLValue::Null,
+        }
+    }
+}
+
+fn store_values(values: Vec<SQLValue>) -> Vec<StoredValue> {
+    values.into_iter().map(Into::into).collect()
+}
+
+fn restore_values(values: Vec<StoredValue>) -> Vec<SQLValue> {
+    values.into_iter().map(Into::into).collect()
+}
+
+fn store_where(w: Where) -> StoredWhere {
+    match w {
+        Where::Simple { expr, values, kind } => StoredWhere {
+            sql: expr,
+            values: store_values(values),
+            or: matches!(kind, BoolKind::Or),
+        },
+    }
+}
+
+fn restore_where(w: StoredWhere) -> Where {
+    Where::Simple {
+        expr: w.sql,
+        values: restore_values(w.values),
+        kind: if w.or { BoolKind::Or } else { BoolKind::And },
+    }
+}
+
+impl TryFrom<Select> for StoredSelect {
+    type Error = QueryError;
+
+    fn try_from(mut select: Select) -> Result<Self, Self::Error> {
+        if let Some(clause) = select.clauses.first() {
+            return Err(unsupported(&format!("custom clause {clause:?}")));
+        }
+        if select.policy.is_some() {
+            return Err(unsupported("column policy"));
+        }
+        if select.transform.is_some() {
+            return Err(unsupported("value transform"));
+        }
+        if select.rls.is_some() {
+            return Err(unsupported("RLS context"));
+        }
+        if select.schema.is_some() {
+            return Err(unsupported("schema search path"));
+        }
+
+        let from = select.table.is_some().then(|| {
+            let (mut sql, mut values) = (String::new(), vec![]);
+            select.push_from(&mut sql, &mut values);
+            StoredExpr {
+                sql,
+                values: store_values(values),
+                alias: None,
+            }
+        });
+
+        Ok(StoredSelect::V1(StoredSelectV1 {
+            with: select
+                .ctes
+                .into_iter()
+                .map(|(name, sql, values)| StoredExpr {
+                    sql,
+                    values: store_values(values),
+                    alias: Some(name),
+                })
+                .collect(),
+            select: select
+                .select
+                .into_iter()
+                .map(|s| StoredExpr {
+                    sql: s.expr,
+                    values: store_values(s.values),
+                    alias: s.alias,
+                })
+                .collect(),
+            from,
+            where_: select.where_.into_iter().map(store_where).collect(),
+            group_by: select.group_by,
+            having: select.having.into_iter().map(store_where).collect(),
+            order_by: select
+                .order_by
+                .into_iter()
+                .map(|(sql, values, dir)| {
+                    let dir = match dir {
+                        OrderDir::Asc => StoredOrder::Asc,
+                        OrderDir::Desc => StoredOrder::Desc,
+                    };
+                    if values.is_empty() {
+                        StoredOrderKey::Plain(sql, dir)
+                    } else {
+                        StoredOrderKey::Bound {
+                            sql,
+                            values: store_values(values),
+                            dir,
+                        }
+                    }
+                })
+                .collect(),
+            limit: select.limit,
+            default_limit: select.default_limit,
+            offset: select.offset,
+            limit_percent: select.limit_percent,
+            with_ties: select.with_ties,
+            lock: select.lock.map(|lock| StoredLock {
+                strength: match lock.strength {
+                    "share" => StoredLockStrength::Share,
+                    _ => StoredLockStrength::Update,
+                },
+                of: lock.of,
+                wait: lock.wait.map(|wait| match wait {
+                    "nowait" => StoredLockWait::Nowait,
+                    _ => StoredLockWait::SkipLocked,
+                }),
+            }),
+            tag: select.tag,
+            uppercase_keywords: select.uppercase,
+        }))
+    }
+}
+
+impl StoredSelect {
+    /// Restores the query, migrating it to the current version first.
+    pub fn into_select(self) -> Select {
+        let v1 = self.into_latest();
+        Select {
+            ctes: v1
+                .with
+                .into_iter()
+                .map(|cte| {
+                    let name = cte.alias.unwrap_or_default();
+                    (name, cte.sql, restore_values(cte.values))
+                })
+                .collect(),
+            table: v1
+                .from
+                .map(|from| TableType::Bound(from.sql, restore_values(from.values))),
+            select: v1
+                .select
+                .into_iter()
+                .map(|s| SelectExpr {
+                    expr: s.sql,
+                    values: restore_values(s.values),
+                    alias: s.alias,
+                })
+                .collect(),
+            where_: v1.where_.into_iter().map(restore_where).collect(),
+            group_by: v1.group_by,
+            having: v1.having.into_iter().map(restore_where).collect(),
+            order_by: v1
+                .order_by
+                .into_iter()
+                .map(|key| {
+                    let (sql, values, dir) = match key {
+                        StoredOrderKey::Plain(sql, dir) => (sql, vec![], dir),
+                        StoredOrderKey::Bound { sql, values, dir } => {
+                            (sql, restore_values(values), dir)
+                        }
+                    };
+                    let dir = match dir {
+                        StoredOrder::Asc => OrderDir::Asc,
+                        StoredOrder::Desc => OrderDir::Desc,
+                    };
+                    (sql, values, dir)
+                })
+                .collect(),
+            limit: v1.limit,
+            default_limit: v1.default_limit,
+            offset: v1.offset,
+            limit_percent: v1.limit_percent,
+            with_ties: v1.with_ties,
+            lock: v1.lock.map(|lock| Lock {
+                strength: match lock.strength {
+                    StoredLockStrength::Update => "update",
+                    StoredLockStrength::Share => "share",
+                },
+                of: lock.of,
+                wait: lock.wait.map(|wait| match wait {
+                    StoredLockWait::SkipLocked => "skip locked",
+                    StoredLockWait::Nowait => "nowait",
+                }),
+            }),
+            tag: v1.tag,
+            uppercase: v1.uppercase_keywords,
+            ..Select::default()
+        }
+    }
+}
+
+fn unsupported(what: &str) -> QueryError {
+    QueryError::Unsupported(format!("{what} can't be stored"))
+}
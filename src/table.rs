@@ -0,0 +1,82 @@
+use crate::util::{is_identifier, RESERVED};
+
+/// A table reference, optionally schema qualified and aliased.
+///
+/// Each part is quoted when it needs to be, so names with upper case
+/// letters or other characters survive Postgres' identifier folding, and
+/// reserved words such as `user` can be used.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{Join, Select, Table};
+/// let events = Table::new("events").schema("analytics").alias("e");
+/// let q = Select::from(events)
+///     .join(Join::inner(Table::new("Users").schema("crm")).on("\"Users\".id = e.user_id")?);
+/// assert_eq!(
+///     "select * from analytics.events as e inner join crm.\"Users\" on \"Users\".id = e.user_id",
+///     q.into_builder().sql()
+/// );
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub schema: Option<String>,
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+impl Table {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            schema: None,
+            name: name.into(),
+            alias: None,
+        }
+    }
+
+    pub fn schema(mut self, schema: impl Into<String>) -> Self {
+        self.schema = Some(schema.into());
+        self
+    }
+
+    pub fn alias(mut self, alias: impl Into<String>) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+
+    /// The name columns of this table should be qualified with, quoted
+    /// like in [Table::to_sql].
+    pub fn qualifier(&self) -> String {
+        quote(self.alias.as_deref().unwrap_or(&self.name))
+    }
+
+    pub fn to_sql(&self) -> String {
+        let mut sql = String::new();
+        if let Some(schema) = &self.schema {
+            sql.push_str(&quote(schema));
+            sql.push('.');
+        }
+        sql.push_str(&quote(&self.name));
+        if let Some(alias) = &self.alias {
+            sql.push_str(" as ");
+            sql.push_str(&quote(alias));
+        }
+        sql
+    }
+}
+
+impl From<Table> for String {
+    fn from(table: Table) -> Self {
+        table.to_sql()
+    }
+}
+
+/// Quotes `s` unless it is a plain lower case identifier and not a
+/// reserved word.
+pub(crate) fn quote(s: &str) -> String {
+    if is_identifier(s) && !s.chars().any(|c| c.is_ascii_uppercase()) && !RESERVED.contains(&s) {
+        s.to_string()
+    } else {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    }
+}
@@ -0,0 +1,76 @@
+//! Helpers for TimescaleDB, whose queries mostly group on `time_bucket`.
+
+use crate::error::{located, QResult, QueryError};
+use crate::{Select, SelectExpr};
+
+impl Select {
+    /// Adds `time_bucket(interval, col) as alias` to the select list, with
+    /// the interval bound as a value.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("metrics")
+    ///     .select_time_bucket("5 minutes", "ts", "bucket")
+    ///     .select("avg(value)");
+    /// assert_eq!(
+    ///     "select time_bucket($1::interval, ts) as bucket, avg(value) from metrics",
+    ///     q.into_builder().sql()
+    /// );
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn select_time_bucket(
+        self,
+        interval: impl Into<String>,
+        col: impl Into<String>,
+        alias: impl Into<String>,
+    ) -> Self {
+        let expr = format!("time_bucket(?::interval, {})", col.into());
+        self.select(SelectExpr::new(expr, vec![interval.into().into()]).alias(alias))
+    }
+
+    /// Adds `time_bucket('interval'::interval, col) as alias` to the select
+    /// list, and groups by the same expression.
+    ///
+    /// The interval is written into the query rather than bound, as
+    /// Postgres treats each bound interval as a different expression, so
+    /// it may only hold letters, digits, spaces and `.:-+`. Anything else
+    /// fails with [QueryError::InvalidIdentifier]. Grouping by the
+    /// expression rather than the alias keeps an input column of the same
+    /// name from being grouped on instead.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Select;
+    /// let q = Select::from("metrics")
+    ///     .select("device_id")
+    ///     .group_by("device_id")
+    ///     .group_by_time_bucket("1 hour", "ts", "hour")?
+    ///     .select("max(value)");
+    /// assert_eq!(
+    ///     "select device_id, time_bucket('1 hour'::interval, ts) as hour, max(value) from metrics group by device_id, time_bucket('1 hour'::interval, ts) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// assert!(Select::from("metrics").group_by_time_bucket("1 hour'", "ts", "hour").is_err());
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn group_by_time_bucket(
+        self,
+        interval: &str,
+        col: impl Into<String>,
+        alias: impl Into<String>,
+    ) -> QResult<Self> {
+        let valid = |c: char| c.is_ascii_alphanumeric() || " .:-+".contains(c);
+        if interval.trim().is_empty() || !interval.chars().all(valid) {
+            return located(Err(QueryError::InvalidIdentifier(interval.to_string())));
+        }
+        let expr = format!("time_bucket('{interval}'::interval, {})", col.into());
+        let mut q = self.select(SelectExpr::new(expr.clone(), vec![]).alias(alias));
+        q.group_by = Some(match q.group_by {
+            Some(group_by) => format!("{group_by}, {expr}"),
+            None => expr,
+        });
+        Ok(q)
+    }
+}
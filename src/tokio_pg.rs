@@ -0,0 +1,113 @@
+//! Output for [tokio-postgres](https://docs.rs/tokio-postgres), for codebases
+//! not using sqlx.
+
+use crate::util::number_placeholders;
+use crate::{SQLValue, Select, TypedArray};
+use bytes::BytesMut;
+use postgres_types::{IsNull, ToSql, Type};
+use std::error::Error;
+
+type ToSqlResult = Result<IsNull, Box<dyn Error + Sync + Send>>;
+
+impl ToSql for SQLValue {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> ToSqlResult {
+        match self {
+            SQLValue::I16(v) => v.to_sql(ty, out),
+            SQLValue::I32(v) => v.to_sql(ty, out),
+            SQLValue::I64(v) => v.to_sql(ty, out),
+            SQLValue::U64(v) => (*v as i64).to_sql(ty, out),
+            SQLValue::F64(v) => v.to_sql(ty, out),
+            SQLValue::DateTime(v) => v.to_sql(ty, out),
+            SQLValue::Date(v) => v.to_sql(ty, out),
+            SQLValue::VecI64(v) => v.to_sql(ty, out),
+            SQLValue::Array(v) => match v {
+                TypedArray::I16(v) => v.to_sql(ty, out),
+                TypedArray::I32(v) => v.to_sql(ty, out),
+                TypedArray::I64(v) => v.to_sql(ty, out),
+                TypedArray::F64(v) => v.to_sql(ty, out),
+                TypedArray::DateTime(v) => v.to_sql(ty, out),
+                TypedArray::Date(v) => v.to_sql(ty, out),
+                TypedArray::String(v) => v.to_sql(ty, out),
+                TypedArray::Bool(v) => v.to_sql(ty, out),
+                TypedArray::Json(v) => v.to_sql(ty, out),
+                TypedArray::Uuid(v) => v.to_sql(ty, out),
+            },
+            SQLValue::String(v) => v.to_sql(ty, out),
+            SQLValue::Bool(v) => v.to_sql(ty, out),
+            SQLValue::Json(v) => v.to_sql(ty, out),
+            SQLValue::Uuid(v) => v.to_sql(ty, out),
+            SQLValue::Null => Ok(IsNull::Yes),
+        }
+    }
+
+    /// The real check happens per variant in [ToSql::to_sql_checked].
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    fn to_sql_checked(&self, ty: &Type, out: &mut BytesMut) -> ToSqlResult {
+        match self {
+            SQLValue::I16(v) => v.to_sql_checked(ty, out),
+            SQLValue::I32(v) => v.to_sql_checked(ty, out),
+            SQLValue::I64(v) => v.to_sql_checked(ty, out),
+            SQLValue::U64(v) => (*v as i64).to_sql_checked(ty, out),
+            SQLValue::F64(v) => v.to_sql_checked(ty, out),
+            SQLValue::DateTime(v) => v.to_sql_checked(ty, out),
+            SQLValue::Date(v) => v.to_sql_checked(ty, out),
+            SQLValue::VecI64(v) => v.to_sql_checked(ty, out),
+            SQLValue::Array(v) => match v {
+                TypedArray::I16(v) => v.to_sql_checked(ty, out),
+                TypedArray::I32(v) => v.to_sql_checked(ty, out),
+                TypedArray::I64(v) => v.to_sql_checked(ty, out),
+                TypedArray::F64(v) => v.to_sql_checked(ty, out),
+                TypedArray::DateTime(v) => v.to_sql_checked(ty, out),
+                TypedArray::Date(v) => v.to_sql_checked(ty, out),
+                TypedArray::String(v) => v.to_sql_checked(ty, out),
+                TypedArray::Bool(v) => v.to_sql_checked(ty, out),
+                TypedArray::Json(v) => v.to_sql_checked(ty, out),
+                TypedArray::Uuid(v) => v.to_sql_checked(ty, out),
+            },
+            SQLValue::String(v) => v.to_sql_checked(ty, out),
+            SQLValue::Bool(v) => v.to_sql_checked(ty, out),
+            SQLValue::Json(v) => v.to_sql_checked(ty, out),
+            SQLValue::Uuid(v) => v.to_sql_checked(ty, out),
+            SQLValue::Null => Ok(IsNull::Yes),
+        }
+    }
+}
+
+/// A rendered query with `$n` placeholders, ready for tokio-postgres.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::Select;
+/// let q = Select::from("users").where_(("id = ?", 1))?.to_tokio_postgres();
+/// assert_eq!("select * from users where id = $1 ", q.sql);
+/// // client.query(&q.sql, &q.params()).await?;
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TokioPostgresQuery {
+    pub sql: String,
+    pub values: Vec<SQLValue>,
+}
+
+impl TokioPostgresQuery {
+    /// The values in the form expected by `Client::query` and friends.
+    pub fn params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.values
+            .iter()
+            .map(|v| v as &(dyn ToSql + Sync))
+            .collect()
+    }
+}
+
+impl Select {
+    pub fn to_tokio_postgres(self) -> TokioPostgresQuery {
+        let (sql, values) = self.parts();
+        TokioPostgresQuery {
+            sql: number_placeholders(&sql),
+            values,
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::util::fold_identifier;
+use crate::{SQLValue, SelectExpr};
+use std::collections::HashSet;
+use std::fmt::{Debug, Formatter};
+
+/// A hook rewriting how values of some columns are written and read, e.g.
+/// for application level encryption. Attach it with
+/// [Select::with_transform](crate::Select::with_transform),
+/// [Insert::with_transform](crate::Insert::with_transform) and
+/// [Update::with_transform](crate::Update::with_transform).
+///
+/// Inserts and updates pass each bound value through [ValueTransform::write].
+/// Selects replace named columns with [ValueTransform::read], keeping the
+/// column name as the alias. Like [ColumnPolicy](crate::ColumnPolicy),
+/// only columns named in the select list are affected, and where clauses
+/// are left alone. Column names are passed as Postgres resolves them:
+/// lower case unless quoted.
+pub trait ValueTransform: Debug + Send + Sync {
+    /// The expression to write `value` to `column` with, and its values.
+    /// The default binds the value as is.
+    fn write(&self, column: &str, value: SQLValue) -> (String, Vec<SQLValue>) {
+        let _ = column;
+        ("?".to_string(), vec![value])
+    }
+
+    /// The expression to read `column` with, and its values, or `None` to
+    /// read it as is. `expr` is the column as written in the select list,
+    /// e.g. `users.ssn` for the column `ssn`.
+    fn read(&self, column: &str, expr: &str) -> Option<(String, Vec<SQLValue>)> {
+        let _ = (column, expr);
+        None
+    }
+}
+
+/// Encrypts the configured columns with pgcrypto's `pgp_sym_encrypt` and
+/// decrypts them with `pgp_sym_decrypt`. The key is bound, never rendered,
+/// and left out of the `Debug` output.
+///
+/// Values are encrypted as text and decrypted columns read back as text.
+/// Nulls are written as null rather than encrypted.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{Insert, PgpSymEncryption, Select};
+/// use std::sync::Arc;
+///
+/// let pgp = Arc::new(PgpSymEncryption::new("secret").column("ssn"));
+/// let q = Insert::into("users")
+///     .value("name", "Alice")
+///     .value("ssn", "123-45-6789")
+///     .with_transform(pgp.clone());
+/// assert_eq!(
+///     "insert into users (name, ssn) values ($1, pgp_sym_encrypt($2::text, $3))",
+///     q.into_builder().sql()
+/// );
+///
+/// let q = Select::from("users")
+///     .select(("name", "users.ssn"))
+///     .with_transform(pgp);
+/// assert_eq!(
+///     "select name, pgp_sym_decrypt(users.ssn, $1) as ssn from users",
+///     q.into_builder().sql()
+/// );
+/// ```
+#[derive(Clone)]
+pub struct PgpSymEncryption {
+    key: String,
+    columns: HashSet<String>,
+}
+
+impl PgpSymEncryption {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            columns: HashSet::new(),
+        }
+    }
+
+    /// Encrypts `column`, matched by name with or without a table qualifier.
+    pub fn column(mut self, column: impl Into<String>) -> Self {
+        self.columns
+            .insert(fold_identifier(&column.into()).into_owned());
+        self
+    }
+}
+
+impl Debug for PgpSymEncryption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PgpSymEncryption")
+            .field("columns", &self.columns)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ValueTransform for PgpSymEncryption {
+    fn write(&self, column: &str, value: SQLValue) -> (String, Vec<SQLValue>) {
+        if !self.columns.contains(column) || matches!(value, SQLValue::Null) {
+            return ("?".to_string(), vec![value]);
+        }
+        (
+            "pgp_sym_encrypt(?::text, ?)".to_string(),
+            vec![value, self.key.clone().into()],
+        )
+    }
+
+    fn read(&self, column: &str, expr: &str) -> Option<(String, Vec<SQLValue>)> {
+        self.columns.contains(column).then(|| {
+            (
+                format!("pgp_sym_decrypt({expr}, ?)"),
+                vec![self.key.clone().into()],
+            )
+        })
+    }
+}
+
+/// Applies `transform` to the named columns of a select list.
+pub(crate) fn read_all(transform: &dyn ValueTransform, select: Vec<SelectExpr>) -> Vec<SelectExpr> {
+    select
+        .into_iter()
+        .map(|s| {
+            if !s.values.is_empty() {
+                return s;
+            }
+            let expr = s.expr.trim();
+            let name = expr.rsplit('.').next().unwrap_or(expr);
+            match transform.read(&fold_identifier(name), expr) {
+                None => s,
+                Some((read, values)) => {
+                    let alias = s.alias.clone().unwrap_or_else(|| name.to_string());
+                    SelectExpr::new(read, values).alias(alias)
+                }
+            }
+        })
+        .collect()
+}
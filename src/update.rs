@@ -0,0 +1,154 @@
+use crate::error::{QResult, QueryError};
+use crate::r#where::{push_where_list, Where};
+use crate::util::{fold_identifier, placeholder_count, validate_column};
+use crate::{alias, build_query, located, SQLValue, ValueTransform};
+use itertools::Itertools;
+use sqlx::{Postgres, QueryBuilder};
+use std::sync::Arc;
+
+/// Builds an `update` statement.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::Update;
+/// let q = Update::table("users")
+///     .set("name", "Alice")
+///     .set_expr("updated_at", "now()")?
+///     .where_(("id = ?", 7))?
+///     .returning(["id"]);
+/// assert_eq!(
+///     "update users set name = $1, updated_at = now() where id = $2 returning id",
+///     q.into_builder().sql()
+/// );
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Update {
+    table: String,
+    /// Each column with an expression with `?` placeholders and its values.
+    set: Vec<(String, String, Vec<SQLValue>)>,
+    where_: Vec<Where>,
+    returning: Vec<String>,
+    transform: Option<Arc<dyn ValueTransform>>,
+}
+
+impl Update {
+    pub fn table(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            set: vec![],
+            where_: vec![],
+            returning: vec![],
+            transform: None,
+        }
+    }
+
+    /// Renders `col = ?`.
+    pub fn set(mut self, col: impl Into<String>, value: impl Into<SQLValue>) -> Self {
+        self.set
+            .push((col.into(), "?".to_string(), vec![value.into()]));
+        self
+    }
+
+    /// Renders `col = expr`, e.g. `now()` or `count + 1`. The expression
+    /// can't contain placeholders.
+    pub fn set_expr(mut self, col: impl Into<String>, expr: impl Into<String>) -> QResult<Self> {
+        let expr = expr.into();
+        placeholder_count(&expr, 0)?;
+        self.set.push((col.into(), expr, vec![]));
+        Ok(self)
+    }
+
+    /// Adds a where condition. Accepts the same forms as
+    /// [Select::where_](crate::Select::where_).
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_<T, E>(mut self, where_: T) -> QResult<Self>
+    where
+        T: TryInto<Where, Error = E>,
+        QueryError: From<E>,
+    {
+        self.where_.push(located(where_.try_into())?);
+        Ok(self)
+    }
+
+    /// Optimistic locking: only updates the row if `col` still holds
+    /// `current`, the version it was read at, and increments it. Zero rows
+    /// affected means another writer got there first.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Update;
+    /// let q = Update::table("documents")
+    ///     .set("body", "new text")
+    ///     .where_(("id = ?", 3))?
+    ///     .where_version("version", 12)?;
+    /// assert_eq!(
+    ///     "update documents set body = $1, version = version + 1 where id = $2 and version = $3",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn where_version(self, col: &str, current: impl Into<SQLValue>) -> QResult<Self> {
+        located(validate_column(col))?;
+        // Postgres doesn't allow a qualified column on the left of `set`.
+        let name = col.rsplit('.').next().unwrap_or(col);
+        self.set_expr(name, format!("{col} + 1"))?
+            .where_((format!("{col} = ?"), current.into()))
+    }
+
+    /// Renders `returning col, ...`.
+    pub fn returning<C: Into<String>>(mut self, cols: impl IntoIterator<Item = C>) -> Self {
+        self.returning.extend(cols.into_iter().map(Into::into));
+        self
+    }
+
+    /// Writes values through a [ValueTransform], e.g. encrypting them.
+    /// Only values are transformed, not [Update::set_expr] expressions.
+    pub fn with_transform(mut self, transform: Arc<dyn ValueTransform>) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    pub fn parts(mut self) -> (String, Vec<SQLValue>) {
+        if let Some(transform) = self.transform.take() {
+            for (col, expr, values) in &mut self.set {
+                if expr == "?" && values.len() == 1 {
+                    (*expr, *values) = transform.write(&fold_identifier(col), values.remove(0));
+                }
+            }
+        }
+
+        let mut q = format!("update {} set ", self.table);
+        let mut vals = vec![];
+
+        q.push_str(
+            &self
+                .set
+                .iter()
+                .map(|(col, expr, _)| format!("{col} = {expr}"))
+                .join(", "),
+        );
+        vals.extend(self.set.into_iter().flat_map(|(_, _, values)| values));
+
+        if !self.where_.is_empty() {
+            let mut where_ = String::new();
+            push_where_list(&self.where_, &mut where_, &mut vals);
+            q.push_str(" where ");
+            q.push_str(where_.trim_end());
+        }
+
+        if !self.returning.is_empty() {
+            q.push_str(" returning ");
+            q.push_str(&self.returning.join(", "));
+        }
+
+        alias::renumber(&mut q, 0);
+        (q, vals)
+    }
+
+    pub fn into_builder<'args>(self) -> QueryBuilder<'args, Postgres> {
+        let (sql, values) = self.parts();
+        build_query(&sql, values, String::new)
+    }
+}
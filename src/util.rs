@@ -1,4 +1,59 @@
 use crate::error::{QResult, QueryError};
+use std::borrow::Cow;
+
+/// A single unquoted identifier, e.g. `users` or `created_at`.
+pub fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Checks `s` is a column name, optionally qualified, e.g. `users.id`.
+pub fn validate_column(s: &str) -> QResult<()> {
+    if s.split('.').all(is_identifier) {
+        Ok(())
+    } else {
+        Err(QueryError::InvalidIdentifier(s.to_string()))
+    }
+}
+
+/// The name Postgres resolves the identifier `s` to: unquoted identifiers
+/// fold to lower case, quoted ones are kept exactly, without their quotes.
+pub fn fold_identifier(s: &str) -> Cow<'_, str> {
+    match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(quoted) if quoted.contains("\"\"") => Cow::Owned(quoted.replace("\"\"", "\"")),
+        Some(quoted) => Cow::Borrowed(quoted),
+        None if s.bytes().any(|b| b.is_ascii_uppercase()) => Cow::Owned(s.to_ascii_lowercase()),
+        None => Cow::Borrowed(s),
+    }
+}
+
+/// Checks `s` is a type name for a cast, e.g. `int`, `double precision`,
+/// `numeric(10, 2)` or `text[]`.
+pub fn validate_type(s: &str) -> QResult<()> {
+    let invalid = || QueryError::InvalidIdentifier(s.to_string());
+    let mut name = s.trim();
+    while let Some(rest) = name.strip_suffix("[]") {
+        name = rest.trim_end();
+    }
+    if let Some(rest) = name.strip_suffix(')') {
+        let (rest, modifiers) = rest.split_once('(').ok_or_else(invalid)?;
+        let numeric =
+            |m: &str| !m.trim().is_empty() && m.trim().chars().all(|c| c.is_ascii_digit());
+        if !modifiers.split(',').all(numeric) {
+            return Err(invalid());
+        }
+        name = rest.trim_end();
+    }
+    if name
+        .split(' ')
+        .all(|word| word.split('.').all(is_identifier))
+    {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
 
 pub fn placeholder_count(s: &str, exp: usize) -> QResult<()> {
     if s.chars().filter(|c| *c == '?').count() != exp {
@@ -7,3 +62,286 @@ pub fn placeholder_count(s: &str, exp: usize) -> QResult<()> {
         Ok(())
     }
 }
+
+/// 64 bit FNV-1a. Used for fingerprints, which need to be stable across
+/// processes and compiler versions, unlike [std::hash::DefaultHasher].
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Replaces each `?` with a numbered Postgres placeholder, starting at `$1`.
+#[cfg(any(feature = "sea-query", feature = "tokio-postgres"))]
+pub fn number_placeholders(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for (i, part) in s.split('?').enumerate() {
+        if i > 0 {
+            out.push('$');
+            out.push_str(&i.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+/// Postgres' reserved key words, which must be quoted to be used as names.
+pub(crate) const RESERVED: &[&str] = &[
+    "all",
+    "analyse",
+    "analyze",
+    "and",
+    "any",
+    "array",
+    "as",
+    "asc",
+    "asymmetric",
+    "authorization",
+    "binary",
+    "both",
+    "case",
+    "cast",
+    "check",
+    "collate",
+    "collation",
+    "column",
+    "concurrently",
+    "constraint",
+    "create",
+    "cross",
+    "current_catalog",
+    "current_date",
+    "current_role",
+    "current_schema",
+    "current_time",
+    "current_timestamp",
+    "current_user",
+    "default",
+    "deferrable",
+    "desc",
+    "distinct",
+    "do",
+    "else",
+    "end",
+    "except",
+    "false",
+    "fetch",
+    "for",
+    "foreign",
+    "freeze",
+    "from",
+    "full",
+    "grant",
+    "group",
+    "having",
+    "ilike",
+    "in",
+    "initially",
+    "inner",
+    "intersect",
+    "into",
+    "is",
+    "isnull",
+    "join",
+    "lateral",
+    "leading",
+    "left",
+    "like",
+    "limit",
+    "localtime",
+    "localtimestamp",
+    "natural",
+    "not",
+    "notnull",
+    "null",
+    "offset",
+    "on",
+    "only",
+    "or",
+    "order",
+    "outer",
+    "overlaps",
+    "placing",
+    "primary",
+    "references",
+    "returning",
+    "right",
+    "select",
+    "session_user",
+    "similar",
+    "some",
+    "symmetric",
+    "system_user",
+    "table",
+    "tablesample",
+    "then",
+    "to",
+    "trailing",
+    "true",
+    "union",
+    "unique",
+    "user",
+    "using",
+    "variadic",
+    "verbose",
+    "when",
+    "where",
+    "window",
+    "with",
+];
+
+/// Words upper cased by [uppercase_keywords]. Unquoted identifiers are case
+/// insensitive in Postgres, so a column sharing a name is unaffected.
+const KEYWORDS: &[&str] = &[
+    "all",
+    "and",
+    "any",
+    "array",
+    "as",
+    "asc",
+    "between",
+    "by",
+    "case",
+    "cast",
+    "collate",
+    "conflict",
+    "constraint",
+    "create",
+    "cross",
+    "default",
+    "delete",
+    "desc",
+    "distinct",
+    "do",
+    "else",
+    "end",
+    "except",
+    "exists",
+    "false",
+    "fetch",
+    "filter",
+    "first",
+    "for",
+    "from",
+    "full",
+    "group",
+    "having",
+    "ilike",
+    "in",
+    "inner",
+    "insert",
+    "intersect",
+    "interval",
+    "into",
+    "is",
+    "join",
+    "lateral",
+    "left",
+    "like",
+    "limit",
+    "locked",
+    "not",
+    "nothing",
+    "nowait",
+    "null",
+    "nulls",
+    "of",
+    "offset",
+    "on",
+    "or",
+    "order",
+    "outer",
+    "over",
+    "partition",
+    "percent",
+    "recursive",
+    "returning",
+    "right",
+    "rows",
+    "select",
+    "set",
+    "share",
+    "skip",
+    "table",
+    "tablesample",
+    "temp",
+    "then",
+    "ties",
+    "true",
+    "union",
+    "update",
+    "using",
+    "values",
+    "when",
+    "where",
+    "window",
+    "with",
+];
+
+/// Upper cases the SQL keywords in `sql`, leaving string literals, quoted
+/// identifiers and comments as they are.
+pub fn uppercase_keywords(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '\'' | '"' => quoted_len(rest, c, false),
+            'e' | 'E' if rest[1..].starts_with('\'') => 1 + quoted_len(&rest[1..], '\'', true),
+            '-' if rest.starts_with("--") => rest.find('\n').unwrap_or(rest.len()),
+            '/' if rest.starts_with("/*") => rest.find("*/").map_or(rest.len(), |i| i + 2),
+            '$' => dollar_quoted_len(rest).unwrap_or(1),
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+                    .unwrap_or(rest.len());
+                let word = &rest[..len];
+                if KEYWORDS.contains(&word.to_ascii_lowercase().as_str()) {
+                    out.push_str(&word.to_ascii_uppercase());
+                } else {
+                    out.push_str(word);
+                }
+                rest = &rest[len..];
+                continue;
+            }
+            c => c.len_utf8(),
+        };
+        out.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    out
+}
+
+/// The length of the literal starting at `s`, which opens with `quote`. A
+/// doubled quote is an escaped one, and so is any character after a
+/// backslash in an `E'...'` string, which sets `backslash`.
+fn quoted_len(s: &str, quote: char, backslash: bool) -> usize {
+    let mut chars = s.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if backslash && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            if chars.peek().map(|(_, c)| *c) == Some(quote) {
+                chars.next();
+            } else {
+                return i + 1;
+            }
+        }
+    }
+    s.len()
+}
+
+/// The length of a `$tag$ ... $tag$` literal starting at `s`, if it is one.
+fn dollar_quoted_len(s: &str) -> Option<usize> {
+    let close = s[1..].find('$')? + 2;
+    let tag = &s[..close];
+    if !tag[1..close - 1]
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+    s[close..].find(tag).map(|i| close + i + tag.len())
+}
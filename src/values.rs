@@ -0,0 +1,33 @@
+use crate::SQLValue;
+
+/// A row of a `values` list, see [crate::Select::from_values].
+///
+/// Implemented for tuples of up to six values and for `Vec<SQLValue>`.
+pub trait ValuesRow {
+    fn into_row(self) -> Vec<SQLValue>;
+}
+
+impl ValuesRow for Vec<SQLValue> {
+    fn into_row(self) -> Vec<SQLValue> {
+        self
+    }
+}
+
+macro_rules! impl_values_row {
+    ($($t:ident),+) => {
+        impl<$($t: Into<SQLValue>),+> ValuesRow for ($($t,)+) {
+            #[allow(non_snake_case)]
+            fn into_row(self) -> Vec<SQLValue> {
+                let ($($t,)+) = self;
+                vec![$($t.into()),+]
+            }
+        }
+    };
+}
+
+impl_values_row!(A);
+impl_values_row!(A, B);
+impl_values_row!(A, B, C);
+impl_values_row!(A, B, C, D);
+impl_values_row!(A, B, C, D, E);
+impl_values_row!(A, B, C, D, E, F);
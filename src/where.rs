@@ -1,7 +1,8 @@
 use crate::bool_kind::BoolKind;
 use crate::error::{QResult, QueryError};
+use crate::op::Op;
 use crate::sql_value::SQLValue;
-use crate::util::placeholder_count;
+use crate::util::{placeholder_count, validate_column, validate_type};
 use std::fmt::Debug;
 
 #[derive(Default)]
@@ -84,6 +85,47 @@ impl<T: Into<SQLValue>> IntoWhere for T {
     }
 }
 
+/// Expands to one placeholder per element, so `id in (?)` with
+/// `List(vec![1, 2, 3])` renders `id in (?, ?, ?)`. Unlike binding an
+/// array for `= any(?)`, the elements can have different types. An empty
+/// list is [QueryError::Unsupported], `in ()` isn't valid SQL.
+///
+/// A plain `Vec<i64>` still binds as a single `bigint[]` value.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{List, Select};
+/// let q = Select::from("users").where_(("id in (?)", List(vec![1, 2, 3])))?;
+/// assert_eq!("select * from users where id in ($1, $2, $3) ", q.into_builder().sql());
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct List<T>(pub Vec<T>);
+
+impl<T: Into<SQLValue>> IntoWhere for List<T> {
+    fn into_where(self, expr: &mut String, vals: &mut Vec<SQLValue>) -> QResult<()> {
+        if self.0.is_empty() {
+            return Err(QueryError::Unsupported(format!(
+                "empty list bound at `{expr}?`"
+            )));
+        }
+        expr.push_str(&vec!["?"; self.0.len()].join(", "));
+        vals.extend(self.0.into_iter().map(Into::into));
+        Ok(())
+    }
+}
+
+/// Inlines the subquery, merging its values in order.
+impl IntoWhere for crate::Select {
+    fn into_where(self, expr: &mut String, vals: &mut Vec<SQLValue>) -> QResult<()> {
+        self.check_policy()?;
+        let (sub_q, sub_vals) = self.render();
+        expr.push_str(sub_q.trim());
+        vals.extend(sub_vals);
+        Ok(())
+    }
+}
+
 impl IntoWhere for Where {
     fn into_where(self, expression: &mut String, vals: &mut Vec<SQLValue>) -> QResult<()> {
         match self {
@@ -118,6 +160,234 @@ impl Where {
             Where::Simple { kind, .. } => *kind,
         }
     }
+
+    /// Negates the clause, rendering `not (expr)` with the same values.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Select, Where};
+    /// let banned = Where::try_from(("status = ? or strikes > ?", "banned", 3))?;
+    /// let q = Select::from("users").where_(Where::not(banned))?;
+    /// assert_eq!(
+    ///     "select * from users where not (status = $1 or strikes > $2) ",
+    ///     q.into_builder().sql()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(w: Where) -> Where {
+        match w {
+            Where::Simple { expr, values, kind } => Where::Simple {
+                expr: format!("not ({expr})"),
+                values,
+                kind,
+            },
+        }
+    }
+
+    /// Rewrites `= ?` to `is null` and `!= ?`/`<> ?` to `is not null`
+    /// wherever the bound value is `NULL`, dropping the value. `= NULL`
+    /// is never true, so the unrewritten clause silently matches nothing.
+    /// A `::type` cast directly after the placeholder is dropped too, or,
+    /// when it can't be told apart from what follows, the clause is left
+    /// as it is.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::Where;
+    /// let deleted_at: Option<&str> = None;
+    /// let w = Where::nullable(Where::try_from((
+    ///     "deleted_at = ?::timestamptz and archived_by <> ?",
+    ///     deleted_at,
+    ///     None::<i64>,
+    /// ))?);
+    /// let Where::Simple { expr, values, .. } = w;
+    /// assert_eq!("deleted_at is null and archived_by is not null", expr);
+    /// assert!(values.is_empty());
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn nullable(w: Where) -> Where {
+        match w {
+            Where::Simple { expr, values, kind } => {
+                let mut parts = expr.split('?');
+                let mut out = parts.next().unwrap_or_default().to_string();
+                let mut kept = Vec::with_capacity(values.len());
+                for (part, value) in parts.zip(values) {
+                    let test = match value {
+                        SQLValue::Null => null_test(&out).zip(strip_cast(part)),
+                        _ => None,
+                    };
+                    match test {
+                        Some(((len, test), rest)) => {
+                            out.truncate(len);
+                            out.push(' ');
+                            out.push_str(test);
+                            out.push_str(rest);
+                        }
+                        None => {
+                            out.push('?');
+                            out.push_str(part);
+                            kept.push(value);
+                        }
+                    }
+                }
+                Where::Simple {
+                    expr: out,
+                    values: kept,
+                    kind,
+                }
+            }
+        }
+    }
+
+    /// Builds `col op ? and col op ? ...` from a list of conditions, such
+    /// as a report definition loaded from config. Column names must be
+    /// plain, optionally qualified, identifiers.
+    ///
+    /// An empty list renders as `true`.
+    ///
+    /// Example:
+    /// ```
+    /// use composable_query_builder2::{Op, Select, Where};
+    /// let w = Where::from_conditions(vec![
+    ///     ("status_id".to_string(), Op::Eq, 2.into()),
+    ///     ("orders.total".to_string(), Op::Gte, 100.into()),
+    /// ])?;
+    /// let q = Select::from("orders").where_(w)?;
+    /// assert_eq!(
+    ///     "select * from orders where status_id = $1 and orders.total >= $2",
+    ///     q.into_builder().sql().trim()
+    /// );
+    /// # Ok::<(), composable_query_builder2::QueryError>(())
+    /// ```
+    pub fn from_conditions(conditions: Vec<(String, Op, SQLValue)>) -> QResult<Where> {
+        if conditions.is_empty() {
+            return Where::try_from("true");
+        }
+
+        let mut exprs = Vec::with_capacity(conditions.len());
+        let mut values = Vec::with_capacity(conditions.len());
+        for (col, op, value) in conditions {
+            validate_column(&col)?;
+            exprs.push(format!("{} {} ?", col, op.as_str()));
+            values.push(value);
+        }
+
+        Ok(Where::Simple {
+            expr: exprs.join(" and "),
+            values,
+            kind: BoolKind::And,
+        })
+    }
+}
+
+/// The length of `expr` without a trailing `=`, `!=` or `<>` operator, and
+/// the null test replacing it.
+fn null_test(expr: &str) -> Option<(usize, &'static str)> {
+    let trimmed = expr.trim_end();
+    let (rest, test) = if let Some(rest) = trimmed
+        .strip_suffix("!=")
+        .or_else(|| trimmed.strip_suffix("<>"))
+    {
+        (rest, "is not null")
+    } else {
+        let rest = trimmed.strip_suffix('=')?;
+        if rest.ends_with(['<', '>', '!', '=']) {
+            return None;
+        }
+        (rest, "is null")
+    };
+    Some((rest.trim_end().len(), test))
+}
+
+/// `part` without a leading `::type` cast, or `None` if the type can't be
+/// told apart from what follows it.
+fn strip_cast(part: &str) -> Option<&str> {
+    match part.strip_prefix("::") {
+        Some(rest) => cast_len(rest).map(|len| &rest[len..]),
+        None => Some(part),
+    }
+}
+
+/// The words that may follow the first word of a type name, e.g.
+/// `double precision` or `timestamp with time zone`.
+const TYPE_SUFFIXES: &[&str] = &[
+    "precision",
+    "varying",
+    "with time zone",
+    "without time zone",
+];
+
+/// The length of the type name at the start of `s`, following the grammar
+/// of [validate_type].
+fn cast_len(s: &str) -> Option<usize> {
+    fn word_len(s: &str) -> usize {
+        s.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(s.len())
+    }
+    // The length of `(n, ...)` or `[]` after optional whitespace.
+    fn bracketed_len(s: &str, open: &str, close: char) -> usize {
+        let t = s.trim_start();
+        match t.strip_prefix(open).and_then(|r| r.find(close)) {
+            Some(i) => s.len() - t.len() + open.len() + i + 1,
+            None => 0,
+        }
+    }
+
+    let mut len = word_len(s);
+    if len == 0 {
+        return None;
+    }
+    len += bracketed_len(&s[len..], "(", ')');
+    let rest = &s[len..];
+    let t = rest.trim_start();
+    for suffix in TYPE_SUFFIXES {
+        let after = t.get(suffix.len()..);
+        if t.len() < rest.len()
+            && t.get(..suffix.len())
+                .is_some_and(|w| w.eq_ignore_ascii_case(suffix))
+            && after.is_some_and(|a| word_len(a) == 0)
+        {
+            len += rest.len() - t.len() + suffix.len();
+            len += bracketed_len(&s[len..], "(", ')');
+            break;
+        }
+    }
+    loop {
+        let array = bracketed_len(&s[len..], "[", ']');
+        if array == 0 {
+            break;
+        }
+        len += array;
+    }
+    validate_type(&s[..len]).is_ok().then_some(len)
+}
+
+/// Renders a list of clauses joined by each clause's [BoolKind], leaving a
+/// trailing space.
+pub(crate) fn push_where_list(clauses: &[Where], q: &mut String, vals: &mut Vec<SQLValue>) {
+    let last_index = clauses.len() - 1;
+    for (index, clause) in clauses.iter().enumerate() {
+        match clause {
+            Where::Simple {
+                expr,
+                values,
+                kind: _,
+            } => {
+                q.push_str(expr);
+                vals.extend(values.clone());
+                if index != last_index {
+                    // Get the next kind
+                    let next_kind = clauses.get(index + 1).unwrap().get_kind();
+                    q.push(' ');
+                    q.push_str(next_kind.as_str());
+                    q.push(' ');
+                } else {
+                    q.push(' ');
+                }
+            }
+        }
+    }
 }
 
 impl TryFrom<&str> for Where {
@@ -148,27 +418,28 @@ impl TryFrom<String> for Where {
     }
 }
 
-impl<S, V> TryFrom<(S, V)> for Where
-where
-    S: Into<String>,
-    V: IntoWhere,
-{
+/// Binds one value per `?` placeholder, for predicates whose number of
+/// values is only known at runtime.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{Select, SQLValue, Where};
+/// let tags = ["rust", "sql"];
+/// let expr = vec!["tags @> array[?]"; tags.len()].join(" or ");
+/// let values: Vec<SQLValue> = tags.iter().map(|t| (*t).into()).collect();
+/// let q = Select::from("posts").where_((expr, values))?;
+/// assert_eq!(
+///     "select * from posts where tags @> array[$1] or tags @> array[$2] ",
+///     q.into_builder().sql()
+/// );
+/// # Ok::<(), composable_query_builder2::QueryError>(())
+/// ```
+impl<S: Into<String>> TryFrom<(S, Vec<SQLValue>)> for Where {
     type Error = QueryError;
 
-    fn try_from((input_expr, v1): (S, V)) -> Result<Self, Self::Error> {
-        let input_expr: String = input_expr.into();
-        placeholder_count(&input_expr, 1)?;
-
-        let mut expr = String::new();
-        let mut values = Vec::with_capacity(1);
-
-        let mut parts = input_expr.split("?");
-        expr.push_str(parts.next().unwrap());
-        v1.into_where(&mut expr, &mut values)?;
-        if let Some(part) = parts.next() {
-            expr.push_str(part);
-        }
-        assert!(parts.next().is_none());
+    fn try_from((expr, values): (S, Vec<SQLValue>)) -> Result<Self, Self::Error> {
+        let expr: String = expr.into();
+        placeholder_count(&expr, values.len())?;
 
         Ok(Where::Simple {
             expr,
@@ -178,91 +449,51 @@ where
     }
 }
 
-impl<S, V1, V2> TryFrom<(S, V1, V2)> for Where
-where
-    S: Into<String>,
-    V1: IntoWhere,
-    V2: IntoWhere,
-{
-    type Error = QueryError;
-
-    fn try_from((expr, v1, v2): (S, V1, V2)) -> Result<Self, Self::Error> {
-        let input_expr: String = expr.into();
-        placeholder_count(&input_expr, 2)?;
-
-        let mut expr = String::new();
-        let mut values = Vec::with_capacity(2);
-
-        let mut parts = input_expr.split("?");
-        expr.push_str(parts.next().unwrap());
-        v1.into_where(&mut expr, &mut values)?;
-        expr.push_str(parts.next().unwrap());
-        v2.into_where(&mut expr, &mut values)?;
-        if let Some(part) = parts.next() {
-            expr.push_str(part);
+/// Implements `TryFrom<(S, V1, ..)>` for an expression with one `?`
+/// placeholder per value. Each value is spliced in with [IntoWhere].
+macro_rules! where_tuple {
+    ($n:literal; $($v:ident: $V:ident),+) => {
+        impl<S, $($V),+> TryFrom<(S, $($V),+)> for Where
+        where
+            S: Into<String>,
+            $($V: IntoWhere,)+
+        {
+            type Error = QueryError;
+
+            fn try_from((input_expr, $($v),+): (S, $($V),+)) -> Result<Self, Self::Error> {
+                let input_expr: String = input_expr.into();
+                placeholder_count(&input_expr, $n)?;
+
+                let mut expr = String::new();
+                let mut values = Vec::with_capacity($n);
+
+                let mut parts = input_expr.split("?");
+                $(
+                    expr.push_str(parts.next().unwrap());
+                    $v.into_where(&mut expr, &mut values)?;
+                )+
+                expr.push_str(parts.next().unwrap());
+                assert!(parts.next().is_none());
+
+                Ok(Where::Simple {
+                    expr,
+                    values,
+                    kind: BoolKind::And,
+                })
+            }
         }
-        assert!(parts.next().is_none());
-
-        Ok(Where::Simple {
-            expr,
-            values,
-            kind: BoolKind::And,
-        })
-    }
+    };
 }
 
-// impl<S, V1, V2> TryFrom<(S, V1, V2)> for Where
-// where
-//     S: Into<String>,
-//     V1: Into<SQLValue>,
-//     V2: Into<SQLValue>,
-// {
-//     type Error = QueryError;
-//
-//     fn try_from(value: (S, V1, V2)) -> Result<Self, Self::Error> {
-//         let expr: String = value.0.into();
-//         placeholder_count(&expr, 2)?;
-//
-//         Ok(Where::Simple {
-//             expr,
-//             values: vec![value.1.into(), value.2.into()],
-//             kind: BoolKind::And,
-//         })
-//     }
-// }
-
-impl<S, V1, V2, V3> TryFrom<(S, V1, V2, V3)> for Where
-where
-    S: Into<String>,
-    V1: IntoWhere,
-    V2: IntoWhere,
-    V3: IntoWhere,
-{
-    type Error = QueryError;
-
-    fn try_from((input_expr, v1, v2, v3): (S, V1, V2, V3)) -> Result<Self, Self::Error> {
-        let input_expr: String = input_expr.into();
-        placeholder_count(&input_expr, 3)?;
-
-        let mut expr = String::new();
-        let mut values = Vec::with_capacity(3);
-
-        let mut parts = input_expr.split("?");
-        expr.push_str(parts.next().unwrap());
-        v1.into_where(&mut expr, &mut values)?;
-        expr.push_str(parts.next().unwrap());
-        v2.into_where(&mut expr, &mut values)?;
-        expr.push_str(parts.next().unwrap());
-        v3.into_where(&mut expr, &mut values)?;
-        if let Some(part) = parts.next() {
-            expr.push_str(part);
-        }
-        assert!(parts.next().is_none());
-
-        Ok(Where::Simple {
-            expr,
-            values,
-            kind: BoolKind::And,
-        })
-    }
-}
+where_tuple!(1; v1: V1);
+where_tuple!(2; v1: V1, v2: V2);
+where_tuple!(3; v1: V1, v2: V2, v3: V3);
+where_tuple!(4; v1: V1, v2: V2, v3: V3, v4: V4);
+where_tuple!(5; v1: V1, v2: V2, v3: V3, v4: V4, v5: V5);
+where_tuple!(6; v1: V1, v2: V2, v3: V3, v4: V4, v5: V5, v6: V6);
+where_tuple!(7; v1: V1, v2: V2, v3: V3, v4: V4, v5: V5, v6: V6, v7: V7);
+where_tuple!(8; v1: V1, v2: V2, v3: V3, v4: V4, v5: V5, v6: V6, v7: V7, v8: V8);
+where_tuple!(9; v1: V1, v2: V2, v3: V3, v4: V4, v5: V5, v6: V6, v7: V7, v8: V8, v9: V9);
+where_tuple!(10; v1: V1, v2: V2, v3: V3, v4: V4, v5: V5, v6: V6, v7: V7, v8: V8, v9: V9, v10: V10);
+where_tuple!(11; v1: V1, v2: V2, v3: V3, v4: V4, v5: V5, v6: V6, v7: V7, v8: V8, v9: V9, v10: V10, v11: V11);
+where_tuple!(12; v1: V1, v2: V2, v3: V3, v4: V4, v5: V5, v6: V6, v7: V7, v8: V8, v9: V9, v10: V10, v11: V11, v12: V12);
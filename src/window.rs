@@ -0,0 +1,59 @@
+use crate::group_by::IntoGroupBy;
+use crate::order::OrderDir;
+use itertools::Itertools;
+
+/// The window definition used inside `over (...)`.
+///
+/// Example:
+/// ```
+/// use composable_query_builder2::{OrderDir, Partition};
+/// let w = Partition::by("user_id").order_by("created_at", OrderDir::Desc);
+/// assert_eq!("partition by user_id order by created_at desc", w.to_sql());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Partition {
+    partition_by: Option<String>,
+    order_by: Vec<(String, OrderDir)>,
+}
+
+impl Partition {
+    /// An empty window, rendering `over ()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn by(cols: impl IntoGroupBy) -> Self {
+        Self {
+            partition_by: Some(cols.into_group_by()),
+            order_by: vec![],
+        }
+    }
+
+    /// Repeated calls add secondary sort keys.
+    pub fn order_by(mut self, col: impl Into<String>, dir: OrderDir) -> Self {
+        self.order_by.push((col.into(), dir));
+        self
+    }
+
+    pub fn to_sql(&self) -> String {
+        let mut s = String::new();
+        if let Some(partition_by) = &self.partition_by {
+            s.push_str("partition by ");
+            s.push_str(partition_by);
+        }
+        if !self.order_by.is_empty() {
+            if !s.is_empty() {
+                s.push(' ');
+            }
+            s.push_str("order by ");
+            s.push_str(
+                &self
+                    .order_by
+                    .iter()
+                    .map(|(col, dir)| format!("{} {}", col, dir))
+                    .join(", "),
+            );
+        }
+        s
+    }
+}